@@ -0,0 +1,114 @@
+//! Reviewing a finished game with the AI.
+//!
+//! Each position of the game is searched, and the move played is compared with the move the AI prefers. Positions
+//! with only one legal move are not searched.
+
+use serde::Serialize;
+
+use crate::game::{AiPlayer, GameState, MoveHistory, PieceColor, Rules};
+
+/// The AI's view of one move of a game.
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveAnalysis {
+    /// Position of the move in the game, starting at 1
+    pub move_number: usize,
+    pub color: PieceColor,
+    /// The move played, in algebraic notation
+    pub played: String,
+    /// The move the AI prefers, or None if the move was forced
+    pub best_move: Option<String>,
+    pub legal_moves: usize,
+    /// Nodes searched to find the AI's move
+    pub nodes: u64,
+}
+
+impl MoveAnalysis {
+    /// True if the move played is the one the AI prefers, or the only legal move.
+    pub fn agrees(&self) -> bool {
+        self.best_move.as_ref().is_none_or(|best| *best == self.played)
+    }
+}
+
+/// Searches every position of a game to the given depth. Fails if the moves are not legal.
+pub fn analyze_game(board_size: usize, history: &MoveHistory, depth: i32) -> Result<Vec<MoveAnalysis>, String> {
+    let positions = Rules::replay(board_size, history)?;
+    let black = AiPlayer::new(PieceColor::Black, depth);
+    let white = AiPlayer::new(PieceColor::White, depth);
+
+    Ok(history
+        .iter()
+        .zip(&positions)
+        .enumerate()
+        .map(|(index, (record, state))| {
+            let ai = match state.current_player() {
+                PieceColor::Black => &black,
+                PieceColor::White => &white,
+            };
+            analyze_move(ai, state, index + 1, record.to_algebraic())
+        })
+        .collect())
+}
+
+fn analyze_move(ai: &AiPlayer, state: &GameState, move_number: usize, played: String) -> MoveAnalysis {
+    let legal_moves = Rules::legal_moves(state).len();
+    let (best_move, nodes) = if legal_moves > 1 {
+        let (_, report) = ai.search(state);
+        (report.best_move, report.nodes)
+    } else {
+        (None, 0)
+    };
+    MoveAnalysis {
+        move_number,
+        color: state.current_player(),
+        played,
+        best_move,
+        legal_moves,
+        nodes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::ai::play_out;
+
+    #[test]
+    fn analyzes_every_move() {
+        let black = AiPlayer::new(PieceColor::Black, 1);
+        let white = AiPlayer::new(PieceColor::White, 1);
+        let (_, history) = play_out(GameState::new(4, PieceColor::Black), &black, &white).unwrap();
+
+        let analysis = analyze_game(4, &history, 1).unwrap();
+
+        assert_eq!(analysis.len(), history.len());
+        assert_eq!(analysis[0].move_number, 1);
+        assert_eq!(analysis[0].color, PieceColor::Black);
+        assert_eq!(analysis[0].played, history[0].to_algebraic());
+        assert_eq!(analysis[0].legal_moves, 4);
+        assert!(analysis[0].best_move.is_some());
+    }
+
+    #[test]
+    fn a_forced_move_always_agrees() {
+        let analysis = MoveAnalysis {
+            move_number: 5,
+            color: PieceColor::White,
+            played: "a2-c2".to_string(),
+            best_move: None,
+            legal_moves: 1,
+            nodes: 0,
+        };
+
+        assert!(analysis.agrees());
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let black = AiPlayer::new(PieceColor::Black, 1);
+        let white = AiPlayer::new(PieceColor::White, 1);
+        let (_, mut history) = play_out(GameState::new(4, PieceColor::Black), &black, &white).unwrap();
+        history.swap(0, 1);
+
+        assert!(analyze_game(4, &history, 1).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! Animated replays of whole games, one frame per move, for sharing games as images.
+//!
+//! Frames are board diagrams: the first shows the starting position and each later one the position after a move,
+//! with the jump path and captured stones marked. Replays are written as APNG, or as GIF with the `gif` feature.
+
+use std::time::Duration;
+
+use crate::diagram::Diagram;
+use crate::game::{GameState, MoveHistory, MoveRecord, PieceColor, Rules};
+
+/// Turns a game into an animation. Created with `new` and configured with the `with_*` methods.
+pub struct Replay<'a> {
+    board_size: usize,
+    history: &'a MoveHistory,
+    frame_delay: Duration,
+    final_delay: Duration,
+    cell_size: f32,
+    coordinates: bool,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(board_size: usize, history: &'a MoveHistory) -> Self {
+        Self {
+            board_size,
+            history,
+            frame_delay: Duration::from_millis(1000),
+            final_delay: Duration::from_millis(3000),
+            cell_size: 48.0,
+            coordinates: true,
+        }
+    }
+
+    /// Sets how long each position is shown.
+    pub fn with_frame_delay(mut self, delay: Duration) -> Self {
+        self.frame_delay = delay;
+        self
+    }
+
+    /// Sets how long the final position is shown before the animation starts again.
+    pub fn with_final_delay(mut self, delay: Duration) -> Self {
+        self.final_delay = delay;
+        self
+    }
+
+    /// Sets the width of a square in pixels.
+    pub fn with_cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Shows or hides the file letters and rank numbers.
+    pub fn with_coordinates(mut self, coordinates: bool) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Writes the replay as an animated PNG that loops forever.
+    pub fn to_apng(&self) -> Result<Vec<u8>, String> {
+        let frames = self.frames()?;
+        let (width, height) = self.frame_size();
+        let error = |err: png::EncodingError| format!("Failed to encode APNG: {}", err);
+
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames.len() as u32, 0).map_err(error)?;
+        let mut writer = encoder.write_header().map_err(error)?;
+        for (frame, delay) in frames.iter().zip(self.delays(frames.len())) {
+            writer.set_frame_delay(millis(delay), 1000).map_err(error)?;
+            writer.write_image_data(&self.render(frame)?).map_err(error)?;
+        }
+        writer.finish().map_err(error)?;
+        Ok(bytes)
+    }
+
+    /// Writes the replay as an animated GIF that loops forever.
+    #[cfg(feature = "gif")]
+    pub fn to_gif(&self) -> Result<Vec<u8>, String> {
+        let frames = self.frames()?;
+        let (width, height) = self.frame_size();
+        let (width, height) = (
+            u16::try_from(width).map_err(|_| "Image too large for GIF".to_string())?,
+            u16::try_from(height).map_err(|_| "Image too large for GIF".to_string())?,
+        );
+        let error = |err: gif::EncodingError| format!("Failed to encode GIF: {}", err);
+
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut bytes, width, height, &[]).map_err(error)?;
+            encoder.set_repeat(gif::Repeat::Infinite).map_err(error)?;
+            for (frame, delay) in frames.iter().zip(self.delays(frames.len())) {
+                let mut pixels = self.render(frame)?;
+                let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+                // GIF delays are in hundredths of a second
+                gif_frame.delay = millis(delay) / 10;
+                encoder.write_frame(&gif_frame).map_err(error)?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn frame_size(&self) -> (u32, u32) {
+        Diagram::new(&GameState::new(self.board_size, PieceColor::Black))
+            .with_cell_size(self.cell_size)
+            .with_coordinates(self.coordinates)
+            .size()
+    }
+
+    fn render(&self, (state, last_move): &(GameState, Option<&MoveRecord>)) -> Result<Vec<u8>, String> {
+        let mut diagram = Diagram::new(state)
+            .with_cell_size(self.cell_size)
+            .with_coordinates(self.coordinates);
+        if let Some(record) = last_move {
+            diagram = diagram.with_last_move(record);
+        }
+        diagram.to_rgba()
+    }
+
+    /// Replays the game, returning the starting position and the position after each move with the move that led
+    /// to it.
+    fn frames(&self) -> Result<Vec<(GameState, Option<&'a MoveRecord>)>, String> {
+        let positions = Rules::replay(self.board_size, self.history)?;
+        let moves = std::iter::once(None).chain(self.history.iter().map(Some));
+        Ok(positions.into_iter().zip(moves).collect())
+    }
+
+    fn delays(&self, frames: usize) -> impl Iterator<Item = Duration> {
+        (0..frames).map(move |i| if i + 1 == frames { self.final_delay } else { self.frame_delay })
+    }
+}
+
+fn millis(delay: Duration) -> u16 {
+    u16::try_from(delay.as_millis()).unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Position;
+
+    /// The 4x4 opening b2, c2, then Black's b4-b2.
+    fn short_game() -> MoveHistory {
+        let mut state = GameState::new(4, PieceColor::Black);
+        let mut history = MoveHistory::new();
+        history.push(Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap());
+        history.push(Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap());
+        let jump = Rules::all_valid_jumps(&state).remove(0);
+        history.push(Rules::apply_jump(&mut state, &jump));
+        history
+    }
+
+    /// Counts the PNG chunks of a type.
+    fn chunk_count(bytes: &[u8], chunk_type: &[u8; 4]) -> usize {
+        let mut offset = 8;
+        let mut count = 0;
+        while offset + 8 <= bytes.len() {
+            let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+            if &bytes[offset + 4..offset + 8] == chunk_type {
+                count += 1;
+            }
+            offset += length + 12;
+        }
+        count
+    }
+
+    mod frames {
+        use super::*;
+
+        #[test]
+        fn one_frame_per_move_after_the_start() {
+            let history = short_game();
+
+            let frames = Replay::new(4, &history).frames().unwrap();
+
+            assert_eq!(frames.len(), 4);
+            assert!(frames[0].1.is_none());
+            assert_eq!(frames[3].1, Some(&history[2]));
+        }
+
+        #[test]
+        fn frames_show_the_position_after_each_move() {
+            let history = short_game();
+
+            let frames = Replay::new(4, &history).frames().unwrap();
+
+            assert!(frames[0].0.board().get_piece_color(Position::new(1, 1)).is_some());
+            assert!(frames[1].0.board().is_empty(Position::new(1, 1)));
+            assert!(frames[3].0.board().is_empty(Position::new(2, 1)));
+        }
+
+        #[test]
+        fn rejects_illegal_moves() {
+            let mut history = short_game();
+            history.swap(0, 1);
+
+            let err = Replay::new(4, &history).frames().unwrap_err();
+
+            assert!(err.starts_with("Move 1:"));
+        }
+
+        #[test]
+        fn holds_the_final_frame() {
+            let history = short_game();
+            let replay = Replay::new(4, &history)
+                .with_frame_delay(Duration::from_millis(200))
+                .with_final_delay(Duration::from_millis(900));
+
+            let delays: Vec<u128> = replay.delays(3).map(|delay| delay.as_millis()).collect();
+
+            assert_eq!(delays, [200, 200, 900]);
+        }
+    }
+
+    mod apng {
+        use super::*;
+
+        #[test]
+        fn writes_one_animation_frame_per_position() {
+            let history = short_game();
+
+            let bytes = Replay::new(4, &history).with_cell_size(16.0).to_apng().unwrap();
+
+            assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+            assert_eq!(chunk_count(&bytes, b"acTL"), 1);
+            assert_eq!(chunk_count(&bytes, b"fcTL"), 4);
+        }
+    }
+
+    #[cfg(feature = "gif")]
+    mod gif_output {
+        use super::*;
+
+        #[test]
+        fn writes_a_looping_gif() {
+            let history = short_game();
+
+            let bytes = Replay::new(4, &history).with_cell_size(16.0).to_gif().unwrap();
+
+            assert_eq!(&bytes[..6], b"GIF89a");
+            assert!(bytes.windows(11).any(|window| window == b"NETSCAPE2.0"));
+        }
+    }
+}
@@ -0,0 +1,698 @@
+//! An HTTP server answering questions about positions and games with JSON, so that web tools can use the rules and
+//! the AI without linking to this crate.
+//!
+//! Every endpoint takes a JSON body by `POST`:
+//!
+//! - `/legal-moves`: a position. Replies with the position and its legal moves.
+//! - `/apply`: a position and a `move` in algebraic notation. Replies with the move as played and the position after
+//!   it.
+//! - `/bestmove`: a position and optionally `depth` and `movetime_ms`. Replies with the AI's move and its search
+//!   report. No search runs longer than the server's maximum move time, whatever the request asks for.
+//! - `/validate`: a game in any import format. Replies with whether the game is legal and, if not, why.
+//!
+//! A position is either `moves` played from the start, such as `{"board_size": 6, "moves": ["c3", "d3"]}`, or a
+//! `board` with one character per square row by row from row 1 (`b`, `w` or `.`) and the color `to_move`. Errors are
+//! replied with a 4xx status and `{"error": "..."}`.
+//!
+//! The server answers a limited number of connections and searches at once, and replies 503 to the rest. Browsers
+//! may call it from pages on other origins only if it is given an allowed origin.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::engine::UNLIMITED_DEPTH;
+use crate::game::player::PlayerMove;
+use crate::game::{AiPlayer, Board, GamePhase, GameState, MoveRecord, PieceColor, Rules};
+use crate::import;
+
+/// Largest request body accepted, far more than any game needs.
+const MAX_BODY: usize = 1 << 20;
+
+/// Time a client has to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest a `/bestmove` search runs unless set with `Api::with_max_move_time`.
+pub const DEFAULT_MAX_MOVE_TIME: Duration = Duration::from_secs(10);
+
+/// Searches run at once unless set with `Api::with_max_searches`.
+pub const DEFAULT_MAX_SEARCHES: usize = 4;
+
+/// Connections answered at once; more are refused with 503 rather than given a thread each.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Time allowed for writing a reply, including the 503 sent to a refused connection.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_board_size() -> usize {
+    8
+}
+
+/// A position given in a request.
+#[derive(Debug, Deserialize)]
+struct PositionRequest {
+    #[serde(default = "default_board_size")]
+    board_size: usize,
+    /// Moves from the start of the game in algebraic notation
+    #[serde(default)]
+    moves: Vec<String>,
+    /// The squares, instead of moves
+    board: Option<String>,
+    /// Whose turn it is on `board`
+    to_move: Option<PieceColor>,
+}
+
+impl PositionRequest {
+    fn to_state(&self) -> Result<GameState, String> {
+        match (&self.board, self.to_move) {
+            (None, _) => Ok(import::import_move_list(self.board_size, &self.moves.join(" "))?.0),
+            (Some(_), _) if !self.moves.is_empty() => Err("Give either board or moves, not both".to_string()),
+            (Some(_), None) => Err("to_move is required with board".to_string()),
+            (Some(cells), Some(player)) => GameState::from_board(Board::from_cells(cells)?, player),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplyRequest {
+    #[serde(flatten)]
+    position: PositionRequest,
+    #[serde(rename = "move")]
+    player_move: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BestMoveRequest {
+    #[serde(flatten)]
+    position: PositionRequest,
+    depth: Option<i32>,
+    movetime_ms: Option<u64>,
+}
+
+/// A position in a reply.
+#[derive(Debug, Serialize)]
+struct PositionReply {
+    board_size: usize,
+    board: String,
+    to_move: PieceColor,
+    winner: Option<PieceColor>,
+    legal_moves: Vec<String>,
+}
+
+impl PositionReply {
+    fn new(state: &GameState) -> Self {
+        let winner = match state.current_phase() {
+            GamePhase::GameOver { winner } => Some(winner),
+            _ => None,
+        };
+        Self {
+            board_size: state.board().size(),
+            board: state.board().to_cells(),
+            to_move: state.current_player(),
+            winner,
+            legal_moves: Rules::legal_moves(state).iter().map(PlayerMove::to_algebraic).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ApplyReply {
+    record: MoveRecord,
+    position: PositionReply,
+}
+
+/// An HTTP response: the status code and the JSON body.
+pub type Response = (u16, String);
+
+fn error(status: u16, message: impl Into<String>) -> Response {
+    (status, json!({ "error": message.into() }).to_string())
+}
+
+fn reply<T: Serialize>(value: &T) -> Response {
+    match serde_json::to_string(value) {
+        Ok(body) => (200, body),
+        Err(err) => error(500, format!("Failed to serialize reply: {}", err)),
+    }
+}
+
+fn parse<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, Response> {
+    serde_json::from_str(body).map_err(|err| error(400, format!("Invalid request: {}", err)))
+}
+
+/// A number of things that may be in use at once, such as searches.
+struct Limit {
+    max: usize,
+    used: AtomicUsize,
+}
+
+/// One use of a `Limit`, given back when dropped.
+struct Permit(Arc<Limit>);
+
+impl Limit {
+    fn new(max: usize) -> Arc<Self> {
+        Arc::new(Self {
+            max,
+            used: AtomicUsize::new(0),
+        })
+    }
+
+    /// Takes a use, or returns `None` if all are taken.
+    fn try_take(self: &Arc<Self>) -> Option<Permit> {
+        self.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| (used < self.max).then_some(used + 1))
+            .ok()
+            .map(|_| Permit(self.clone()))
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.0.used.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn busy() -> Response {
+    error(503, "The server is busy; try again later")
+}
+
+/// Answers requests. It is separate from the server so that requests can be answered without a connection.
+pub struct Api {
+    make_ai: Box<dyn Fn() -> AiPlayer + Send + Sync>,
+    default_depth: i32,
+    /// Longest any search runs, so that a client can't keep the server's CPUs busy
+    max_move_time: Duration,
+    /// Searches running at once; a `/bestmove` request beyond the limit is refused
+    searches: Arc<Limit>,
+}
+
+impl Api {
+    /// `make_ai` creates the AI for each `/bestmove` request, which searches to `default_depth` unless the request
+    /// gives a depth or a time.
+    pub fn new<F>(make_ai: F, default_depth: i32) -> Self
+    where
+        F: Fn() -> AiPlayer + Send + Sync + 'static,
+    {
+        Self {
+            make_ai: Box::new(make_ai),
+            default_depth,
+            max_move_time: DEFAULT_MAX_MOVE_TIME,
+            searches: Limit::new(DEFAULT_MAX_SEARCHES),
+        }
+    }
+
+    /// Limits every `/bestmove` search to the given time. A request's `movetime_ms` is reduced to it.
+    pub fn with_max_move_time(mut self, max_move_time: Duration) -> Self {
+        self.max_move_time = max_move_time;
+        self
+    }
+
+    /// Limits how many `/bestmove` searches run at once. Requests beyond the limit are replied with 503.
+    pub fn with_max_searches(mut self, max_searches: usize) -> Self {
+        self.searches = Limit::new(max_searches);
+        self
+    }
+
+    /// Answers one request.
+    pub fn handle(&self, method: &str, path: &str, body: &str) -> Response {
+        let endpoint = match path {
+            "/legal-moves" | "/apply" | "/bestmove" | "/validate" => path,
+            _ => return error(404, format!("No endpoint {}", path)),
+        };
+        if method != "POST" {
+            return error(405, format!("{} takes POST", endpoint));
+        }
+        let result = match endpoint {
+            "/legal-moves" => self.legal_moves(body),
+            "/apply" => self.apply(body),
+            "/bestmove" => self.best_move(body),
+            _ => Ok(self.validate(body)),
+        };
+        result.unwrap_or_else(|response| response)
+    }
+
+    fn legal_moves(&self, body: &str) -> Result<Response, Response> {
+        let request: PositionRequest = parse(body)?;
+        let state = request.to_state().map_err(|err| error(400, err))?;
+        Ok(reply(&PositionReply::new(&state)))
+    }
+
+    fn apply(&self, body: &str) -> Result<Response, Response> {
+        let request: ApplyRequest = parse(body)?;
+        let mut state = request.position.to_state().map_err(|err| error(400, err))?;
+        let record = PlayerMove::from_algebraic(&state, &request.player_move)
+            .and_then(|player_move| player_move.apply(&mut state).map_err(str::to_string))
+            .map_err(|err| error(422, format!("{}: {}", request.player_move, err)))?;
+        Ok(reply(&ApplyReply {
+            record,
+            position: PositionReply::new(&state),
+        }))
+    }
+
+    fn best_move(&self, body: &str) -> Result<Response, Response> {
+        let request: BestMoveRequest = parse(body)?;
+        let state = request.position.to_state().map_err(|err| error(400, err))?;
+        if matches!(state.current_phase(), GamePhase::GameOver { .. }) {
+            return Err(error(422, "The game is over"));
+        }
+        let depth = match (request.depth, request.movetime_ms) {
+            (Some(depth), _) if !(1..=UNLIMITED_DEPTH).contains(&depth) => {
+                return Err(error(400, format!("depth must be between 1 and {}", UNLIMITED_DEPTH)));
+            }
+            (Some(depth), _) => depth,
+            (None, Some(_)) => UNLIMITED_DEPTH,
+            (None, None) => self.default_depth,
+        };
+
+        let move_time = request
+            .movetime_ms
+            .map_or(self.max_move_time, |movetime| Duration::from_millis(movetime).min(self.max_move_time));
+        let _search = self.searches.try_take().ok_or_else(busy)?;
+        let (best_move, report) = (self.make_ai)().search_with_time_limit(&state, depth, move_time);
+        Ok(reply(&json!({
+            "best_move": best_move.map(|best_move| best_move.to_algebraic()),
+            "report": report,
+        })))
+    }
+
+    fn validate(&self, body: &str) -> Response {
+        match import::import_game_from_content(body) {
+            Ok((state, history, _)) => {
+                let winner = match state.current_phase() {
+                    GamePhase::GameOver { winner } => Some(winner),
+                    _ => None,
+                };
+                reply(&json!({
+                    "valid": true,
+                    "board_size": state.board().size(),
+                    "moves": history.len(),
+                    "winner": winner,
+                }))
+            }
+            Err(err) => reply(&json!({ "valid": false, "error": err })),
+        }
+    }
+}
+
+/// Serves the API over HTTP, one thread per connection up to a limit.
+pub struct ApiServer {
+    listener: TcpListener,
+    api: Arc<Api>,
+    /// Origin sent in `Access-Control-Allow-Origin`, if pages on other origins may call the API
+    allowed_origin: Option<Arc<str>>,
+    connections: Arc<Limit>,
+}
+
+impl ApiServer {
+    /// Listens on the address, such as "127.0.0.1:8080". Port 0 picks a free port.
+    pub fn bind(address: &str, api: Api) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|err| format!("Failed to listen on {}: {}", address, err))?;
+        Ok(Self {
+            listener,
+            api: Arc::new(api),
+            allowed_origin: None,
+            connections: Limit::new(MAX_CONNECTIONS),
+        })
+    }
+
+    /// Lets browsers call the API from pages on `origin`, such as "https://example.com", or from any origin with
+    /// "*". Without it, no CORS headers are sent.
+    pub fn with_allowed_origin(mut self, origin: &str) -> Self {
+        self.allowed_origin = Some(origin.into());
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.listener.local_addr().map_err(|err| err.to_string())
+    }
+
+    /// Answers requests until the process ends.
+    pub fn run(self) {
+        for mut stream in self.listener.incoming().flatten() {
+            let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+            let origin = self.allowed_origin.clone();
+            let Some(permit) = self.connections.try_take() else {
+                let _ = write_response(&mut stream, busy(), origin.as_deref());
+                continue;
+            };
+            let api = self.api.clone();
+            thread::spawn(move || {
+                serve_connection(stream, &api, origin.as_deref());
+                drop(permit);
+            });
+        }
+    }
+
+    /// Runs the server on a background thread.
+    pub fn spawn(self) -> thread::JoinHandle<()> {
+        thread::spawn(move || self.run())
+    }
+}
+
+/// An HTTP request, as far as the API needs it.
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+/// Answers one request and closes the connection.
+fn serve_connection(stream: TcpStream, api: &Api, origin: Option<&str>) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let response = match read_request(&mut BufReader::new(stream)) {
+        // Browsers ask before sending JSON to another origin
+        Ok(request) if request.method == "OPTIONS" => (204, String::new()),
+        Ok(request) => api.handle(&request.method, &request.path, &request.body),
+        Err(response) => response,
+    };
+    let _ = write_response(&mut writer, response, origin);
+}
+
+fn read_request<R: BufRead>(reader: &mut R) -> Result<Request, Response> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|_| error(400, "Failed to read the request"))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(error(400, "Malformed request line"));
+    };
+    let method = method.to_string();
+    // The query, if any, is not used
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|_| error(400, "Failed to read the headers"))?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().map_err(|_| error(400, "Invalid Content-Length"))?;
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(error(413, format!("The body is larger than {} bytes", MAX_BODY)));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|_| error(400, "The body is shorter than its Content-Length"))?;
+    let body = String::from_utf8(body).map_err(|_| error(400, "The body is not UTF-8"))?;
+    Ok(Request { method, path, body })
+}
+
+fn write_response(stream: &mut TcpStream, (status, body): Response, origin: Option<&str>) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let cors = origin.map_or(String::new(), |origin| {
+        format!(
+            "Access-Control-Allow-Origin: {}\r\nAccess-Control-Allow-Methods: POST, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\nVary: Origin\r\n",
+            origin
+        )
+    });
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        cors,
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    fn api() -> Api {
+        Api::new(|| AiPlayer::new(PieceColor::Black, 2), 2)
+    }
+
+    /// Answers a request and parses the reply.
+    fn post(path: &str, body: serde_json::Value) -> (u16, serde_json::Value) {
+        let (status, reply) = api().handle("POST", path, &body.to_string());
+        (status, serde_json::from_str(&reply).unwrap())
+    }
+
+    mod positions {
+        use super::*;
+
+        #[test]
+        fn lists_the_legal_moves_after_a_move_list() {
+            let (status, reply) = post("/legal-moves", json!({ "board_size": 4, "moves": ["b2"] }));
+
+            assert_eq!(status, 200);
+            assert_eq!(reply["board"], "bwbww.wbbwbwwbwb");
+            assert_eq!(reply["to_move"], "White");
+            assert_eq!(reply["legal_moves"], json!(["b3", "b1", "a2", "c2"]));
+        }
+
+        #[test]
+        fn accepts_a_board() {
+            let body = json!({ "board": "bwbww.wbbwbwwbwb", "to_move": "White" });
+
+            let (status, reply) = post("/legal-moves", body);
+
+            assert_eq!(status, 200);
+            assert_eq!(reply["legal_moves"].as_array().unwrap().len(), 4);
+        }
+
+        #[test]
+        fn rejects_bad_positions() {
+            let (illegal, reply) = post("/legal-moves", json!({ "board_size": 4, "moves": ["a2"] }));
+            assert_eq!(illegal, 400);
+            assert!(reply["error"].as_str().unwrap().starts_with("Move 1 (a2)"), "{}", reply);
+
+            let (both, _) = post("/legal-moves", json!({ "board": "bwbww.wbbwbwwbwb", "to_move": "White", "moves": ["b2"] }));
+            assert_eq!(both, 400);
+            let (no_player, reply) = post("/legal-moves", json!({ "board": "bwbww.wbbwbwwbwb" }));
+            assert_eq!((no_player, reply["error"].as_str()), (400, Some("to_move is required with board")));
+        }
+    }
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn plays_a_move() {
+            let (status, reply) = post("/apply", json!({ "board_size": 4, "moves": ["b2"], "move": "c2" }));
+
+            assert_eq!(status, 200);
+            assert_eq!(reply["record"], json!({ "OpeningRemoval": { "color": "White", "position": { "row": 1, "col": 2 } } }));
+            assert_eq!(reply["position"]["board"], "bwbww..bbwbwwbwb");
+            assert_eq!(reply["position"]["to_move"], "Black");
+        }
+
+        #[test]
+        fn rejects_an_illegal_move() {
+            let (status, reply) = post("/apply", json!({ "board_size": 4, "moves": ["b2"], "move": "a1" }));
+
+            assert_eq!(status, 422);
+            assert_eq!(reply["error"], "a1: Invalid removal position for White");
+        }
+    }
+
+    mod best_move {
+        use super::*;
+
+        #[test]
+        fn searches_the_position() {
+            let (status, reply) = post("/bestmove", json!({ "board_size": 4, "moves": ["b2", "c2"], "depth": 1 }));
+
+            assert_eq!(status, 200);
+            assert_eq!(reply["report"]["depth_reached"], 1);
+            let best_move = reply["best_move"].as_str().unwrap();
+            let (_, legal) = post("/legal-moves", json!({ "board_size": 4, "moves": ["b2", "c2"] }));
+            assert!(legal["legal_moves"].as_array().unwrap().contains(&json!(best_move)));
+        }
+
+        #[test]
+        fn stops_at_the_time_limit() {
+            let (status, reply) = post("/bestmove", json!({ "moves": ["d4", "e4"], "movetime_ms": 50 }));
+
+            assert_eq!(status, 200);
+            assert!(reply["best_move"].is_string());
+            assert!(reply["report"]["depth_reached"].as_i64().unwrap() < UNLIMITED_DEPTH as i64);
+        }
+
+        #[test]
+        fn never_searches_longer_than_the_maximum() {
+            let api = api().with_max_move_time(Duration::from_millis(50));
+            let start = std::time::Instant::now();
+
+            for body in [json!({ "depth": UNLIMITED_DEPTH }), json!({ "movetime_ms": 3_600_000 })] {
+                let (status, reply) = api.handle("POST", "/bestmove", &body.to_string());
+                let reply: serde_json::Value = serde_json::from_str(&reply).unwrap();
+
+                assert_eq!(status, 200);
+                assert!(reply["best_move"].is_string());
+                assert!(reply["report"]["depth_reached"].as_i64().unwrap() < UNLIMITED_DEPTH as i64);
+            }
+            assert!(start.elapsed() < Duration::from_secs(10));
+        }
+
+        #[test]
+        fn refuses_searches_beyond_the_limit() {
+            let api = api().with_max_searches(1);
+            let _running = api.searches.try_take().unwrap();
+
+            let (status, _) = api.handle("POST", "/bestmove", &json!({ "board_size": 4, "depth": 1 }).to_string());
+
+            assert_eq!(status, 503);
+        }
+
+        #[test]
+        fn finished_searches_make_room() {
+            let api = api().with_max_searches(1);
+            let body = json!({ "board_size": 4, "depth": 1 }).to_string();
+
+            assert_eq!(api.handle("POST", "/bestmove", &body).0, 200);
+            assert_eq!(api.handle("POST", "/bestmove", &body).0, 200);
+        }
+
+        #[test]
+        fn rejects_a_bad_depth() {
+            let (status, _) = post("/bestmove", json!({ "depth": 0 }));
+
+            assert_eq!(status, 400);
+        }
+    }
+
+    mod validate {
+        use super::*;
+
+        #[test]
+        fn accepts_a_legal_game() {
+            let game = r#"{"format_version": 1, "board_size": 4, "total_moves": 2, "moves": [
+                {"OpeningRemoval": {"color": "Black", "position": {"row": 1, "col": 1}}},
+                {"OpeningRemoval": {"color": "White", "position": {"row": 1, "col": 2}}}]}"#;
+
+            let (status, reply) = api().handle("POST", "/validate", game);
+
+            assert_eq!(status, 200);
+            let reply: serde_json::Value = serde_json::from_str(&reply).unwrap();
+            assert_eq!(reply, json!({ "valid": true, "board_size": 4, "moves": 2, "winner": null }));
+        }
+
+        #[test]
+        fn explains_an_illegal_game() {
+            let game = r#"{"format_version": 1, "board_size": 4, "total_moves": 1, "moves": [
+                {"OpeningRemoval": {"color": "Black", "position": {"row": 0, "col": 1}}}]}"#;
+
+            let (status, reply) = api().handle("POST", "/validate", game);
+
+            assert_eq!(status, 200);
+            let reply: serde_json::Value = serde_json::from_str(&reply).unwrap();
+            assert_eq!(reply["valid"], false);
+            assert!(reply["error"].is_string());
+        }
+    }
+
+    mod routing {
+        use super::*;
+
+        #[test]
+        fn rejects_unknown_paths_and_methods() {
+            assert_eq!(api().handle("POST", "/nothing", "{}").0, 404);
+            assert_eq!(api().handle("GET", "/legal-moves", "").0, 405);
+            assert_eq!(api().handle("POST", "/legal-moves", "not json").0, 400);
+        }
+    }
+
+    mod server {
+        use super::*;
+
+        /// Sends a raw HTTP request to the server and returns the head and the body.
+        fn exchange(address: SocketAddr, request: &str) -> (String, String) {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            let (head, body) = response.split_once("\r\n\r\n").unwrap();
+            (head.to_string(), body.to_string())
+        }
+
+        /// Sends a raw HTTP request to the server and returns the status line and the body.
+        fn request(address: SocketAddr, request: &str) -> (String, String) {
+            let (head, body) = exchange(address, request);
+            (head.lines().next().unwrap().to_string(), body)
+        }
+
+        fn start_server(server: ApiServer) -> SocketAddr {
+            let address = server.local_addr().unwrap();
+            server.spawn();
+            address
+        }
+
+        fn start() -> SocketAddr {
+            start_server(ApiServer::bind("127.0.0.1:0", api()).unwrap())
+        }
+
+        #[test]
+        fn answers_over_http() {
+            let address = start();
+            let body = r#"{"board_size": 4, "moves": ["b2"]}"#;
+
+            let (status, reply) = request(
+                address,
+                &format!("POST /legal-moves HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", body.len(), body),
+            );
+
+            assert_eq!(status, "HTTP/1.1 200 OK");
+            let reply: serde_json::Value = serde_json::from_str(&reply).unwrap();
+            assert_eq!(reply["to_move"], "White");
+        }
+
+        #[test]
+        fn reports_errors_with_a_status() {
+            let address = start();
+
+            let (not_found, _) = request(address, "POST /nothing HTTP/1.1\r\nContent-Length: 0\r\n\r\n");
+            let (too_large, _) = request(address, "POST /validate HTTP/1.1\r\nContent-Length: 99999999\r\n\r\n");
+            let (preflight, _) = request(address, "OPTIONS /bestmove HTTP/1.1\r\n\r\n");
+
+            assert_eq!(not_found, "HTTP/1.1 404 Not Found");
+            assert_eq!(too_large, "HTTP/1.1 413 Payload Too Large");
+            assert_eq!(preflight, "HTTP/1.1 204 No Content");
+        }
+
+        #[test]
+        fn sends_cors_headers_only_for_an_allowed_origin() {
+            let preflight = "OPTIONS /bestmove HTTP/1.1\r\n\r\n";
+            let closed = start();
+            let open = start_server(
+                ApiServer::bind("127.0.0.1:0", api()).unwrap().with_allowed_origin("https://example.com"),
+            );
+
+            let (closed_head, _) = exchange(closed, preflight);
+            let (open_head, _) = exchange(open, preflight);
+
+            assert!(!closed_head.contains("Access-Control-Allow-Origin"), "{}", closed_head);
+            assert!(open_head.contains("Access-Control-Allow-Origin: https://example.com\r\n"), "{}", open_head);
+        }
+    }
+}
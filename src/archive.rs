@@ -0,0 +1,362 @@
+//! Files holding many games.
+//!
+//! An archive is either JSON Lines, with one game per line in the JSON import format, or concatenated PGN records.
+//! Games are read one at a time, so large archives are never held in memory. A game that fails to import is reported
+//! as an error for that game, and reading continues with the next one.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::Path;
+
+use crate::export;
+use crate::game::{GameState, MoveHistory, PieceColor};
+use crate::import::{import_game_from_content, import_pgn_game_from_content};
+use crate::pgn::PgnGame;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// One JSON game per line
+    JsonLines,
+    /// PGN records, one after another
+    Pgn,
+}
+
+impl ArchiveFormat {
+    /// Chooses the format from a file extension: `.jsonl` or `.pgn`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "jsonl" => Some(ArchiveFormat::JsonLines),
+            "pgn" => Some(ArchiveFormat::Pgn),
+            _ => None,
+        }
+    }
+}
+
+/// A game in an archive that could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveError {
+    /// Position of the game in the archive, starting at 1
+    pub game: usize,
+    /// Line of the archive that the game starts on
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Game {} (line {}): {}", self.game, self.line, self.message)
+    }
+}
+
+/// Reads the games in an archive one at a time.
+pub struct ArchiveReader<R: BufRead> {
+    lines: Lines<R>,
+    format: ArchiveFormat,
+    /// Number of lines read so far
+    line: usize,
+    /// Number of games returned so far
+    game: usize,
+    /// The first line of the next PGN record, read while looking for the end of the previous one
+    pending: Option<(usize, String)>,
+    /// Set after a read error, which ends the archive
+    failed: bool,
+}
+
+impl ArchiveReader<BufReader<File>> {
+    /// Opens an archive file. The format comes from the extension, or from the first character of the file if the
+    /// extension is not recognized.
+    pub fn open(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| format!("Failed to open archive: {}", err))?;
+        let mut reader = BufReader::new(file);
+        let format = match ArchiveFormat::from_path(Path::new(path)) {
+            Some(format) => format,
+            None => {
+                let buffer = reader.fill_buf().map_err(|err| format!("Failed to read archive: {}", err))?;
+                match buffer.iter().find(|byte| !byte.is_ascii_whitespace()) {
+                    Some(b'[') => ArchiveFormat::Pgn,
+                    _ => ArchiveFormat::JsonLines,
+                }
+            }
+        };
+        Ok(Self::new(reader, format))
+    }
+}
+
+impl<R: BufRead> ArchiveReader<R> {
+    pub fn new(reader: R, format: ArchiveFormat) -> Self {
+        Self {
+            lines: reader.lines(),
+            format,
+            line: 0,
+            game: 0,
+            pending: None,
+            failed: false,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<Result<(usize, String), ArchiveError>> {
+        if let Some(pending) = self.pending.take() {
+            return Some(Ok(pending));
+        }
+        let result = self.lines.next()?;
+        self.line += 1;
+        Some(result.map(|text| (self.line, text)).map_err(|err| ArchiveError {
+            game: self.game + 1,
+            line: self.line,
+            message: format!("Failed to read archive: {}", err),
+        }))
+    }
+
+    /// Returns the next game's text and the line it starts on.
+    fn next_record(&mut self) -> Option<Result<(usize, String), ArchiveError>> {
+        match self.format {
+            ArchiveFormat::JsonLines => loop {
+                match self.next_line()? {
+                    Ok((_, text)) if text.trim().is_empty() => continue,
+                    other => return Some(other),
+                }
+            },
+            ArchiveFormat::Pgn => {
+                let mut record = String::new();
+                let mut start = 0;
+                let mut in_movetext = false;
+                while let Some(next) = self.next_line() {
+                    let (line, text) = match next {
+                        Ok(next) => next,
+                        Err(err) => return Some(Err(err)),
+                    };
+                    let trimmed = text.trim_start();
+                    if trimmed.is_empty() && record.is_empty() {
+                        continue;
+                    }
+                    // A tag after the movetext starts the next record
+                    if in_movetext && trimmed.starts_with('[') {
+                        self.pending = Some((line, text));
+                        break;
+                    }
+                    if !trimmed.is_empty() && !trimmed.starts_with('[') {
+                        in_movetext = true;
+                    }
+                    if record.is_empty() {
+                        start = line;
+                    }
+                    record.push_str(&text);
+                    record.push('\n');
+                }
+                (!record.is_empty()).then_some(Ok((start, record)))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ArchiveReader<R> {
+    type Item = Result<(GameState, MoveHistory), ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let (line, record) = match self.next_record()? {
+            Ok(record) => record,
+            Err(err) => {
+                self.failed = true;
+                return Some(Err(err));
+            }
+        };
+        self.game += 1;
+
+        let imported = match self.format {
+            ArchiveFormat::JsonLines => import_game_from_content(&record),
+            ArchiveFormat::Pgn => import_pgn_game_from_content(&record),
+        };
+        Some(
+            imported
+                .map(|(state, history, _)| (state, history))
+                .map_err(|message| ArchiveError {
+                    game: self.game,
+                    line,
+                    message,
+                }),
+        )
+    }
+}
+
+/// Writes games to an archive.
+pub struct ArchiveWriter<W: Write> {
+    writer: W,
+    format: ArchiveFormat,
+    games: usize,
+}
+
+impl ArchiveWriter<BufWriter<File>> {
+    /// Creates an archive file, replacing any existing file.
+    pub fn create(path: &str, format: ArchiveFormat) -> Result<Self, String> {
+        let file = File::create(path).map_err(|err| format!("Failed to create archive: {}", err))?;
+        Ok(Self::new(BufWriter::new(file), format))
+    }
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    pub fn new(writer: W, format: ArchiveFormat) -> Self {
+        Self { writer, format, games: 0 }
+    }
+
+    /// Returns the number of games written.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Appends a game. The winner is None for an unfinished game.
+    pub fn write_game(&mut self, board_size: usize, history: &MoveHistory, winner: Option<PieceColor>) -> Result<(), String> {
+        match self.format {
+            ArchiveFormat::JsonLines => {
+                let line = export::game_to_json_line(board_size, winner, history)?;
+                writeln!(self.writer, "{}", line)
+            }
+            ArchiveFormat::Pgn => self.write_pgn_record(&PgnGame::new(board_size, history, winner)),
+        }
+        .map_err(|err| format!("Failed to write archive: {}", err))?;
+        self.games += 1;
+        Ok(())
+    }
+
+    /// Appends a PGN record with its tags and annotations. In a JSON Lines archive only the main line is kept.
+    pub fn write_pgn(&mut self, game: &PgnGame) -> Result<(), String> {
+        match self.format {
+            ArchiveFormat::JsonLines => self.write_game(game.board_size()?, &game.move_history(), game.result),
+            ArchiveFormat::Pgn => {
+                self.write_pgn_record(game).map_err(|err| format!("Failed to write archive: {}", err))?;
+                self.games += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn write_pgn_record(&mut self, game: &PgnGame) -> std::io::Result<()> {
+        if self.games > 0 {
+            writeln!(self.writer)?;
+        }
+        write!(self.writer, "{}", game.to_pgn())
+    }
+
+    /// Flushes buffered output and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, String> {
+        self.writer.flush().map_err(|err| format!("Failed to write archive: {}", err))?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GamePhase, Position, Rules};
+
+    /// Plays the first legal move until the game ends.
+    fn played_history(board_size: usize) -> (MoveHistory, PieceColor) {
+        let center = board_size / 2 - 1;
+        let mut state = GameState::new(board_size, PieceColor::Black);
+        let mut history = vec![
+            Rules::apply_opening_removal(&mut state, Position::new(center, center)).unwrap(),
+            Rules::apply_opening_removal(&mut state, Position::new(center, center + 1)).unwrap(),
+        ];
+        while matches!(state.current_phase(), GamePhase::Play) {
+            let jump = Rules::all_valid_jumps(&state).remove(0);
+            history.push(Rules::apply_jump(&mut state, &jump));
+        }
+        let GamePhase::GameOver { winner } = state.current_phase() else {
+            unreachable!();
+        };
+        (history, winner)
+    }
+
+    fn write_archive(format: ArchiveFormat, sizes: &[usize]) -> String {
+        let mut writer = ArchiveWriter::new(Vec::new(), format);
+        for &size in sizes {
+            let (history, winner) = played_history(size);
+            writer.write_game(size, &history, Some(winner)).unwrap();
+        }
+        assert_eq!(writer.games(), sizes.len());
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    fn read_archive(content: &str, format: ArchiveFormat) -> Vec<Result<(GameState, MoveHistory), ArchiveError>> {
+        ArchiveReader::new(content.as_bytes(), format).collect()
+    }
+
+    mod json_lines {
+        use super::*;
+
+        #[test]
+        fn round_trips_games() {
+            let content = write_archive(ArchiveFormat::JsonLines, &[4, 6, 8]);
+            assert_eq!(content.lines().count(), 3);
+
+            let games = read_archive(&content, ArchiveFormat::JsonLines);
+            assert_eq!(games.len(), 3);
+            for (game, size) in games.into_iter().zip([4, 6, 8]) {
+                let (state, history) = game.unwrap();
+                assert_eq!(state.board().size(), size);
+                assert_eq!(history, played_history(size).0);
+            }
+        }
+
+        #[test]
+        fn reports_bad_game_and_continues() {
+            let good = write_archive(ArchiveFormat::JsonLines, &[4]);
+            let content = format!("{}\n{{ \"board_size\": 5, \"moves\": [] }}\n\n{}", good.trim(), good.trim());
+
+            let games = read_archive(&content, ArchiveFormat::JsonLines);
+            assert_eq!(games.len(), 3);
+            assert!(games[0].is_ok());
+            let err = games[1].as_ref().unwrap_err();
+            assert_eq!((err.game, err.line), (2, 2));
+            assert!(err.to_string().starts_with("Game 2 (line 2): Invalid board_size"));
+            // Blank lines are skipped, so the third game starts on line 4
+            assert!(games[2].is_ok());
+        }
+    }
+
+    mod pgn {
+        use super::*;
+
+        #[test]
+        fn round_trips_games() {
+            let content = write_archive(ArchiveFormat::Pgn, &[4, 6]);
+            let games = read_archive(&content, ArchiveFormat::Pgn);
+            assert_eq!(games.len(), 2);
+            assert_eq!(games[1].as_ref().unwrap().1, played_history(6).0);
+        }
+
+        #[test]
+        fn reports_bad_game_and_continues() {
+            let content = "[BoardSize \"4\"]\n\n1. b2 c2 *\n\n[BoardSize \"4\"]\n\n1. b2 a1 *\n\n[BoardSize \"4\"]\n1. b2 *\n";
+
+            let games = read_archive(content, ArchiveFormat::Pgn);
+            assert_eq!(games.len(), 3);
+            assert_eq!(games[0].as_ref().unwrap().1.len(), 2);
+            let err = games[1].as_ref().unwrap_err();
+            assert_eq!((err.game, err.line), (2, 5));
+            assert_eq!(games[2].as_ref().unwrap().1.len(), 1);
+        }
+
+        #[test]
+        fn keeps_annotations() {
+            let game = PgnGame::parse("[BoardSize \"4\"]\n1. b2 {Center} c2 *").unwrap();
+            let mut writer = ArchiveWriter::new(Vec::new(), ArchiveFormat::Pgn);
+            writer.write_pgn(&game).unwrap();
+            writer.write_pgn(&game).unwrap();
+            let content = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+            assert_eq!(content.matches("{Center}").count(), 2);
+            assert_eq!(read_archive(&content, ArchiveFormat::Pgn).len(), 2);
+        }
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(ArchiveFormat::from_path(Path::new("games.jsonl")), Some(ArchiveFormat::JsonLines));
+        assert_eq!(ArchiveFormat::from_path(Path::new("games.PGN")), Some(ArchiveFormat::Pgn));
+        assert_eq!(ArchiveFormat::from_path(Path::new("games.txt")), None);
+    }
+}
@@ -0,0 +1,60 @@
+//! A minimal engine for testing programs that speak the engine protocol, such as the external engine player.
+//!
+//! It always plays the first legal move. Options make it misbehave:
+//!
+//! - `--hang`: never reply to `go`, even after `stop`.
+//! - `--crash`: exit when asked for a move.
+//! - `--illegal`: reply to `go` with a move that is never legal.
+//! - `--silent`: never answer the `konane` handshake.
+//! - `--ignore-quit`: keep running after `quit`, until its input is closed.
+
+use std::io::{self, BufRead, Write};
+
+use konane::game::{Board, GameState, PieceColor, Rules};
+
+fn main() {
+    let options: Vec<String> = std::env::args().skip(1).collect();
+    let has = |option: &str| options.iter().any(|arg| arg == option);
+
+    let mut state = GameState::new(8, PieceColor::Black);
+    let mut stdout = io::stdout();
+    for line in io::stdin().lock().lines().map_while(Result::ok) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let reply = match words.as_slice() {
+            ["konane"] if has("--silent") => continue,
+            ["konane"] => "id name mock engine\nkonaneok".to_string(),
+            ["isready"] => "readyok".to_string(),
+            ["newgame", size] => match size.parse() {
+                Ok(size) => {
+                    state = GameState::new(size, PieceColor::Black);
+                    continue;
+                }
+                Err(_) => format!("error invalid board size {}", size),
+            },
+            ["position", "board", cells, player] => {
+                let player = if *player == "white" { PieceColor::White } else { PieceColor::Black };
+                match Board::from_cells(cells).and_then(|board| GameState::from_board(board, player)) {
+                    Ok(position) => {
+                        state = position;
+                        continue;
+                    }
+                    Err(err) => format!("error {}", err),
+                }
+            }
+            ["go", ..] if has("--hang") => continue,
+            ["go", ..] if has("--crash") => std::process::exit(3),
+            ["go", ..] if has("--illegal") => "bestmove a1-a1".to_string(),
+            ["go", ..] => match Rules::legal_moves(&state).first() {
+                Some(player_move) => format!("bestmove {}", player_move.to_algebraic()),
+                None => "bestmove none".to_string(),
+            },
+            ["stop"] => continue,
+            ["quit"] if has("--ignore-quit") => continue,
+            ["quit"] => break,
+            _ => format!("error unknown command {}", line),
+        };
+        if writeln!(stdout, "{}", reply).and_then(|()| stdout.flush()).is_err() {
+            break;
+        }
+    }
+}
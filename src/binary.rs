@@ -0,0 +1,263 @@
+//! A compact binary encoding of games for large datasets such as self-play records and opening book sources.
+//!
+//! Instead of squares and captures, each move is stored as its index in `Rules::legal_moves` for the position it was
+//! played in, so a typical move takes a single byte. A game record is the board size as one byte, the number of moves,
+//! and then the move indices, with the count and indices written as LEB128 varints. Records are self-delimiting, so a
+//! dataset is simply records written one after another; `write_game` and `read_game` handle one record at a time.
+//!
+//! Decoding replays the game, so a record only makes sense with the move ordering it was written with.
+
+use std::io::{ErrorKind, Read, Write};
+
+use crate::game::player::PlayerMove;
+use crate::game::{GamePhase, GameState, MoveHistory, MoveRecord, PieceColor, Rules};
+
+/// Encodes a game as a binary record. Fails if the board size is out of range or a move is not legal.
+pub fn encode_game(board_size: usize, history: &MoveHistory) -> Result<Vec<u8>, String> {
+    if !(4..=16).contains(&board_size) || !board_size.is_multiple_of(2) {
+        return Err(format!("Invalid board size {}", board_size));
+    }
+
+    let mut bytes = vec![board_size as u8];
+    write_varint(&mut bytes, history.len() as u64);
+    let mut state = GameState::new(board_size, PieceColor::Black);
+    for (number, record) in history.iter().enumerate() {
+        let moves = Rules::legal_moves(&state);
+        let index = moves
+            .iter()
+            .position(|candidate| same_move(candidate, record))
+            .ok_or_else(|| format!("Move {}: {} is not legal", number + 1, record.to_algebraic()))?;
+        moves[index]
+            .apply(&mut state)
+            .map_err(|err| format!("Move {}: {}", number + 1, err))?;
+        write_varint(&mut bytes, index as u64);
+    }
+    Ok(bytes)
+}
+
+/// Decodes a binary record, replaying its moves. Fails if the record is truncated, has bytes after the last move or
+/// refers to a move that does not exist.
+pub fn decode_game(bytes: &[u8]) -> Result<(GameState, MoveHistory), String> {
+    let mut reader = bytes;
+    let game = read_game(&mut reader)?.ok_or_else(|| "Empty game record".to_string())?;
+    if !reader.is_empty() {
+        return Err(format!("{} unexpected bytes after the last move", reader.len()));
+    }
+    Ok(game)
+}
+
+/// Writes a game record to a dataset.
+pub fn write_game<W: Write>(writer: &mut W, board_size: usize, history: &MoveHistory) -> Result<(), String> {
+    let bytes = encode_game(board_size, history)?;
+    writer
+        .write_all(&bytes)
+        .map_err(|err| format!("Failed to write game: {}", err))
+}
+
+/// Reads the next game record from a dataset, or `None` at the end of the data.
+pub fn read_game<R: Read>(reader: &mut R) -> Result<Option<(GameState, MoveHistory)>, String> {
+    let mut size = [0u8; 1];
+    match reader.read_exact(&mut size) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(format!("Failed to read game: {}", err)),
+    }
+    let board_size = size[0] as usize;
+    if !(4..=16).contains(&board_size) || !board_size.is_multiple_of(2) {
+        return Err(format!("Invalid board size {}", board_size));
+    }
+
+    let count = read_varint(reader)?;
+    let mut state = GameState::new(board_size, PieceColor::Black);
+    let mut history = MoveHistory::new();
+    for number in 1..=count {
+        let index = read_varint(reader)?;
+        if matches!(state.current_phase(), GamePhase::GameOver { .. }) {
+            return Err(format!("Move {}: the game is already over", number));
+        }
+        let moves = Rules::legal_moves(&state);
+        let player_move = usize::try_from(index)
+            .ok()
+            .and_then(|index| moves.get(index))
+            .ok_or_else(|| format!("Move {}: index {} but only {} legal moves", number, index, moves.len()))?;
+        let record = player_move
+            .apply(&mut state)
+            .map_err(|err| format!("Move {}: {}", number, err))?;
+        history.push(record);
+    }
+    Ok(Some((state, history)))
+}
+
+/// Compares a legal move with a recorded one by their squares. Captures follow from the squares, so they are not
+/// compared.
+fn same_move(candidate: &PlayerMove, record: &MoveRecord) -> bool {
+    match (candidate, record) {
+        (PlayerMove::OpeningRemoval(a), MoveRecord::OpeningRemoval { position: b, .. }) => a == b,
+        (PlayerMove::Jump(jump), MoveRecord::Jump { from, to, .. }) => jump.from == *from && jump.to == *to,
+        _ => false,
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte).map_err(|err| match err.kind() {
+            ErrorKind::UnexpectedEof => "Truncated game record".to_string(),
+            _ => format!("Failed to read game: {}", err),
+        })?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("Number too large in game record".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Position;
+
+    /// The 4x4 opening b2, c2, then Black's b4-b2.
+    fn short_game() -> MoveHistory {
+        let mut state = GameState::new(4, PieceColor::Black);
+        let mut history = MoveHistory::new();
+        history.push(Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap());
+        history.push(Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap());
+        let jump = Rules::all_valid_jumps(&state).remove(0);
+        history.push(Rules::apply_jump(&mut state, &jump));
+        history
+    }
+
+    /// A whole 6x6 game, always playing the last legal move.
+    fn finished_game() -> MoveHistory {
+        let mut state = GameState::new(6, PieceColor::Black);
+        let mut history = MoveHistory::new();
+        while let Some(player_move) = Rules::legal_moves(&state).pop() {
+            history.push(player_move.apply(&mut state).unwrap());
+        }
+        history
+    }
+
+    mod encoding {
+        use super::*;
+
+        #[test]
+        fn stores_one_byte_per_move() {
+            let bytes = encode_game(4, &short_game()).unwrap();
+
+            assert_eq!(bytes.len(), 5);
+            assert_eq!(bytes[..2], [4, 3]);
+            // b2 is the first of Black's removals
+            assert_eq!(bytes[2], 0);
+        }
+
+        #[test]
+        fn round_trips_a_finished_game() {
+            let history = finished_game();
+
+            let (state, decoded) = decode_game(&encode_game(6, &history).unwrap()).unwrap();
+
+            assert_eq!(decoded, history);
+            assert!(matches!(state.current_phase(), GamePhase::GameOver { .. }));
+        }
+
+        #[test]
+        fn restores_captures() {
+            let history = short_game();
+
+            let (_, decoded) = decode_game(&encode_game(4, &history).unwrap()).unwrap();
+
+            assert!(matches!(&decoded[2], MoveRecord::Jump { captured, .. } if captured.len() == 1));
+        }
+
+        #[test]
+        fn rejects_illegal_moves() {
+            let mut history = short_game();
+            history.swap(0, 1);
+
+            let err = encode_game(4, &history).unwrap_err();
+
+            assert!(err.starts_with("Move 1:"), "{}", err);
+        }
+
+        #[test]
+        fn rejects_invalid_board_sizes() {
+            assert!(encode_game(5, &MoveHistory::new()).is_err());
+            assert!(encode_game(18, &MoveHistory::new()).is_err());
+        }
+    }
+
+    mod decoding {
+        use super::*;
+
+        #[test]
+        fn rejects_out_of_range_indices() {
+            let err = decode_game(&[4, 1, 9]).unwrap_err();
+
+            assert_eq!(err, "Move 1: index 9 but only 4 legal moves");
+        }
+
+        #[test]
+        fn rejects_truncated_records() {
+            let bytes = encode_game(4, &short_game()).unwrap();
+
+            assert_eq!(decode_game(&bytes[..4]).unwrap_err(), "Truncated game record");
+        }
+
+        #[test]
+        fn rejects_trailing_bytes() {
+            let mut bytes = encode_game(4, &short_game()).unwrap();
+            bytes.push(0);
+
+            assert!(decode_game(&bytes).is_err());
+        }
+
+        #[test]
+        fn rejects_moves_after_the_end() {
+            let mut bytes = encode_game(6, &finished_game()).unwrap();
+            bytes[1] += 1;
+            bytes.push(0);
+
+            assert!(decode_game(&bytes).unwrap_err().contains("already over"));
+        }
+    }
+
+    mod datasets {
+        use super::*;
+
+        #[test]
+        fn reads_records_one_after_another() {
+            let mut data = Vec::new();
+            write_game(&mut data, 4, &short_game()).unwrap();
+            write_game(&mut data, 6, &finished_game()).unwrap();
+            write_game(&mut data, 8, &MoveHistory::new()).unwrap();
+
+            let mut reader = data.as_slice();
+            let mut sizes = Vec::new();
+            while let Some((state, _)) = read_game(&mut reader).unwrap() {
+                sizes.push(state.board().size());
+            }
+
+            assert_eq!(sizes, [4, 6, 8]);
+        }
+
+        #[test]
+        fn encodes_large_indices_as_varints() {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, 300);
+
+            assert_eq!(bytes, [0xac, 0x02]);
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), 300);
+        }
+    }
+}
@@ -0,0 +1,633 @@
+//! The command-line tools: everything the binary does besides opening the desktop app.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use konane::analysis;
+use konane::api::{Api, ApiServer};
+use konane::archive::{ArchiveFormat, ArchiveReader, ArchiveWriter};
+use konane::binary;
+use konane::correspondence::CorrespondenceGame;
+use konane::engine::Engine;
+use konane::export;
+use konane::game::ai::play_out;
+use konane::game::player::PlayerMove;
+use konane::game::{AiPlayer, GamePhase, GameState, MoveHistory, OpeningBook, PieceColor, Rules};
+use konane::import;
+use konane::network::GameServer;
+use konane::pgn::result_code;
+use konane::tournament::{Contestant, Format, Tournament};
+use konane::tui::{render_board, TerminalGame};
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::SeedableRng;
+
+/// How the AI is set up for the commands that use it.
+pub struct AiOptions {
+    pub depth: i32,
+    pub book: Option<Arc<OpeningBook>>,
+    pub book_random: bool,
+    pub move_ordering: bool,
+}
+
+impl AiOptions {
+    fn create_ai(&self, color: PieceColor, rng: &mut StdRng) -> AiPlayer {
+        let mut ai = AiPlayer::new(color, self.depth)
+            .with_move_ordering(self.move_ordering)
+            .with_rng(StdRng::from_rng(rng));
+        if let Some(ref book) = self.book {
+            ai = ai.with_book(book.clone(), self.book_random);
+        }
+        ai
+    }
+}
+
+fn new_rng(seed: Option<u64>) -> StdRng {
+    seed.map_or_else(StdRng::from_os_rng, StdRng::seed_from_u64)
+}
+
+fn winner(state: &GameState) -> Option<PieceColor> {
+    match state.current_phase() {
+        GamePhase::GameOver { winner } => Some(winner),
+        _ => None,
+    }
+}
+
+fn validate_board_size(size: usize) -> Result<(), String> {
+    if !(4..=16).contains(&size) || !size.is_multiple_of(2) {
+        return Err("Invalid board size: must be even and between 4 and 16".to_string());
+    }
+    Ok(())
+}
+
+/// Reads a position given as a game file or as moves from the start in algebraic notation.
+fn load_position(position: &str, size: usize) -> Result<(GameState, MoveHistory), String> {
+    if Path::new(position).is_file() {
+        let (state, history, _) = import::import_game_from_path(position)?;
+        return Ok((state, history));
+    }
+    let (state, history, _) = import::import_move_list(size, position)?;
+    Ok((state, history))
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+type GameResult = Result<(GameState, MoveHistory), String>;
+
+/// Reads the games in a file: every game of a `.jsonl` or `.pgn` archive or a `.bin` dataset, or the single game in
+/// any other file. A game that cannot be read is returned as an error and reading continues where possible.
+fn read_games(path: &str) -> Result<Box<dyn Iterator<Item = GameResult>>, String> {
+    match extension(path).as_str() {
+        "jsonl" | "pgn" => {
+            let reader = ArchiveReader::open(path)?;
+            Ok(Box::new(reader.map(|game| game.map_err(|err| err.to_string()))))
+        }
+        "bin" => {
+            let file = File::open(path).map_err(|err| format!("Failed to open {}: {}", path, err))?;
+            let mut reader = BufReader::new(file);
+            let mut failed = false;
+            // A bad record leaves the reader in the middle of it, so reading stops at the first error
+            Ok(Box::new(std::iter::from_fn(move || {
+                if failed {
+                    return None;
+                }
+                let game = binary::read_game(&mut reader).transpose()?;
+                failed = game.is_err();
+                Some(game)
+            })))
+        }
+        _ => {
+            let game = import::import_game_from_path(path).map(|(state, history, _)| (state, history));
+            Ok(Box::new(std::iter::once(game)))
+        }
+    }
+}
+
+/// Writes games to a file in the format given by its extension: `.jsonl` or `.pgn` archives and `.bin` datasets hold
+/// any number of games, `.json` and `.txt` files exactly one.
+struct GameWriter {
+    path: String,
+    kind: WriterKind,
+    games: usize,
+}
+
+enum WriterKind {
+    Archive(ArchiveWriter<BufWriter<File>>),
+    Binary(BufWriter<File>),
+    Json,
+    Text,
+}
+
+impl GameWriter {
+    fn create(path: &str) -> Result<Self, String> {
+        let create_file = || {
+            File::create(path)
+                .map(BufWriter::new)
+                .map_err(|err| format!("Failed to create {}: {}", path, err))
+        };
+        let kind = match extension(path).as_str() {
+            "jsonl" => WriterKind::Archive(ArchiveWriter::create(path, ArchiveFormat::JsonLines)?),
+            "pgn" => WriterKind::Archive(ArchiveWriter::create(path, ArchiveFormat::Pgn)?),
+            "bin" => WriterKind::Binary(create_file()?),
+            "json" => WriterKind::Json,
+            "txt" => WriterKind::Text,
+            _ => return Err(format!("Unknown game format for {}: use .json, .txt, .pgn, .jsonl or .bin", path)),
+        };
+        Ok(Self {
+            path: path.to_string(),
+            kind,
+            games: 0,
+        })
+    }
+
+    /// Fails if the file cannot hold `games` more games, so that games are not played only to be refused.
+    fn check_room(&self, games: usize) -> Result<(), String> {
+        if matches!(self.kind, WriterKind::Json | WriterKind::Text) && self.games + games > 1 {
+            return Err(format!("{} can hold only one game; use .jsonl, .pgn or .bin", self.path));
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, board_size: usize, history: &MoveHistory, winner: Option<PieceColor>) -> Result<(), String> {
+        self.check_room(1)?;
+        match &mut self.kind {
+            WriterKind::Archive(writer) => writer.write_game(board_size, history, winner)?,
+            WriterKind::Binary(writer) => binary::write_game(writer, board_size, history)?,
+            WriterKind::Json => {
+                let json = export::game_to_json(board_size, winner, history)?;
+                std::fs::write(&self.path, json).map_err(|err| format!("Failed to write {}: {}", self.path, err))?;
+            }
+            WriterKind::Text => {
+                let text = export::game_to_text(board_size, winner, history);
+                std::fs::write(&self.path, text).map_err(|err| format!("Failed to write {}: {}", self.path, err))?;
+            }
+        }
+        self.games += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<usize, String> {
+        match self.kind {
+            WriterKind::Archive(writer) => {
+                writer.finish()?;
+            }
+            WriterKind::Binary(mut writer) => {
+                writer.flush().map_err(|err| format!("Failed to write {}: {}", self.path, err))?;
+            }
+            WriterKind::Json | WriterKind::Text => {}
+        }
+        Ok(self.games)
+    }
+}
+
+pub fn convert(input: &str, output: &str) -> Result<(), String> {
+    let mut writer = GameWriter::create(output)?;
+    let mut skipped = 0;
+    for game in read_games(input)? {
+        match game {
+            Ok((state, history)) => writer.write(state.board().size(), &history, winner(&state))?,
+            Err(error) => {
+                eprintln!("Skipped: {}", error);
+                skipped += 1;
+            }
+        }
+    }
+    let games = writer.finish()?;
+    match skipped {
+        0 => println!("Wrote {} games to {}", games, output),
+        _ => println!("Wrote {} games to {}, skipped {}", games, output, skipped),
+    }
+    Ok(())
+}
+
+/// Checks game files, printing a line for each game. Returns false if any game is invalid.
+pub fn validate(files: &[String]) -> Result<bool, String> {
+    let mut all_valid = true;
+    for file in files {
+        let is_collection = matches!(extension(file).as_str(), "jsonl" | "pgn" | "bin");
+        for (index, game) in read_games(file)?.enumerate() {
+            let label = if is_collection { format!("{} game {}", file, index + 1) } else { file.clone() };
+            match game {
+                Ok((state, history)) => {
+                    println!("{}: OK, {} moves, result {}", label, history.len(), result_code(winner(&state)));
+                }
+                Err(error) => {
+                    all_valid = false;
+                    // A single game can be replayed leniently to explain the first illegal move
+                    let diagnostic = (!is_collection)
+                        .then(|| import::import_game_lenient_from_path(file).ok().and_then(|game| game.error))
+                        .flatten();
+                    match diagnostic {
+                        Some(diagnostic) => println!("{}: {}", label, diagnostic),
+                        None => println!("{}: {}", label, error),
+                    }
+                }
+            }
+        }
+    }
+    Ok(all_valid)
+}
+
+pub fn analyze(game: &str, depth: i32, json: bool) -> Result<(), String> {
+    let (state, history, _) = import::import_game_from_path(game)?;
+    let moves = analysis::analyze_game(state.board().size(), &history, depth)?;
+
+    if json {
+        let json = serde_json::to_string_pretty(&moves).map_err(|err| format!("Failed to serialize analysis: {}", err))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    for analyzed in &moves {
+        let verdict = match &analyzed.best_move {
+            None => "forced".to_string(),
+            Some(_) if analyzed.agrees() => "best".to_string(),
+            Some(best) => format!("AI prefers {}", best),
+        };
+        println!("{:>4}. {:<5} {:<8} {}", analyzed.move_number, analyzed.color, analyzed.played, verdict);
+    }
+    let searched: Vec<_> = moves.iter().filter(|analyzed| analyzed.best_move.is_some()).collect();
+    let agreed = searched.iter().filter(|analyzed| analyzed.agrees()).count();
+    println!("Agreed with the AI on {} of {} moves with a choice (depth {})", agreed, searched.len(), depth);
+    Ok(())
+}
+
+pub fn perft(position: Option<&str>, size: usize, depth: usize, divide: bool) -> Result<(), String> {
+    validate_board_size(size)?;
+    let (state, _) = load_position(position.unwrap_or(""), size)?;
+
+    if divide && depth > 0 {
+        let mut total = 0;
+        for player_move in Rules::legal_moves(&state) {
+            let mut next = state.clone();
+            player_move.apply(&mut next).map_err(str::to_string)?;
+            let count = Rules::perft(&next, depth - 1);
+            println!("{}: {}", player_move.to_algebraic(), count);
+            total += count;
+        }
+        println!("Total: {}", total);
+    } else {
+        println!("{}", Rules::perft(&state, depth));
+    }
+    Ok(())
+}
+
+pub fn bestmove(position: &str, size: usize, ai: &AiOptions, seed: Option<u64>, json: bool) -> Result<(), String> {
+    validate_board_size(size)?;
+    let (state, _) = load_position(position, size)?;
+    if winner(&state).is_some() {
+        return Err("The game is over".to_string());
+    }
+
+    let player = ai.create_ai(state.current_player(), &mut new_rng(seed));
+    let (best_move, report) = player.search(&state);
+    if json {
+        let json = serde_json::to_string_pretty(&report).map_err(|err| format!("Failed to serialize report: {}", err))?;
+        println!("{}", json);
+    } else {
+        let best_move = best_move.ok_or_else(|| "No legal move".to_string())?;
+        println!("bestmove {}", best_move.to_algebraic());
+    }
+    Ok(())
+}
+
+/// Plays AI against AI. The first `random_plies` moves of each game are chosen at random so that games differ.
+pub fn selfplay(output: &str, games: usize, size: usize, random_plies: usize, ai: &AiOptions, seed: Option<u64>) -> Result<(), String> {
+    validate_board_size(size)?;
+    let mut rng = new_rng(seed);
+    let mut writer = GameWriter::create(output)?;
+    writer.check_room(games)?;
+    let mut wins = [0; 2];
+
+    for game in 1..=games {
+        let mut state = GameState::new(size, PieceColor::Black);
+        let mut history = MoveHistory::new();
+        for _ in 0..random_plies {
+            let moves = Rules::legal_moves(&state);
+            let Some(player_move) = moves.choose(&mut rng) else {
+                break;
+            };
+            history.push(player_move.apply(&mut state).map_err(str::to_string)?);
+        }
+
+        let black = ai.create_ai(PieceColor::Black, &mut rng);
+        let white = ai.create_ai(PieceColor::White, &mut rng);
+        let (state, rest) = play_out(state, &black, &white)?;
+        history.extend(rest);
+
+        let winner = winner(&state);
+        writer.write(size, &history, winner)?;
+        wins[usize::from(winner == Some(PieceColor::White))] += 1;
+        eprintln!("Game {}: {} moves, {}", game, history.len(), result_code(winner));
+    }
+
+    let written = writer.finish()?;
+    println!("Wrote {} games to {}: Black won {}, White won {}", written, output, wins[0], wins[1]);
+    Ok(())
+}
+
+/// How a tournament is played, besides its contestants.
+pub struct TournamentOptions {
+    pub gauntlet: bool,
+    pub sizes: Vec<usize>,
+    pub rounds: usize,
+    /// Games played at once, or one per processor
+    pub threads: Option<usize>,
+    /// Directory each game is written to
+    pub games_dir: Option<String>,
+}
+
+/// Plays a tournament between AI configurations, printing each result as it finishes and the standings at the end.
+pub fn tournament(players: &[String], options: TournamentOptions, default_depth: i32, seed: Option<u64>) -> Result<(), String> {
+    let contestants = players
+        .iter()
+        .map(|player| Contestant::parse(player, default_depth))
+        .collect::<Result<Vec<_>, _>>()?;
+    let format = if options.gauntlet { Format::Gauntlet } else { Format::RoundRobin };
+    let mut tournament = Tournament::new(contestants, format)?
+        .with_board_sizes(options.sizes)?
+        .with_rounds(options.rounds)
+        .with_seed(seed);
+    if let Some(threads) = options.threads {
+        tournament = tournament.with_threads(threads);
+    }
+    let games_dir = options.games_dir.as_deref();
+    if let Some(dir) = games_dir {
+        std::fs::create_dir_all(dir).map_err(|err| format!("Failed to create {}: {}", dir, err))?;
+    }
+
+    let total = tournament.schedule().len();
+    let contestants = tournament.contestants();
+    let mut finished = 0;
+    let standings = tournament.run(|result| {
+        finished += 1;
+        let game = &result.game;
+        let (black, white) = (&contestants[game.black].name, &contestants[game.white].name);
+        eprintln!(
+            "[{}/{}] Game {}, {}x{}: {} (Black) vs {} (White), {} wins in {} moves",
+            finished,
+            total,
+            game.number,
+            game.board_size,
+            game.board_size,
+            black,
+            white,
+            contestants[result.winner_and_loser().0].name,
+            result.history.len()
+        );
+        let Some(dir) = games_dir else {
+            return Ok(());
+        };
+        let json = export::game_to_json(game.board_size, Some(result.winner), &result.history)?;
+        let name = format!("game-{:04}-{}-vs-{}.json", game.number, file_name_part(black), file_name_part(white));
+        let path = Path::new(dir).join(name);
+        std::fs::write(&path, json).map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+    })?;
+
+    println!("{}", standings.report());
+    Ok(())
+}
+
+/// Replaces the characters of a contestant's name that may not be allowed in a file name.
+fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+fn play_in_terminal(size: usize, game: Option<&str>, ais: Vec<AiPlayer>) -> Result<(), String> {
+    let mut terminal_game = match game {
+        Some(path) => {
+            let (state, history, undo_stack) = import::import_game_from_path(path)?;
+            TerminalGame::from_position(state, history, undo_stack)
+        }
+        None => {
+            validate_board_size(size)?;
+            TerminalGame::new(size)
+        }
+    };
+    for ai in ais {
+        terminal_game = terminal_game.with_ai(ai);
+    }
+
+    println!("Type a move such as e4 or f4-d4, or help for the commands.\n");
+    println!("{}", terminal_game.render());
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        let replies = terminal_game.play_ai_moves();
+        if !replies.is_empty() {
+            println!("{}\n\n{}", replies.join("\n"), terminal_game.render());
+        }
+
+        print!("> ");
+        std::io::stdout().flush().map_err(|err| err.to_string())?;
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line.map_err(|err| format!("Failed to read input: {}", err))?;
+        if matches!(line.trim().to_lowercase().as_str(), "quit" | "exit" | "q") {
+            break;
+        }
+        match terminal_game.execute(&line) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{}", output),
+            Err(error) => println!("{}", error),
+        }
+    }
+    Ok(())
+}
+
+/// Starts a terminal game with the AI playing the given colors.
+pub fn play_in_terminal_against(size: usize, game: Option<&str>, ai_colors: &[PieceColor], ai: &AiOptions, seed: Option<u64>) -> Result<(), String> {
+    let mut rng = new_rng(seed);
+    let ais = ai_colors.iter().map(|&color| ai.create_ai(color, &mut rng)).collect();
+    play_in_terminal(size, game, ais)
+}
+
+/// Runs the engine protocol on stdin and stdout until `quit` or the end of the input.
+pub fn run_engine(ai: AiOptions, seed: Option<u64>) -> Result<(), String> {
+    let (output, lines) = mpsc::channel::<String>();
+    let printer = thread::spawn(move || {
+        let mut stdout = std::io::stdout().lock();
+        for line in lines {
+            if writeln!(stdout, "{}", line).and_then(|()| stdout.flush()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let depth = ai.depth;
+    let make_ai = move || ai.create_ai(PieceColor::Black, &mut new_rng(seed));
+    let mut engine = Engine::new(make_ai, depth, output);
+    let mut quit = false;
+    for line in std::io::stdin().lock().lines() {
+        let line = line.map_err(|err| format!("Failed to read input: {}", err))?;
+        if !engine.execute(&line) {
+            quit = true;
+            break;
+        }
+    }
+    // At the end of piped input, a search that is still running is allowed to finish
+    while !quit && engine.is_searching() {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // Dropping the engine closes the output once the worker has finished, which ends the printer
+    drop(engine);
+    let _ = printer.join();
+    Ok(())
+}
+
+/// Hosts a network game until the process is stopped.
+pub fn serve(port: u16, size: usize) -> Result<(), String> {
+    let server = GameServer::bind(&format!("0.0.0.0:{}", port), size)?;
+    println!("Serving a {}x{} game on port {}", size, size, server.local_addr()?.port());
+    server.run();
+    Ok(())
+}
+
+/// Answers HTTP/JSON API requests on localhost until the process is stopped.
+pub fn serve_api(port: u16, allowed_origin: Option<&str>, max_searches: usize, ai: AiOptions, seed: Option<u64>) -> Result<(), String> {
+    if max_searches == 0 {
+        return Err("--max-searches must be at least 1".to_string());
+    }
+    let depth = ai.depth;
+    let api = Api::new(move || ai.create_ai(PieceColor::Black, &mut new_rng(seed)), depth).with_max_searches(max_searches);
+    let mut server = ApiServer::bind(&format!("127.0.0.1:{}", port), api)?;
+    if let Some(origin) = allowed_origin {
+        server = server.with_allowed_origin(origin);
+    }
+    println!("Serving the API on http://{}", server.local_addr()?);
+    server.run();
+    Ok(())
+}
+
+/// Creates a correspondence game file. An existing file is not replaced.
+pub fn new_correspondence(file: &str, black: &str, white: &str, size: usize) -> Result<(), String> {
+    if Path::new(file).exists() {
+        return Err(format!("{} already exists", file));
+    }
+    let game = CorrespondenceGame::new(black, white, size)?;
+    game.save(file)?;
+    println!("Created {}. Send it to {}, who plays Black and moves first.", file, game.name(PieceColor::Black));
+    Ok(())
+}
+
+/// Plays the named player's move in a correspondence game and writes the file back.
+pub fn play_correspondence(file: &str, player: &str, notation: &str) -> Result<(), String> {
+    let mut game = CorrespondenceGame::load(file)?;
+    let player_move = PlayerMove::from_algebraic(game.state(), notation)?;
+    let record = game.play(player, &player_move)?;
+    game.save(file)?;
+
+    println!("{}", render_board(game.state().board()));
+    match game.winner() {
+        Some(winner) => println!("{} wins as {}. The game is over.", game.name(winner), winner),
+        None => println!(
+            "Played {}. Send {} to {}, who moves next.",
+            record.to_algebraic(),
+            file,
+            game.player_to_move().unwrap_or_default()
+        ),
+    }
+    Ok(())
+}
+
+/// Prints a correspondence game after checking its signatures.
+pub fn show_correspondence(file: &str) -> Result<(), String> {
+    let game = CorrespondenceGame::load(file)?;
+    println!(
+        "{} (Black) vs {} (White), {} moves, signatures verified",
+        game.name(PieceColor::Black),
+        game.name(PieceColor::White),
+        game.move_history().len()
+    );
+    println!("{}", render_board(game.state().board()));
+    match game.winner() {
+        Some(winner) => println!("{} wins as {}", game.name(winner), winner),
+        None => println!("{} to move as {}", game.player_to_move().unwrap_or_default(), game.state().current_player()),
+    }
+    Ok(())
+}
+
+pub fn build_book(output: &str, sizes: &[usize], depth: i32, plies: usize, margin: f32) -> Result<(), String> {
+    let mut book = OpeningBook::new();
+    for &size in sizes {
+        validate_board_size(size)?;
+        book.merge(OpeningBook::generate(size, depth, plies, margin));
+    }
+
+    book.save(output)?;
+    println!("Wrote {} positions to {}", book.len(), output);
+    Ok(())
+}
+
+pub fn write_diagram(game: &str, output: &str, move_number: Option<usize>, cell_size: f32, coordinates: bool) -> Result<(), String> {
+    let (final_state, history, undo_stack) = import::import_game_from_path(game)?;
+    let move_number = move_number.unwrap_or(history.len());
+    if move_number > history.len() {
+        return Err(format!("The game has only {} moves", history.len()));
+    }
+    // The undo stack holds the position before each move
+    let state = undo_stack.get(move_number).map_or(&final_state, |(state, _)| state);
+
+    let mut diagram = konane::diagram::Diagram::new(state)
+        .with_cell_size(cell_size)
+        .with_coordinates(coordinates);
+    if let Some(last_move) = move_number.checked_sub(1).map(|i| &history[i]) {
+        diagram = diagram.with_last_move(last_move);
+    }
+
+    let bytes = match extension(output).as_str() {
+        "svg" => diagram.to_svg().into_bytes(),
+        #[cfg(feature = "png")]
+        "png" => diagram.to_png()?,
+        #[cfg(not(feature = "png"))]
+        "png" => return Err("PNG output requires building with --features png".to_string()),
+        _ => return Err(format!("Unknown diagram format for {}: use .svg or .png", output)),
+    };
+    std::fs::write(output, bytes).map_err(|err| format!("Failed to write {}: {}", output, err))
+}
+
+pub fn write_html(game: &str, output: &str) -> Result<(), String> {
+    let (final_state, history, _) = import::import_game_from_path(game)?;
+    let html = konane::html::game_to_html(final_state.board().size(), winner(&final_state), &history)?;
+    std::fs::write(output, html).map_err(|err| format!("Failed to write {}: {}", output, err))?;
+    println!("Wrote {}", output);
+    Ok(())
+}
+
+#[cfg(feature = "png")]
+pub fn write_animation(game: &str, output: &str, delays: (Duration, Duration), cell_size: f32, coordinates: bool) -> Result<(), String> {
+    let (final_state, history, _) = import::import_game_from_path(game)?;
+    let replay = konane::animation::Replay::new(final_state.board().size(), &history)
+        .with_frame_delay(delays.0)
+        .with_final_delay(delays.1)
+        .with_cell_size(cell_size)
+        .with_coordinates(coordinates);
+
+    let bytes = match extension(output).as_str() {
+        "png" | "apng" => replay.to_apng()?,
+        #[cfg(feature = "gif")]
+        "gif" => replay.to_gif()?,
+        #[cfg(not(feature = "gif"))]
+        "gif" => return Err("GIF output requires building with --features gif".to_string()),
+        _ => return Err(format!("Unknown animation format for {}: use .gif, .png or .apng", output)),
+    };
+    std::fs::write(output, bytes).map_err(|err| format!("Failed to write {}: {}", output, err))
+}
+
+#[cfg(not(feature = "png"))]
+pub fn write_animation(_game: &str, _output: &str, _delays: (Duration, Duration), _cell_size: f32, _coordinates: bool) -> Result<(), String> {
+    Err("Animations require building with --features png or --features gif".to_string())
+}
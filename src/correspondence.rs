@@ -0,0 +1,326 @@
+//! Correspondence games: a game file passed back and forth between two named players, one move at a time.
+//!
+//! A correspondence file wraps a game in the JSON format with the players' names and a signature for each move. The
+//! signatures form a hash chain: each is the SHA-256 of the previous one (or, for the first move, of the board size
+//! and the names), the name of the player who moved and the move. Editing, removing or reordering an earlier move, or
+//! changing a name, breaks the chain from that point on, so the file is refused. The chain holds no secret, so it
+//! guards against mistakes and casual tampering rather than a player who recomputes it.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::export;
+use crate::game::player::PlayerMove;
+use crate::game::{GamePhase, GameState, MoveHistory, MoveRecord, PieceColor};
+use crate::import;
+
+/// Version of the correspondence format written by this version of Kōnane.
+pub const CORRESPONDENCE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CorrespondenceFile {
+    correspondence_version: u32,
+    black: String,
+    white: String,
+    game: serde_json::Value,
+    /// One hex-encoded hash per move, each chained to the one before
+    signatures: Vec<String>,
+}
+
+/// A correspondence game and its players.
+#[derive(Debug, Clone)]
+pub struct CorrespondenceGame {
+    black: String,
+    white: String,
+    state: GameState,
+    move_history: MoveHistory,
+    signatures: Vec<String>,
+}
+
+impl CorrespondenceGame {
+    /// Starts a game between two players, Black moving first.
+    pub fn new(black: &str, white: &str, board_size: usize) -> Result<Self, String> {
+        let (black, white) = (black.trim(), white.trim());
+        if black.is_empty() || white.is_empty() {
+            return Err("Both players need a name".to_string());
+        }
+        if black == white {
+            return Err("The players need different names".to_string());
+        }
+        let (state, move_history, _) = import::import_move_list(board_size, "")?;
+        Ok(Self {
+            black: black.to_string(),
+            white: white.to_string(),
+            state,
+            move_history,
+            signatures: Vec::new(),
+        })
+    }
+
+    pub fn name(&self, color: PieceColor) -> &str {
+        match color {
+            PieceColor::Black => &self.black,
+            PieceColor::White => &self.white,
+        }
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn move_history(&self) -> &MoveHistory {
+        &self.move_history
+    }
+
+    pub fn winner(&self) -> Option<PieceColor> {
+        match self.state.current_phase() {
+            GamePhase::GameOver { winner } => Some(winner),
+            _ => None,
+        }
+    }
+
+    /// The name of the player to move, or `None` once the game is over.
+    pub fn player_to_move(&self) -> Option<&str> {
+        self.winner().is_none().then(|| self.name(self.state.current_player()))
+    }
+
+    /// Plays a move for the named player. Fails if it is not their turn or the move is illegal.
+    pub fn play(&mut self, player: &str, player_move: &PlayerMove) -> Result<MoveRecord, String> {
+        let Some(to_move) = self.player_to_move() else {
+            return Err("The game is over".to_string());
+        };
+        if player.trim() != to_move {
+            return Err(format!(
+                "It is {}'s turn ({}), not {}'s",
+                to_move,
+                self.state.current_player(),
+                player.trim()
+            ));
+        }
+        let record = player_move.apply(&mut self.state).map_err(str::to_string)?;
+        let previous = self.signatures.last().cloned().unwrap_or_else(|| self.genesis());
+        self.signatures.push(sign(&previous, self.name(record.color()), &record));
+        self.move_history.push(record.clone());
+        Ok(record)
+    }
+
+    /// The hash the chain starts from, binding the board size and the players' names.
+    fn genesis(&self) -> String {
+        hex_digest(format!("konane correspondence\n{}\n{}\n{}", self.state.board().size(), self.black, self.white))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        let game = export::game_to_json(self.state.board().size(), self.winner(), &self.move_history)?;
+        let file = CorrespondenceFile {
+            correspondence_version: CORRESPONDENCE_VERSION,
+            black: self.black.clone(),
+            white: self.white.clone(),
+            game: serde_json::from_str(&game).map_err(|err| format!("Failed to serialize game: {}", err))?,
+            signatures: self.signatures.clone(),
+        };
+        serde_json::to_string_pretty(&file).map_err(|err| format!("Failed to serialize correspondence game: {}", err))
+    }
+
+    /// Reads a game written by `to_json`, replaying its moves and checking every signature.
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let file: CorrespondenceFile =
+            serde_json::from_str(content).map_err(|err| format!("Invalid correspondence game: {}", err))?;
+        if file.correspondence_version > CORRESPONDENCE_VERSION {
+            return Err(format!(
+                "Unsupported correspondence_version {}: this version of Kōnane reads up to version {}. Upgrade Kōnane \
+                 to continue this game.",
+                file.correspondence_version, CORRESPONDENCE_VERSION
+            ));
+        }
+
+        let (state, move_history, _) = import::import_game_from_content(&file.game.to_string())?;
+        let mut game = Self::new(&file.black, &file.white, state.board().size())?;
+        if file.signatures.len() != move_history.len() {
+            return Err(format!(
+                "The game has {} moves but {} signatures",
+                move_history.len(),
+                file.signatures.len()
+            ));
+        }
+        for (index, (record, signature)) in move_history.iter().zip(&file.signatures).enumerate() {
+            let player = game.name(record.color()).to_string();
+            let player_move = PlayerMove::from_record(&game.state, record)?;
+            game.play(&player, &player_move)?;
+            if game.signatures[index] != *signature {
+                return Err(format!(
+                    "Move {} ({}) does not match its signature: the game has been altered",
+                    index + 1,
+                    record.to_algebraic()
+                ));
+            }
+        }
+        Ok(game)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        std::fs::write(path, self.to_json()?).map_err(|err| format!("Failed to write correspondence game: {}", err))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| format!("Failed to read file: {}", err))?;
+        Self::from_json(&content)
+    }
+}
+
+/// True if the content looks like a correspondence file rather than a plain game.
+pub fn is_correspondence(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content).is_ok_and(|value| value.get("correspondence_version").is_some())
+}
+
+/// The link after `previous` for a move by `player`.
+fn sign(previous: &str, player: &str, record: &MoveRecord) -> String {
+    let record = serde_json::to_string(record).expect("move records serialize");
+    hex_digest(format!("{}\n{}\n{}", previous, player, record))
+}
+
+fn hex_digest(data: String) -> String {
+    Sha256::digest(data.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Position, Rules};
+
+    /// A 4x4 game after b2, c2 and Black's first jump, between Ana and Kai.
+    fn started_game() -> CorrespondenceGame {
+        let mut game = CorrespondenceGame::new("Ana", "Kai", 4).unwrap();
+        game.play("Ana", &PlayerMove::OpeningRemoval(Position::new(1, 1))).unwrap();
+        game.play("Kai", &PlayerMove::OpeningRemoval(Position::new(1, 2))).unwrap();
+        let jump = PlayerMove::from_algebraic(game.state(), "b4-b2").unwrap();
+        game.play("Ana", &jump).unwrap();
+        game
+    }
+
+    /// Edits the JSON of a saved game.
+    fn altered(game: &CorrespondenceGame, alter: impl FnOnce(&mut serde_json::Value)) -> String {
+        let mut json: serde_json::Value = serde_json::from_str(&game.to_json().unwrap()).unwrap();
+        alter(&mut json);
+        json.to_string()
+    }
+
+    mod turns {
+        use super::*;
+
+        #[test]
+        fn alternate_between_the_players() {
+            let game = started_game();
+
+            assert_eq!(game.move_history().len(), 3);
+            assert_eq!(game.player_to_move(), Some("Kai"));
+            assert_eq!(game.name(PieceColor::Black), "Ana");
+        }
+
+        #[test]
+        fn refuse_a_move_out_of_turn() {
+            let mut game = started_game();
+            let player_move = Rules::legal_moves(game.state()).remove(0);
+
+            let err = game.play("Ana", &player_move).unwrap_err();
+
+            assert_eq!(err, "It is Kai's turn (White), not Ana's");
+            assert_eq!(game.move_history().len(), 3);
+        }
+
+        #[test]
+        fn refuse_an_illegal_move() {
+            let mut game = CorrespondenceGame::new("Ana", "Kai", 4).unwrap();
+
+            assert!(game.play("Ana", &PlayerMove::OpeningRemoval(Position::new(0, 1))).is_err());
+            assert!(game.signatures.is_empty());
+        }
+
+        #[test]
+        fn need_two_different_names() {
+            assert!(CorrespondenceGame::new("Ana", " ", 4).is_err());
+            assert!(CorrespondenceGame::new("Ana", "Ana", 4).is_err());
+        }
+    }
+
+    mod files {
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let game = started_game();
+
+            let json = game.to_json().unwrap();
+            let loaded = CorrespondenceGame::from_json(&json).unwrap();
+
+            assert!(is_correspondence(&json));
+            assert_eq!(loaded.move_history(), game.move_history());
+            assert_eq!(loaded.signatures, game.signatures);
+            assert_eq!(loaded.player_to_move(), Some("Kai"));
+        }
+
+        #[test]
+        fn wrap_a_game_that_imports_on_its_own() {
+            let json: serde_json::Value = serde_json::from_str(&started_game().to_json().unwrap()).unwrap();
+
+            let (_, history, _) = import::import_game_from_content(&json["game"].to_string()).unwrap();
+
+            assert_eq!(history.len(), 3);
+        }
+
+        #[test]
+        fn detect_an_altered_move() {
+            let game = started_game();
+            // Black opens from a1 instead of b2; still legal, but not what was signed
+            let content = altered(&game, |json| {
+                json["game"]["moves"] = serde_json::json!([
+                    {"OpeningRemoval": {"color": "Black", "position": {"row": 0, "col": 0}}},
+                    {"OpeningRemoval": {"color": "White", "position": {"row": 0, "col": 1}}},
+                    {"Jump": {"color": "Black", "from": {"row": 2, "col": 0}, "to": {"row": 0, "col": 0}, "captured": [{"row": 1, "col": 0}]}}
+                ]);
+            });
+
+            let err = CorrespondenceGame::from_json(&content).unwrap_err();
+
+            assert_eq!(err, "Move 1 (a1) does not match its signature: the game has been altered");
+        }
+
+        #[test]
+        fn detect_renamed_players() {
+            let content = altered(&started_game(), |json| json["white"] = "Mallory".into());
+
+            let err = CorrespondenceGame::from_json(&content).unwrap_err();
+
+            assert!(err.starts_with("Move 1 (b2) does not match"), "{}", err);
+        }
+
+        #[test]
+        fn detect_removed_moves_and_signatures() {
+            let game = started_game();
+            let fewer_moves = altered(&game, |json| {
+                json["game"]["moves"].as_array_mut().unwrap().pop();
+                json["game"]["total_moves"] = 2.into();
+            });
+            let fewer_signatures = altered(&game, |json| {
+                json["game"]["moves"].as_array_mut().unwrap().pop();
+                json["game"]["total_moves"] = 2.into();
+                json["signatures"].as_array_mut().unwrap().pop();
+            });
+
+            assert_eq!(CorrespondenceGame::from_json(&fewer_moves).unwrap_err(), "The game has 2 moves but 3 signatures");
+            // Dropping the last move with its signature leaves a valid earlier state of the game
+            assert_eq!(CorrespondenceGame::from_json(&fewer_signatures).unwrap().move_history().len(), 2);
+        }
+
+        #[test]
+        fn rejects_newer_versions() {
+            let content = altered(&started_game(), |json| json["correspondence_version"] = 99.into());
+
+            let err = CorrespondenceGame::from_json(&content).unwrap_err();
+
+            assert!(err.starts_with("Unsupported correspondence_version 99"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,616 @@
+//! Board diagrams drawn without a display, for printing positions in articles and game write-ups.
+//!
+//! A diagram is laid out once as a list of shapes, which are then written as SVG or, with the `png` feature, rasterized
+//! to PNG. Both outputs show the same board: row 1 at the bottom, coordinates along the left and bottom edges, an arrow
+//! for the last jump and rings around the stones it captured.
+
+use crate::game::{GameState, MoveRecord, PieceColor, Position};
+
+const LIGHT_SQUARE: Color = Color(222, 184, 135, 255);
+const DARK_SQUARE: Color = Color(205, 160, 105, 255);
+const HOLE: Color = Color(120, 85, 50, 255);
+const BLACK_STONE: Color = Color(25, 25, 25, 255);
+const WHITE_STONE: Color = Color(242, 242, 242, 255);
+const STONE_OUTLINE: Color = Color(60, 60, 60, 255);
+const LABEL: Color = Color(77, 77, 77, 255);
+const LAST_MOVE: Color = Color(30, 110, 230, 200);
+const CAPTURE: Color = Color(220, 40, 40, 255);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Color(u8, u8, u8, u8);
+
+impl Color {
+    fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    fn opacity(self) -> f32 {
+        self.3 as f32 / 255.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Rect { x: f32, y: f32, size: f32, fill: Color },
+    Circle { cx: f32, cy: f32, r: f32, fill: Option<Color>, stroke: Option<(Color, f32)> },
+    Line { x1: f32, y1: f32, x2: f32, y2: f32, color: Color, width: f32 },
+    Polygon { points: Vec<(f32, f32)>, fill: Color },
+    /// Text centered on the point
+    Label { x: f32, y: f32, text: String, size: f32 },
+}
+
+/// Draws a position as an image. Created with `new` and configured with the `with_*` methods.
+pub struct Diagram<'a> {
+    state: &'a GameState,
+    last_move: Option<&'a MoveRecord>,
+    cell_size: f32,
+    coordinates: bool,
+}
+
+impl<'a> Diagram<'a> {
+    pub fn new(state: &'a GameState) -> Self {
+        Self {
+            state,
+            last_move: None,
+            cell_size: 48.0,
+            coordinates: true,
+        }
+    }
+
+    /// Marks the move that led to the position: an arrow for a jump, with its captures ringed, or a ring around the
+    /// square emptied by an opening removal.
+    pub fn with_last_move(mut self, record: &'a MoveRecord) -> Self {
+        self.last_move = Some(record);
+        self
+    }
+
+    /// Sets the width of a square in pixels.
+    pub fn with_cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Shows or hides the file letters and rank numbers.
+    pub fn with_coordinates(mut self, coordinates: bool) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Width and height of the image in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        let board = self.board_size() as f32 * self.cell_size;
+        let side = (board + self.margin() + self.padding()).ceil() as u32;
+        (side, side)
+    }
+
+    pub fn to_svg(&self) -> String {
+        let (width, height) = self.size();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+        );
+        svg.push_str(&format!("  <rect width=\"{width}\" height=\"{height}\" fill=\"#ffffff\"/>\n"));
+        for shape in self.shapes() {
+            svg.push_str("  ");
+            svg.push_str(&shape_to_svg(&shape));
+            svg.push('\n');
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Renders the diagram as a PNG image.
+    #[cfg(feature = "png")]
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let (width, height) = self.size();
+        raster::encode(width, height, &self.to_rgba()?)
+    }
+
+    /// Renders the diagram as rows of RGBA pixels, for use as an animation frame.
+    #[cfg(feature = "png")]
+    pub(crate) fn to_rgba(&self) -> Result<Vec<u8>, String> {
+        let (width, height) = self.size();
+        raster::render(&self.shapes(), width, height)
+    }
+
+    fn board_size(&self) -> usize {
+        self.state.board().size()
+    }
+
+    /// Space left of and below the board for coordinates.
+    fn margin(&self) -> f32 {
+        if self.coordinates { self.cell_size * 0.6 } else { 0.0 }
+    }
+
+    /// Space above and right of the board, so the board isn't flush with the edge.
+    fn padding(&self) -> f32 {
+        self.cell_size * 0.25
+    }
+
+    /// Center of a square. Row 0 is at the bottom of the image.
+    fn center(&self, pos: Position) -> (f32, f32) {
+        let screen_row = self.board_size() - 1 - pos.row;
+        (
+            self.margin() + (pos.col as f32 + 0.5) * self.cell_size,
+            self.padding() + (screen_row as f32 + 0.5) * self.cell_size,
+        )
+    }
+
+    fn shapes(&self) -> Vec<Shape> {
+        let n = self.board_size();
+        let cell = self.cell_size;
+        let mut shapes = Vec::new();
+
+        for row in 0..n {
+            for col in 0..n {
+                let pos = Position::new(row, col);
+                let (cx, cy) = self.center(pos);
+                let fill = if (row + col).is_multiple_of(2) { DARK_SQUARE } else { LIGHT_SQUARE };
+                shapes.push(Shape::Rect {
+                    x: cx - cell / 2.0,
+                    y: cy - cell / 2.0,
+                    size: cell,
+                    fill,
+                });
+                let stone = self.state.board().get_piece_color(pos).map(|color| match color {
+                    PieceColor::Black => BLACK_STONE,
+                    PieceColor::White => WHITE_STONE,
+                });
+                shapes.push(match stone {
+                    Some(fill) => Shape::Circle {
+                        cx,
+                        cy,
+                        r: cell * 0.4,
+                        fill: Some(fill),
+                        stroke: Some((STONE_OUTLINE, (cell * 0.03).max(1.0))),
+                    },
+                    None => Shape::Circle {
+                        cx,
+                        cy,
+                        r: cell * 0.15,
+                        fill: Some(HOLE),
+                        stroke: None,
+                    },
+                });
+            }
+        }
+
+        if self.coordinates {
+            let size = cell * 0.3;
+            for i in 0..n {
+                let (_, y) = self.center(Position::new(i, 0));
+                shapes.push(Shape::Label {
+                    x: self.margin() / 2.0,
+                    y,
+                    text: (i + 1).to_string(),
+                    size,
+                });
+                let (x, _) = self.center(Position::new(0, i));
+                shapes.push(Shape::Label {
+                    x,
+                    y: self.padding() + n as f32 * cell + self.margin() / 2.0,
+                    text: ((b'a' + i as u8) as char).to_string(),
+                    size,
+                });
+            }
+        }
+
+        match self.last_move {
+            Some(MoveRecord::Jump { from, to, captured, .. }) => {
+                for &pos in captured {
+                    let (cx, cy) = self.center(pos);
+                    shapes.push(Shape::Circle {
+                        cx,
+                        cy,
+                        r: cell * 0.4,
+                        fill: None,
+                        stroke: Some((CAPTURE, cell * 0.06)),
+                    });
+                }
+                shapes.extend(self.arrow(*from, *to));
+            }
+            Some(MoveRecord::OpeningRemoval { position, .. }) => {
+                let (cx, cy) = self.center(*position);
+                shapes.push(Shape::Circle {
+                    cx,
+                    cy,
+                    r: cell * 0.4,
+                    fill: None,
+                    stroke: Some((LAST_MOVE, cell * 0.06)),
+                });
+            }
+            None => {}
+        }
+
+        shapes
+    }
+
+    /// An arrow from the center of one square to the center of another, with its head ending at the destination.
+    fn arrow(&self, from: Position, to: Position) -> Vec<Shape> {
+        let (x1, y1) = self.center(from);
+        let (x2, y2) = self.center(to);
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        if length == 0.0 {
+            return Vec::new();
+        }
+        let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+        let head_length = self.cell_size * 0.35;
+        let head_width = self.cell_size * 0.2;
+        let (bx, by) = (x2 - dx * head_length, y2 - dy * head_length);
+
+        vec![
+            Shape::Line {
+                x1,
+                y1,
+                x2: bx,
+                y2: by,
+                color: LAST_MOVE,
+                width: self.cell_size * 0.1,
+            },
+            Shape::Polygon {
+                points: vec![(x2, y2), (bx - dy * head_width, by + dx * head_width), (bx + dy * head_width, by - dx * head_width)],
+                fill: LAST_MOVE,
+            },
+        ]
+    }
+}
+
+fn shape_to_svg(shape: &Shape) -> String {
+    match shape {
+        Shape::Rect { x, y, size, fill } => format!(
+            "<rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{size:.1}\" height=\"{size:.1}\"{}/>",
+            fill_attributes(Some(*fill))
+        ),
+        Shape::Circle { cx, cy, r, fill, stroke } => {
+            let stroke = match stroke {
+                Some((color, width)) => format!(
+                    " stroke=\"{}\" stroke-width=\"{width:.1}\"{}",
+                    color.hex(),
+                    opacity_attribute("stroke-opacity", *color)
+                ),
+                None => String::new(),
+            };
+            format!("<circle cx=\"{cx:.1}\" cy=\"{cy:.1}\" r=\"{r:.1}\"{}{stroke}/>", fill_attributes(*fill))
+        }
+        Shape::Line { x1, y1, x2, y2, color, width } => format!(
+            "<line x1=\"{x1:.1}\" y1=\"{y1:.1}\" x2=\"{x2:.1}\" y2=\"{y2:.1}\" stroke=\"{}\" stroke-width=\"{width:.1}\"{}/>",
+            color.hex(),
+            opacity_attribute("stroke-opacity", *color)
+        ),
+        Shape::Polygon { points, fill } => {
+            let points: Vec<String> = points.iter().map(|(x, y)| format!("{x:.1},{y:.1}")).collect();
+            format!("<polygon points=\"{}\"{}/>", points.join(" "), fill_attributes(Some(*fill)))
+        }
+        Shape::Label { x, y, text, size } => format!(
+            "<text x=\"{x:.1}\" y=\"{y:.1}\" font-family=\"sans-serif\" font-size=\"{size:.1}\" text-anchor=\"middle\" \
+             dominant-baseline=\"central\" fill=\"{}\">{text}</text>",
+            LABEL.hex()
+        ),
+    }
+}
+
+fn fill_attributes(fill: Option<Color>) -> String {
+    match fill {
+        Some(color) => format!(" fill=\"{}\"{}", color.hex(), opacity_attribute("fill-opacity", color)),
+        None => " fill=\"none\"".to_string(),
+    }
+}
+
+fn opacity_attribute(name: &str, color: Color) -> String {
+    if color.3 == 255 { String::new() } else { format!(" {name}=\"{:.2}\"", color.opacity()) }
+}
+
+#[cfg(feature = "png")]
+mod raster {
+    use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Rect, Stroke, Transform};
+
+    use super::{Color, LABEL, Shape};
+
+    /// Glyphs for coordinates, 5 pixels wide and 7 high, one row per byte with the leftmost pixel in bit 4. A raster
+    /// font keeps PNG rendering independent of the fonts installed.
+    const DIGITS: [[u8; 7]; 10] = [
+        [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+    ];
+    const LETTERS: [[u8; 7]; 16] = [
+        [0x00, 0x00, 0x0E, 0x01, 0x0F, 0x11, 0x0F],
+        [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1E],
+        [0x00, 0x00, 0x0E, 0x10, 0x10, 0x11, 0x0E],
+        [0x01, 0x01, 0x0D, 0x13, 0x11, 0x11, 0x0F],
+        [0x00, 0x00, 0x0E, 0x11, 0x1F, 0x10, 0x0E],
+        [0x06, 0x09, 0x08, 0x1C, 0x08, 0x08, 0x08],
+        [0x00, 0x0F, 0x11, 0x11, 0x0F, 0x01, 0x0E],
+        [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11],
+        [0x04, 0x00, 0x0C, 0x04, 0x04, 0x04, 0x0E],
+        [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0C],
+        [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12],
+        [0x0C, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        [0x00, 0x00, 0x1A, 0x15, 0x15, 0x11, 0x11],
+        [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11],
+        [0x00, 0x00, 0x0E, 0x11, 0x11, 0x11, 0x0E],
+        [0x00, 0x00, 0x1E, 0x11, 0x1E, 0x10, 0x10],
+    ];
+
+    /// Draws the shapes on a white background, returning unpremultiplied RGBA pixels.
+    pub fn render(shapes: &[Shape], width: u32, height: u32) -> Result<Vec<u8>, String> {
+        let mut pixmap = Pixmap::new(width, height).ok_or_else(|| format!("Invalid image size {}x{}", width, height))?;
+        pixmap.fill(tiny_skia::Color::WHITE);
+        for shape in shapes {
+            draw(&mut pixmap, shape);
+        }
+
+        let mut data = Vec::with_capacity(pixmap.data().len());
+        for pixel in pixmap.pixels() {
+            let color = pixel.demultiply();
+            data.extend_from_slice(&[color.red(), color.green(), color.blue(), color.alpha()]);
+        }
+        Ok(data)
+    }
+
+    fn paint(color: Color) -> Paint<'static> {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(color.0, color.1, color.2, color.3);
+        paint.anti_alias = true;
+        paint
+    }
+
+    fn draw(pixmap: &mut Pixmap, shape: &Shape) {
+        match shape {
+            Shape::Rect { x, y, size, fill } => {
+                if let Some(rect) = Rect::from_xywh(*x, *y, *size, *size) {
+                    pixmap.fill_rect(rect, &paint(*fill), Transform::identity(), None);
+                }
+            }
+            Shape::Circle { cx, cy, r, fill, stroke } => {
+                let Some(path) = PathBuilder::from_circle(*cx, *cy, *r) else {
+                    return;
+                };
+                if let Some(fill) = fill {
+                    pixmap.fill_path(&path, &paint(*fill), FillRule::Winding, Transform::identity(), None);
+                }
+                if let Some((color, width)) = stroke {
+                    let stroke = Stroke {
+                        width: *width,
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(&path, &paint(*color), &stroke, Transform::identity(), None);
+                }
+            }
+            Shape::Line { x1, y1, x2, y2, color, width } => {
+                let mut builder = PathBuilder::new();
+                builder.move_to(*x1, *y1);
+                builder.line_to(*x2, *y2);
+                if let Some(path) = builder.finish() {
+                    let stroke = Stroke {
+                        width: *width,
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(&path, &paint(*color), &stroke, Transform::identity(), None);
+                }
+            }
+            Shape::Polygon { points, fill } => {
+                let mut builder = PathBuilder::new();
+                for (i, &(x, y)) in points.iter().enumerate() {
+                    if i == 0 {
+                        builder.move_to(x, y);
+                    } else {
+                        builder.line_to(x, y);
+                    }
+                }
+                builder.close();
+                if let Some(path) = builder.finish() {
+                    pixmap.fill_path(&path, &paint(*fill), FillRule::Winding, Transform::identity(), None);
+                }
+            }
+            Shape::Label { x, y, text, size } => draw_label(pixmap, *x, *y, text, *size),
+        }
+    }
+
+    /// Draws text from the raster font, scaled so that a glyph is `size` pixels high.
+    fn draw_label(pixmap: &mut Pixmap, x: f32, y: f32, text: &str, size: f32) {
+        let pixel = size / 7.0;
+        let glyphs: Vec<&[u8; 7]> = text
+            .chars()
+            .filter_map(|c| match c {
+                '0'..='9' => Some(&DIGITS[c as usize - '0' as usize]),
+                'a'..='p' => Some(&LETTERS[c as usize - 'a' as usize]),
+                _ => None,
+            })
+            .collect();
+        let width = (glyphs.len() * 6).saturating_sub(1) as f32 * pixel;
+        let left = x - width / 2.0;
+        let top = y - size / 2.0;
+        let paint = paint(LABEL);
+
+        for (i, glyph) in glyphs.iter().enumerate() {
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..5 {
+                    if bits & (0x10 >> col) != 0
+                        && let Some(rect) = Rect::from_xywh(
+                            left + (i * 6 + col) as f32 * pixel,
+                            top + row as f32 * pixel,
+                            pixel,
+                            pixel,
+                        )
+                    {
+                        pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn encode(width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|err| format!("Failed to encode PNG: {}", err))?;
+        writer.write_image_data(data).map_err(|err| format!("Failed to encode PNG: {}", err))?;
+        writer.finish().map_err(|err| format!("Failed to encode PNG: {}", err))?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Rules;
+
+    /// The 4x4 opening b2, c2, then Black's b4-b2 capturing b3.
+    fn after_first_jump() -> (GameState, MoveRecord) {
+        let mut state = GameState::new(4, PieceColor::Black);
+        Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap();
+        Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap();
+        let jump = Rules::all_valid_jumps(&state).remove(0);
+        let record = Rules::apply_jump(&mut state, &jump);
+        (state, record)
+    }
+
+    fn count(shapes: &[Shape], predicate: impl Fn(&Shape) -> bool) -> usize {
+        shapes.iter().filter(|shape| predicate(shape)).count()
+    }
+
+    mod layout {
+        use super::*;
+
+        #[test]
+        fn draws_a_square_and_a_stone_or_hole_per_cell() {
+            let state = GameState::new(6, PieceColor::Black);
+
+            let shapes = Diagram::new(&state).shapes();
+
+            assert_eq!(count(&shapes, |s| matches!(s, Shape::Rect { .. })), 36);
+            assert_eq!(count(&shapes, |s| matches!(s, Shape::Circle { fill: Some(BLACK_STONE), .. })), 18);
+            assert_eq!(count(&shapes, |s| matches!(s, Shape::Circle { fill: Some(WHITE_STONE), .. })), 18);
+        }
+
+        #[test]
+        fn draws_holes_for_empty_squares() {
+            let (state, _) = after_first_jump();
+
+            let shapes = Diagram::new(&state).shapes();
+
+            assert_eq!(count(&shapes, |s| matches!(s, Shape::Circle { fill: Some(HOLE), .. })), 3);
+        }
+
+        #[test]
+        fn row_one_is_at_the_bottom() {
+            let state = GameState::new(4, PieceColor::Black);
+            let diagram = Diagram::new(&state);
+
+            let (_, bottom) = diagram.center(Position::new(0, 0));
+            let (_, top) = diagram.center(Position::new(3, 0));
+
+            assert!(bottom > top);
+        }
+
+        #[test]
+        fn labels_files_and_ranks() {
+            let state = GameState::new(4, PieceColor::Black);
+
+            let shapes = Diagram::new(&state).shapes();
+            let labels: Vec<&str> = shapes
+                .iter()
+                .filter_map(|s| match s {
+                    Shape::Label { text, .. } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+
+            assert_eq!(labels, ["1", "a", "2", "b", "3", "c", "4", "d"]);
+        }
+
+        #[test]
+        fn coordinates_can_be_hidden() {
+            let state = GameState::new(4, PieceColor::Black);
+            let diagram = Diagram::new(&state).with_coordinates(false).with_cell_size(10.0);
+
+            assert_eq!(count(&diagram.shapes(), |s| matches!(s, Shape::Label { .. })), 0);
+            assert_eq!(diagram.size(), (43, 43));
+        }
+    }
+
+    mod last_move {
+        use super::*;
+
+        #[test]
+        fn jump_draws_an_arrow_and_rings_captures() {
+            let (state, record) = after_first_jump();
+
+            let shapes = Diagram::new(&state).with_last_move(&record).shapes();
+
+            assert_eq!(count(&shapes, |s| matches!(s, Shape::Line { .. })), 1);
+            assert_eq!(count(&shapes, |s| matches!(s, Shape::Polygon { .. })), 1);
+            assert_eq!(count(&shapes, |s| matches!(s, Shape::Circle { stroke: Some((CAPTURE, _)), .. })), 1);
+        }
+
+        #[test]
+        fn arrow_points_at_the_destination() {
+            let (state, record) = after_first_jump();
+            let diagram = Diagram::new(&state).with_last_move(&record);
+
+            let shapes = diagram.shapes();
+            let tip = shapes.iter().find_map(|s| match s {
+                Shape::Polygon { points, .. } => Some(points[0]),
+                _ => None,
+            });
+
+            assert_eq!(tip, Some(diagram.center(Position::new(1, 1))));
+        }
+
+        #[test]
+        fn removal_rings_the_emptied_square() {
+            let mut state = GameState::new(4, PieceColor::Black);
+            let record = Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap();
+
+            let shapes = Diagram::new(&state).with_last_move(&record).shapes();
+
+            assert_eq!(count(&shapes, |s| matches!(s, Shape::Circle { stroke: Some((LAST_MOVE, _)), .. })), 1);
+            assert_eq!(count(&shapes, |s| matches!(s, Shape::Line { .. })), 0);
+        }
+    }
+
+    mod svg {
+        use super::*;
+
+        #[test]
+        fn writes_a_complete_document() {
+            let (state, record) = after_first_jump();
+
+            let svg = Diagram::new(&state).with_last_move(&record).to_svg();
+
+            assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+            assert!(svg.trim_end().ends_with("</svg>"));
+            assert_eq!(svg.matches("<rect ").count(), 17);
+            assert!(svg.contains(">a</text>"));
+            assert!(svg.contains("<polygon "));
+            assert!(svg.contains("stroke-opacity=\"0.78\""));
+        }
+    }
+
+    #[cfg(feature = "png")]
+    mod png_output {
+        use super::*;
+
+        #[test]
+        fn writes_a_png_of_the_diagram_size() {
+            let (state, record) = after_first_jump();
+            let diagram = Diagram::new(&state).with_last_move(&record);
+
+            let bytes = diagram.to_png().unwrap();
+
+            assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+            let (width, height) = diagram.size();
+            assert_eq!(u32::from_be_bytes(bytes[16..20].try_into().unwrap()), width);
+            assert_eq!(u32::from_be_bytes(bytes[20..24].try_into().unwrap()), height);
+        }
+    }
+}
@@ -0,0 +1,468 @@
+//! A line-based engine protocol, so that other programs can use the AI.
+//!
+//! The engine reads one command per line and writes one response per line, in the style of UCI for chess. Squares
+//! and moves use the algebraic notation of the game formats. Commands:
+//!
+//! - `konane`: identify the engine. Replies `id name <name> <version>`, then `konaneok`.
+//! - `isready`: replies `readyok`, even while searching.
+//! - `newgame <size>`: start a new game on an even board from 4 to 16 (8 if omitted) and forget earlier searches.
+//! - `position [moves <move>...]`: set the position to the current game's start followed by the moves, such as
+//!   `position moves d4 e4 b4-d4`. The word `moves` may be left out.
+//! - `position board <cells> <black|white>`: set the position from the squares of the board, row by row from rank 1,
+//!   with `b` for a black stone, `w` for a white one and `.` for an empty square, and the player to move. The board
+//!   must be the size of the current game. Used by programs that do not keep the moves, such as the external engine
+//!   player.
+//! - `go [depth <n>] [movetime <ms>] [infinite]`: search the position. The search deepens up to the depth (the
+//!   engine's default depth if omitted), stopping early after `movetime` milliseconds or when `stop` is received;
+//!   `infinite` searches until `stop`. When it ends, the engine writes an `info depth <d> nodes <n> time <ms> move
+//!   <move>` line for each completed iteration, then `bestmove <move>`, or `bestmove none` if there are no legal moves.
+//! - `stop`: end the search early. The best move of the deepest completed iteration is returned.
+//! - `moves`: replies `moves` followed by the legal moves.
+//! - `board`: prints the board as text, followed by a line naming the player to move.
+//! - `quit`: stop the engine.
+//!
+//! Errors are reported as a line starting with `error`, and unknown commands are reported rather than ignored. A
+//! search runs on a worker thread, so commands are read while it runs.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::game::player::PlayerMove;
+use crate::game::{AiPlayer, Board, GamePhase, GameState, PieceColor, Rules};
+use crate::import::import_move_list;
+use crate::tui::render_board;
+
+/// Depth searched by `go infinite` and `go movetime`, deep enough that the search ends by time or `stop`.
+pub(crate) const UNLIMITED_DEPTH: i32 = 64;
+
+enum Request {
+    Search { state: GameState, depth: i32 },
+    /// Replace the AI, forgetting its tables.
+    Reset,
+    Shutdown,
+}
+
+/// Runs the protocol. Responses are sent on the channel given to `new`, one line per message.
+pub struct Engine {
+    board_size: usize,
+    state: GameState,
+    default_depth: i32,
+    requests: Sender<Request>,
+    output: Sender<String>,
+    stop: Arc<AtomicBool>,
+    searching: Arc<AtomicBool>,
+    /// Incremented for each search, so that a `movetime` timer only stops the search it was started for
+    generation: Arc<AtomicU64>,
+}
+
+impl Engine {
+    /// Starts the engine. The AI is created on a worker thread by `make_ai`, and again by `newgame`; `default_depth`
+    /// is searched by `go` without a depth.
+    pub fn new<F>(make_ai: F, default_depth: i32, output: Sender<String>) -> Self
+    where
+        F: Fn() -> AiPlayer + Send + 'static,
+    {
+        let (requests, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let searching = Arc::new(AtomicBool::new(false));
+
+        let worker_output = output.clone();
+        let worker_stop = stop.clone();
+        let worker_searching = searching.clone();
+        thread::spawn(move || run_worker(make_ai, receiver, &worker_output, &worker_stop, &worker_searching));
+
+        Self {
+            board_size: 8,
+            state: GameState::new(8, PieceColor::Black),
+            default_depth,
+            requests,
+            output,
+            stop,
+            searching,
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn send(&self, line: String) {
+        let _ = self.output.send(line);
+    }
+
+    /// Carries out one command. Returns false after `quit`.
+    pub fn execute(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return true;
+        };
+        let arguments: Vec<&str> = words.collect();
+
+        let result = match command {
+            "konane" => {
+                self.send(format!("id name {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
+                self.send("konaneok".to_string());
+                Ok(())
+            }
+            "isready" => {
+                self.send("readyok".to_string());
+                Ok(())
+            }
+            "newgame" => self.new_game(&arguments),
+            "position" => self.set_position(&arguments),
+            "go" => self.go(&arguments),
+            "stop" => {
+                self.stop.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+            "moves" => {
+                let moves: Vec<String> = Rules::legal_moves(&self.state).iter().map(PlayerMove::to_algebraic).collect();
+                self.send(format!("moves {}", moves.join(" ")).trim_end().to_string());
+                Ok(())
+            }
+            "board" => {
+                for row in render_board(self.state.board()).lines() {
+                    self.send(row.to_string());
+                }
+                let status = match self.state.current_phase() {
+                    GamePhase::GameOver { winner } => format!("{} wins", winner),
+                    _ => format!("{} to move", self.state.current_player()),
+                };
+                self.send(status);
+                Ok(())
+            }
+            "quit" => return false,
+            _ => Err(format!("unknown command {}", command)),
+        };
+        if let Err(error) = result {
+            self.send(format!("error {}", error));
+        }
+        true
+    }
+
+    /// True from `go` until the best move has been sent.
+    pub fn is_searching(&self) -> bool {
+        self.searching.load(Ordering::SeqCst)
+    }
+
+    fn ensure_idle(&self) -> Result<(), String> {
+        if self.is_searching() {
+            return Err("searching; send stop first".to_string());
+        }
+        Ok(())
+    }
+
+    fn new_game(&mut self, arguments: &[&str]) -> Result<(), String> {
+        self.ensure_idle()?;
+        let size: usize = match arguments {
+            [] => 8,
+            [size] => size.parse().map_err(|_| format!("invalid board size {}", size))?,
+            _ => return Err("usage: newgame <size>".to_string()),
+        };
+        if !(4..=16).contains(&size) || !size.is_multiple_of(2) {
+            return Err(format!("invalid board size {}", size));
+        }
+        self.board_size = size;
+        self.state = GameState::new(size, PieceColor::Black);
+        let _ = self.requests.send(Request::Reset);
+        Ok(())
+    }
+
+    fn set_position(&mut self, arguments: &[&str]) -> Result<(), String> {
+        self.ensure_idle()?;
+        if let ["board", cells, player] = arguments {
+            return self.set_board(cells, player);
+        }
+        let moves = match arguments {
+            ["board", ..] => return Err("usage: position board <cells> <black|white>".to_string()),
+            ["moves", moves @ ..] | moves => moves.join(" "),
+        };
+        let (state, _, _) = import_move_list(self.board_size, &moves)?;
+        self.state = state;
+        Ok(())
+    }
+
+    fn set_board(&mut self, cells: &str, player: &str) -> Result<(), String> {
+        let board = Board::from_cells(cells)?;
+        if board.size() != self.board_size {
+            let (size, expected) = (board.size(), self.board_size);
+            return Err(format!("board is {}x{} but the game is {}x{}", size, size, expected, expected));
+        }
+        let player = match player {
+            "black" => PieceColor::Black,
+            "white" => PieceColor::White,
+            _ => return Err(format!("invalid player {}", player)),
+        };
+        self.state = GameState::from_board(board, player)?;
+        Ok(())
+    }
+
+    fn go(&mut self, arguments: &[&str]) -> Result<(), String> {
+        self.ensure_idle()?;
+        let mut depth = None;
+        let mut movetime = None;
+        let mut infinite = false;
+        let mut words = arguments.iter();
+        while let Some(&word) = words.next() {
+            let mut value = |name: &str| {
+                words
+                    .next()
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .ok_or_else(|| format!("go {} needs a number", name))
+            };
+            match word {
+                "depth" => depth = Some(value("depth")?.clamp(1, UNLIMITED_DEPTH as u64) as i32),
+                "movetime" => movetime = Some(Duration::from_millis(value("movetime")?)),
+                "infinite" => infinite = true,
+                _ => return Err(format!("unknown go option {}", word)),
+            }
+        }
+        let depth = match depth {
+            Some(depth) => depth,
+            None if infinite || movetime.is_some() => UNLIMITED_DEPTH,
+            None => self.default_depth,
+        };
+
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.stop.store(false, Ordering::SeqCst);
+        self.searching.store(true, Ordering::SeqCst);
+        let _ = self.requests.send(Request::Search {
+            state: self.state.clone(),
+            depth,
+        });
+
+        if let Some(movetime) = movetime {
+            let stop = self.stop.clone();
+            let current = self.generation.clone();
+            thread::spawn(move || {
+                thread::sleep(movetime);
+                if current.load(Ordering::SeqCst) == generation {
+                    stop.store(true, Ordering::SeqCst);
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = self.requests.send(Request::Shutdown);
+    }
+}
+
+fn run_worker<F>(make_ai: F, requests: Receiver<Request>, output: &Sender<String>, stop: &AtomicBool, searching: &AtomicBool)
+where
+    F: Fn() -> AiPlayer,
+{
+    let mut ai = make_ai();
+    while let Ok(request) = requests.recv() {
+        match request {
+            Request::Search { state, depth } => {
+                let (best_move, report) = ai.search_to_depth(&state, depth, stop);
+                for iteration in &report.iterations {
+                    let _ = output.send(format!(
+                        "info depth {} nodes {} time {} move {}",
+                        iteration.depth,
+                        iteration.nodes,
+                        iteration.elapsed_ms,
+                        iteration.best_move.as_deref().unwrap_or("none")
+                    ));
+                }
+                // Cleared before the best move is sent, so a client may send the next command as soon as it arrives
+                searching.store(false, Ordering::SeqCst);
+                let best_move = best_move.map_or_else(|| "none".to_string(), |best| best.to_algebraic());
+                let _ = output.send(format!("bestmove {}", best_move));
+            }
+            Request::Reset => ai = make_ai(),
+            Request::Shutdown => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    fn engine(depth: i32) -> (Engine, Receiver<String>) {
+        let (output, lines) = mpsc::channel();
+        (Engine::new(move || AiPlayer::new(PieceColor::Black, depth), depth, output), lines)
+    }
+
+    /// Receives lines until one starts with the prefix, returning all of them.
+    fn read_until(lines: &Receiver<String>, prefix: &str) -> Vec<String> {
+        let mut received = Vec::new();
+        loop {
+            let line = lines.recv_timeout(TIMEOUT).expect("engine did not reply");
+            let done = line.starts_with(prefix);
+            received.push(line);
+            if done {
+                return received;
+            }
+        }
+    }
+
+    mod commands {
+        use super::*;
+
+        #[test]
+        fn identifies_itself() {
+            let (mut engine, lines) = engine(2);
+
+            engine.execute("konane");
+
+            let reply = read_until(&lines, "konaneok");
+            assert!(reply[0].starts_with("id name konane "));
+        }
+
+        #[test]
+        fn answers_isready() {
+            let (mut engine, lines) = engine(2);
+
+            engine.execute("isready");
+
+            assert_eq!(lines.recv_timeout(TIMEOUT).unwrap(), "readyok");
+        }
+
+        #[test]
+        fn lists_legal_moves_after_position() {
+            let (mut engine, lines) = engine(2);
+
+            engine.execute("newgame 4");
+            engine.execute("position moves b2 c2 b4-b2");
+            engine.execute("moves");
+
+            assert_eq!(lines.recv_timeout(TIMEOUT).unwrap(), "moves b1-b3 a2-c2 d3-b3 c4-c2");
+        }
+
+        #[test]
+        fn sets_the_position_from_a_board() {
+            let (mut engine, lines) = engine(2);
+            engine.execute("newgame 4");
+
+            engine.execute("position board bwbww.wbbwbwwbwb white");
+            engine.execute("moves");
+
+            assert_eq!(lines.recv_timeout(TIMEOUT).unwrap(), "moves b3 b1 a2 c2");
+        }
+
+        #[test]
+        fn rejects_a_board_of_the_wrong_size() {
+            let (mut engine, lines) = engine(2);
+
+            engine.execute("position board bwbww.wbbwbwwbwb white");
+
+            assert_eq!(lines.recv_timeout(TIMEOUT).unwrap(), "error board is 4x4 but the game is 8x8");
+        }
+
+        #[test]
+        fn prints_the_board() {
+            let (mut engine, lines) = engine(2);
+
+            engine.execute("newgame 4");
+            engine.execute("position b2");
+            engine.execute("board");
+
+            let board = read_until(&lines, "White");
+            assert_eq!(board[3], " 2 W . W B 2");
+            assert_eq!(board.last().unwrap(), "White to move");
+        }
+
+        #[test]
+        fn reports_errors() {
+            let (mut engine, lines) = engine(2);
+
+            engine.execute("newgame 5");
+            engine.execute("position z9");
+            engine.execute("fly");
+
+            assert_eq!(lines.recv_timeout(TIMEOUT).unwrap(), "error invalid board size 5");
+            assert!(lines.recv_timeout(TIMEOUT).unwrap().starts_with("error Move 1 (z9): "));
+            assert_eq!(lines.recv_timeout(TIMEOUT).unwrap(), "error unknown command fly");
+        }
+
+        #[test]
+        fn quit_ends_the_session() {
+            let (mut engine, _lines) = engine(2);
+
+            assert!(engine.execute(""));
+            assert!(!engine.execute("quit"));
+        }
+    }
+
+    mod search {
+        use super::*;
+
+        #[test]
+        fn go_depth_reports_each_iteration_and_a_legal_move() {
+            let (mut engine, lines) = engine(8);
+            engine.execute("newgame 4");
+
+            engine.execute("go depth 3");
+
+            let reply = read_until(&lines, "bestmove");
+            assert_eq!(reply.len(), 4);
+            assert!(reply[2].starts_with("info depth 3 nodes "));
+            let best = reply[3].strip_prefix("bestmove ").unwrap();
+            assert!(["b2", "c3", "a1", "d4"].contains(&best), "{}", best);
+        }
+
+        #[test]
+        fn stop_ends_an_infinite_search() {
+            let (mut engine, lines) = engine(8);
+            engine.execute("newgame 8");
+            engine.execute("position d4 e4");
+
+            engine.execute("go infinite");
+            thread::sleep(Duration::from_millis(50));
+            engine.execute("stop");
+
+            let reply = read_until(&lines, "bestmove");
+            assert_ne!(reply.last().unwrap(), "bestmove none");
+        }
+
+        #[test]
+        fn movetime_ends_the_search() {
+            let (mut engine, lines) = engine(8);
+            engine.execute("position d4 e4");
+
+            engine.execute("go movetime 50");
+
+            let reply = read_until(&lines, "bestmove");
+            assert_ne!(reply.last().unwrap(), "bestmove none");
+        }
+
+        #[test]
+        fn rejects_commands_that_change_the_position_while_searching() {
+            let (mut engine, lines) = engine(8);
+            engine.execute("position d4 e4");
+
+            engine.execute("go infinite");
+            engine.execute("position d4");
+            engine.execute("stop");
+
+            assert_eq!(lines.recv_timeout(TIMEOUT).unwrap(), "error searching; send stop first");
+            read_until(&lines, "bestmove");
+        }
+
+        #[test]
+        fn reports_no_move_when_the_game_is_over() {
+            let black = AiPlayer::new(PieceColor::Black, 1);
+            let white = AiPlayer::new(PieceColor::White, 1);
+            let (_, history) = crate::game::ai::play_out(GameState::new(4, PieceColor::Black), &black, &white).unwrap();
+            let moves: Vec<String> = history.iter().map(|record| record.to_algebraic()).collect();
+            let (mut engine, lines) = engine(2);
+            engine.execute("newgame 4");
+            engine.execute(&format!("position moves {}", moves.join(" ")));
+
+            engine.execute("moves");
+            engine.execute("go");
+
+            assert_eq!(lines.recv_timeout(TIMEOUT).unwrap(), "moves");
+            assert_eq!(read_until(&lines, "bestmove").last().unwrap(), "bestmove none");
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Writing games in the JSON and text formats read by `import`.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::game::{MoveHistory, PieceColor, TimeControl};
+use crate::import::FORMAT_VERSION;
+use crate::pgn::result_code;
+
+#[derive(Serialize)]
+struct GameLog<'a> {
+    format_version: u32,
+    board_size: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    winner: Option<String>,
+    total_moves: usize,
+    moves: &'a MoveHistory,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_control: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clocks: Option<Vec<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lost_on_time: Option<String>,
+}
+
+/// The clocks of a game played with a time control.
+#[derive(Debug, Clone)]
+pub struct ClockLog {
+    pub time_control: TimeControl,
+    /// Time left on the mover's clock after each move
+    pub times: Vec<Duration>,
+    /// The player whose clock ran out, if the game ended that way
+    pub lost_on_time: Option<PieceColor>,
+}
+
+impl<'a> GameLog<'a> {
+    fn new(board_size: usize, winner: Option<PieceColor>, history: &'a MoveHistory) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            board_size,
+            winner: winner.map(|color| color.to_string()),
+            total_moves: history.len(),
+            moves: history,
+            time_control: None,
+            clocks: None,
+            lost_on_time: None,
+        }
+    }
+
+    fn with_clock(mut self, clock: &ClockLog) -> Self {
+        self.time_control = Some(clock.time_control.to_string());
+        self.clocks = Some(clock.times.iter().map(|time| time.as_millis() as u64).collect());
+        self.lost_on_time = clock.lost_on_time.map(|color| color.to_string());
+        self
+    }
+}
+
+/// Writes a game as indented JSON. The winner is omitted for unfinished games.
+pub fn game_to_json(board_size: usize, winner: Option<PieceColor>, history: &MoveHistory) -> Result<String, String> {
+    serde_json::to_string_pretty(&GameLog::new(board_size, winner, history))
+        .map_err(|err| format!("Failed to serialize game: {}", err))
+}
+
+/// Writes a game played with a time control as indented JSON, with the time left after each move. For a game lost
+/// on time, `winner` is the opponent of the player whose clock ran out.
+pub fn timed_game_to_json(
+    board_size: usize,
+    winner: Option<PieceColor>,
+    history: &MoveHistory,
+    clock: &ClockLog,
+) -> Result<String, String> {
+    serde_json::to_string_pretty(&GameLog::new(board_size, winner, history).with_clock(clock))
+        .map_err(|err| format!("Failed to serialize game: {}", err))
+}
+
+/// Writes a game as JSON on a single line, as used in JSON Lines archives.
+pub fn game_to_json_line(board_size: usize, winner: Option<PieceColor>, history: &MoveHistory) -> Result<String, String> {
+    serde_json::to_string(&GameLog::new(board_size, winner, history)).map_err(|err| format!("Failed to serialize game: {}", err))
+}
+
+/// Writes a game in the text format: the board size, one numbered move per line and the result code.
+pub fn game_to_text(board_size: usize, winner: Option<PieceColor>, history: &MoveHistory) -> String {
+    let mut log = format!("Board size: {}\n", board_size);
+    for (i, move_record) in history.iter().enumerate() {
+        log.push_str(&format!("{}. {}\n", i + 1, move_record.to_algebraic()));
+    }
+    log.push_str(result_code(winner));
+    log.push('\n');
+    log
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, Position, Rules};
+    use crate::import::{import_game_from_content, import_text_game_from_content};
+
+    fn opening_history() -> MoveHistory {
+        let mut state = GameState::new(4, PieceColor::Black);
+        vec![
+            Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap(),
+            Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn text_can_be_imported() {
+        let text = game_to_text(4, None, &opening_history());
+
+        assert_eq!(text, "Board size: 4\n1. b2\n2. c2\n*\n");
+        let (_, history, _) = import_text_game_from_content(&text).unwrap();
+        assert_eq!(history, opening_history());
+    }
+
+    #[test]
+    fn json_can_be_imported() {
+        let json = game_to_json(4, None, &opening_history()).unwrap();
+        let (_, history, _) = import_game_from_content(&json).unwrap();
+        assert_eq!(history, opening_history());
+    }
+
+    #[test]
+    fn writes_current_format_version() {
+        let json = game_to_json_line(4, None, &opening_history()).unwrap();
+        assert!(json.starts_with(&format!("{{\"format_version\":{},", FORMAT_VERSION)));
+    }
+
+    #[test]
+    fn omits_winner_of_unfinished_game() {
+        let json = game_to_json_line(4, None, &opening_history()).unwrap();
+        assert!(!json.contains("winner"));
+        assert!(!json.contains('\n'));
+    }
+
+    #[test]
+    fn timed_game_records_the_clocks() {
+        let clock = ClockLog {
+            time_control: TimeControl::PerMove(Duration::from_secs(30)),
+            times: vec![Duration::from_millis(28_500), Duration::from_secs(12)],
+            lost_on_time: Some(PieceColor::Black),
+        };
+
+        let json = timed_game_to_json(4, Some(PieceColor::White), &opening_history(), &clock).unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["time_control"], "30s");
+        assert_eq!(value["clocks"], serde_json::json!([28500, 12000]));
+        assert_eq!(value["lost_on_time"], "Black");
+        let (state, history, _) = import_game_from_content(&json).unwrap();
+        assert_eq!(history, opening_history());
+        assert_eq!(state.current_player(), PieceColor::Black);
+    }
+
+    #[test]
+    fn untimed_game_has_no_clock_fields() {
+        let json = game_to_json_line(4, None, &opening_history()).unwrap();
+        assert!(!json.contains("clocks") && !json.contains("time_control"));
+    }
+
+    #[test]
+    fn includes_winner_and_move_count() {
+        let json = game_to_json_line(4, Some(PieceColor::White), &opening_history()).unwrap();
+        assert!(json.contains("\"winner\":\"White\""));
+        assert!(json.contains("\"total_moves\":2"));
+    }
+}
@@ -1,12 +1,24 @@
 use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use game_player::minimax::{ResponseGenerator, search};
 use game_player::{PlayerId, State, StaticEvaluator, TranspositionTable};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
 
+use crate::game::book::OpeningBook;
+use crate::game::ordering::{MoveKey, MoveOrdering};
 use crate::game::player::{Player, PlayerInput, PlayerMove};
+use crate::game::report::{SearchReport, SearchTracker};
 use crate::game::rules::{Jump, Rules};
-use crate::game::state::{GamePhase, GameState, PieceColor, Position};
+use crate::game::state::{GamePhase, GameState, MoveHistory, PieceColor, Position};
 
 #[derive(Debug, Clone)]
 pub enum KonaneAction {
@@ -54,29 +66,30 @@ impl State for KonaneState {
     }
 }
 
-pub struct KonaneEvaluator;
+/// Weights of the terms of the evaluation, from the point of view of the AI's own color. The default weighs the two
+/// sides' mobility equally.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EvalWeights {
+    /// Value of each of the AI's own legal jumps
+    pub mobility: f32,
+    /// Cost of each of the opponent's legal jumps
+    pub opponent_mobility: f32,
+}
 
-impl StaticEvaluator<KonaneState> for KonaneEvaluator {
-    fn evaluate(&self, state: &KonaneState) -> f32 {
-        if let GamePhase::GameOver { winner } = state.inner.current_phase() {
-            return if winner == PieceColor::Black {
-                self.alice_wins_value()
-            } else {
-                self.bob_wins_value()
-            };
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self {
+            mobility: 1.0,
+            opponent_mobility: 1.0,
         }
+    }
+}
 
-        // Mobility heuristic: count valid moves for each player
-        let black_mobility = count_mobility_for(&state.inner, PieceColor::Black);
-        if state.inner.current_player() == PieceColor::Black && black_mobility == 0 {
-            return self.bob_wins_value();
-        }
-        let white_mobility = count_mobility_for(&state.inner, PieceColor::White);
-        if state.inner.current_player() == PieceColor::White && white_mobility == 0 {
-            return self.alice_wins_value();
-        }
+pub struct KonaneEvaluator;
 
-        (black_mobility - white_mobility) as f32
+impl StaticEvaluator<KonaneState> for KonaneEvaluator {
+    fn evaluate(&self, state: &KonaneState) -> f32 {
+        evaluate_mobility(state, PieceColor::Black, &EvalWeights::default())
     }
 
     fn alice_wins_value(&self) -> f32 {
@@ -88,6 +101,38 @@ impl StaticEvaluator<KonaneState> for KonaneEvaluator {
     }
 }
 
+/// Scores a position by the number of jumps each side has, weighted from `color`'s point of view. Positive scores
+/// favor Black, as the search expects.
+fn evaluate_mobility(state: &KonaneState, color: PieceColor, weights: &EvalWeights) -> f32 {
+    if let GamePhase::GameOver { winner } = state.inner.current_phase() {
+        return if winner == PieceColor::Black {
+            KonaneEvaluator.alice_wins_value()
+        } else {
+            KonaneEvaluator.bob_wins_value()
+        };
+    }
+
+    // Mobility heuristic: count valid moves for each player
+    let black_mobility = count_mobility_for(&state.inner, PieceColor::Black);
+    if state.inner.current_player() == PieceColor::Black && black_mobility == 0 {
+        return KonaneEvaluator.bob_wins_value();
+    }
+    let white_mobility = count_mobility_for(&state.inner, PieceColor::White);
+    if state.inner.current_player() == PieceColor::White && white_mobility == 0 {
+        return KonaneEvaluator.alice_wins_value();
+    }
+
+    let (own, opponent) = match color {
+        PieceColor::Black => (black_mobility, white_mobility),
+        PieceColor::White => (white_mobility, black_mobility),
+    };
+    let score = weights.mobility * own as f32 - weights.opponent_mobility * opponent as f32;
+    match color {
+        PieceColor::Black => score,
+        PieceColor::White => -score,
+    }
+}
+
 fn count_mobility_for(state: &GameState, color: PieceColor) -> i32 {
     let mut temp_state = state.clone();
     temp_state.set_current_player(color);
@@ -98,15 +143,26 @@ fn count_mobility_for(state: &GameState, color: PieceColor) -> i32 {
     }
 }
 
-pub struct KonaneMoveGenerator;
+/// Generates the children of a position. Without move ordering, children are returned in board-scan order.
+#[derive(Default)]
+pub struct KonaneMoveGenerator {
+    ordering: Option<Rc<RefCell<MoveOrdering>>>,
+}
+
+impl KonaneMoveGenerator {
+    /// Returns a generator that orders children best first using the given tables.
+    pub fn with_ordering(ordering: Rc<RefCell<MoveOrdering>>) -> Self {
+        Self { ordering: Some(ordering) }
+    }
+}
 
 impl ResponseGenerator for KonaneMoveGenerator {
     type State = KonaneState;
 
-    fn generate(&self, state: &Rc<Self::State>, _depth: i32) -> Vec<Box<Self::State>> {
+    fn generate(&self, state: &Rc<Self::State>, depth: i32) -> Vec<Box<Self::State>> {
         let inner = &state.inner;
 
-        match inner.current_phase() {
+        let mut children: Vec<Box<Self::State>> = match inner.current_phase() {
             GamePhase::OpeningBlackRemoval => Rules::valid_black_opening_removals(inner)
                 .into_iter()
                 .map(|pos| {
@@ -129,38 +185,268 @@ impl ResponseGenerator for KonaneMoveGenerator {
                 })
                 .collect(),
             _ => Vec::new(),
+        };
+
+        if let Some(ref ordering) = self.ordering {
+            ordering.borrow().sort(inner, depth, &mut children);
+        }
+        children
+    }
+}
+
+/// Evaluator that records each evaluated node with a tracker.
+struct TrackingEvaluator<'a> {
+    color: PieceColor,
+    weights: EvalWeights,
+    tracker: &'a RefCell<SearchTracker>,
+}
+
+impl StaticEvaluator<KonaneState> for TrackingEvaluator<'_> {
+    fn evaluate(&self, state: &KonaneState) -> f32 {
+        self.tracker.borrow_mut().visit(state.fingerprint());
+        evaluate_mobility(state, self.color, &self.weights)
+    }
+
+    fn alice_wins_value(&self) -> f32 {
+        KonaneEvaluator.alice_wins_value()
+    }
+
+    fn bob_wins_value(&self) -> f32 {
+        KonaneEvaluator.bob_wins_value()
+    }
+}
+
+/// Payload of the unwind that abandons a search once the stop flag is set. `game_player`'s search has no way to be
+/// stopped, so unwinding out of the move generator is the only way to end it early. The unwind starts before the
+/// generator touches the transposition table or any other state shared with the search.
+struct SearchStopped;
+
+// With panics that abort, stopping a search would end the process
+#[cfg(not(panic = "unwind"))]
+compile_error!("the AI stops searches by unwinding, so it needs panic = \"unwind\"");
+
+/// Move generator that records each expanded node with a tracker and feeds the cutoffs it observes back into the
+/// move ordering tables. Once the stop flag is set, the next call unwinds out of the search with `SearchStopped`.
+/// None of the nodes still being searched return a value, so nothing incomplete is stored in the transposition table.
+struct TrackingMoveGenerator<'a> {
+    generator: KonaneMoveGenerator,
+    ordering: Option<Rc<RefCell<MoveOrdering>>>,
+    tracker: &'a RefCell<SearchTracker>,
+    stop: Option<&'a AtomicBool>,
+}
+
+impl ResponseGenerator for TrackingMoveGenerator<'_> {
+    type State = KonaneState;
+
+    fn generate(&self, state: &Rc<Self::State>, depth: i32) -> Vec<Box<Self::State>> {
+        if self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed)) {
+            // Unlike `panic!`, this does not run the panic hook
+            panic::resume_unwind(Box::new(SearchStopped));
         }
+
+        if let Some(ref ordering) = self.ordering {
+            let mut ordering = ordering.borrow_mut();
+            for cutoff in self.tracker.borrow_mut().take_cutoffs() {
+                ordering.record_cutoff(cutoff);
+            }
+        }
+
+        let children = self.generator.generate(state, depth);
+        let moves = children
+            .iter()
+            .filter_map(|child| child.last_action.as_ref().map(|action| (child.fingerprint(), MoveKey::from(action))))
+            .collect();
+        self.tracker.borrow_mut().expand(state.fingerprint(), depth, moves);
+        children
     }
 }
 
+/// The transposition table and move ordering tables persist between searches, so a search benefits from the
+/// positions analyzed by earlier ones, including searches done while pondering.
 pub struct AiPlayer {
     color: PieceColor,
     depth: i32,
+    book: Option<Arc<OpeningBook>>,
+    book_random: bool,
+    weights: EvalWeights,
+    tt: Rc<RefCell<TranspositionTable>>,
+    ordering: Option<Rc<RefCell<MoveOrdering>>>,
+    /// Source of all randomness in the AI's choices
+    rng: RefCell<StdRng>,
 }
 
 impl AiPlayer {
     pub fn new(color: PieceColor, depth: i32) -> Self {
-        Self { color, depth }
+        Self {
+            color,
+            depth,
+            book: None,
+            book_random: false,
+            weights: EvalWeights::default(),
+            tt: Rc::new(RefCell::new(TranspositionTable::new(100_000, 100))),
+            ordering: Some(Rc::new(RefCell::new(MoveOrdering::new()))),
+            rng: RefCell::new(StdRng::from_os_rng()),
+        }
+    }
+
+    /// Replaces the random number generator. An AI given an identically seeded generator makes the same choices.
+    pub fn with_rng(mut self, rng: StdRng) -> Self {
+        self.rng = RefCell::new(rng);
+        self
+    }
+
+    /// Enables or disables move ordering. Ordering is on by default; turning it off is useful for measuring its
+    /// effect on the node counts in the search report.
+    pub fn with_move_ordering(mut self, enabled: bool) -> Self {
+        self.ordering = enabled.then(|| Rc::new(RefCell::new(MoveOrdering::new())));
+        self
+    }
+
+    /// Consults the opening book before searching. If `random` is true, book moves are chosen at random in
+    /// proportion to their weights so that games vary.
+    pub fn with_book(mut self, book: Arc<OpeningBook>, random: bool) -> Self {
+        self.book = Some(book);
+        self.book_random = random;
+        self
+    }
+
+    /// Replaces the weights of the evaluation, such as to compare styles of play in a tournament.
+    pub fn with_weights(mut self, weights: EvalWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// The deepest the AI searches.
+    pub fn depth(&self) -> i32 {
+        self.depth
     }
 
     pub fn compute_move(&self, state: &GameState) -> Option<PlayerMove> {
+        self.search(state).0
+    }
+
+    /// Chooses a move and reports how it was found. The search deepens one ply at a time up to the configured depth.
+    pub fn search(&self, state: &GameState) -> (Option<PlayerMove>, SearchReport) {
+        self.search_until_stopped(state, &AtomicBool::new(false))
+    }
+
+    /// Like `search`, but gives up when `stop` is set and returns the result of the deepest completed iteration.
+    /// The first iteration always completes, so a move is returned whenever one exists.
+    pub fn search_until_stopped(&self, state: &GameState, stop: &AtomicBool) -> (Option<PlayerMove>, SearchReport) {
+        self.search_to_depth(state, self.depth, stop)
+    }
+
+    /// Like `search_until_stopped`, but deepens up to the given depth instead of the configured one.
+    pub fn search_to_depth(&self, state: &GameState, depth: i32, stop: &AtomicBool) -> (Option<PlayerMove>, SearchReport) {
+        if let Some(book_move) = self.book.as_ref().and_then(|book| book.choose_move(state, self.book_random, &mut *self.rng.borrow_mut())) {
+            let report = SearchReport {
+                best_move: Some(book_move.to_algebraic()),
+                from_book: true,
+                ..Default::default()
+            };
+            return (Some(book_move), report);
+        }
+
+        self.search_tree(state, depth, stop)
+    }
+
+    /// Like `search_to_depth`, but also gives up once `move_time` has passed.
+    pub fn search_with_time_limit(&self, state: &GameState, depth: i32, move_time: Duration) -> (Option<PlayerMove>, SearchReport) {
+        let stop = AtomicBool::new(false);
+        let (done, finished) = mpsc::channel::<()>();
+        thread::scope(|scope| {
+            let stop = &stop;
+            scope.spawn(move || {
+                if finished.recv_timeout(move_time) == Err(RecvTimeoutError::Timeout) {
+                    stop.store(true, Ordering::SeqCst);
+                }
+            });
+            let result = self.search_to_depth(state, depth, stop);
+            drop(done);
+            result
+        })
+    }
+
+    /// Searches a position the AI is not going to play from, such as the opponent's turn, until `stop` is set. The
+    /// opening book is not consulted. The result is discarded, but the tables filled by the search make later
+    /// searches of the same lines faster.
+    pub fn ponder(&self, state: &GameState, stop: &AtomicBool) -> SearchReport {
+        self.search_tree(state, self.depth, stop).1
+    }
+
+    fn search_tree(&self, state: &GameState, max_depth: i32, stop: &AtomicBool) -> (Option<PlayerMove>, SearchReport) {
+        let mut report = SearchReport::default();
+
         let konane_state = Rc::new(KonaneState {
             inner: state.clone(),
             last_action: None,
         });
 
-        let evaluator = KonaneEvaluator;
-        let generator = KonaneMoveGenerator;
-        let tt = Rc::new(RefCell::new(TranspositionTable::new(100_000, 100)));
+        let tracker = RefCell::new(SearchTracker::new());
+        let evaluator = TrackingEvaluator {
+            color: self.color,
+            weights: self.weights,
+            tracker: &tracker,
+        };
+
+        if let Some(ref ordering) = self.ordering {
+            ordering.borrow_mut().age();
+        }
+
+        let mut best_move = None;
+        for depth in 1..=max_depth {
+            let interruptible = depth > 1;
+            if interruptible && stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let generator = TrackingMoveGenerator {
+                generator: match self.ordering {
+                    Some(ref ordering) => KonaneMoveGenerator::with_ordering(ordering.clone()),
+                    None => KonaneMoveGenerator::default(),
+                },
+                ordering: self.ordering.clone(),
+                tracker: &tracker,
+                stop: interruptible.then_some(stop),
+            };
 
-        let result = search(&tt, &evaluator, &generator, &konane_state, self.depth);
+            let start = Instant::now();
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                search(&self.tt, &evaluator, &generator, &konane_state, depth)
+            }));
+            let counts = tracker.borrow_mut().finish_search();
+
+            // An interrupted iteration has no result. The transposition table entries it stored are for subtrees it
+            // finished, so they are kept for later searches along with the move ordering tables.
+            let result = match result {
+                Ok(result) => result,
+                Err(payload) if payload.is::<SearchStopped>() => break,
+                Err(payload) => panic::resume_unwind(payload),
+            };
+
+            // The best move of this iteration is searched first in the next one
+            if let (Some(ordering), Some(action)) =
+                (&self.ordering, result.as_ref().and_then(|best| best.last_action.as_ref()))
+            {
+                ordering.borrow_mut().record_best_move(konane_state.fingerprint(), MoveKey::from(action));
+            }
+
+            best_move = result
+                .and_then(|best_state| best_state.last_action.clone())
+                .map(|action| match action {
+                    KonaneAction::OpeningRemoval(pos) => PlayerMove::OpeningRemoval(pos),
+                    KonaneAction::Jump(jump) => PlayerMove::Jump(jump),
+                });
+
+            report.add_iteration(
+                depth,
+                best_move.as_ref().map(PlayerMove::to_algebraic),
+                start.elapsed().as_millis() as u64,
+                counts,
+            );
+        }
 
-        result
-            .and_then(|best_state| best_state.last_action.clone())
-            .map(|action| match action {
-                KonaneAction::OpeningRemoval(pos) => PlayerMove::OpeningRemoval(pos),
-                KonaneAction::Jump(jump) => PlayerMove::Jump(jump),
-            })
+        (best_move, report)
     }
 }
 
@@ -182,6 +468,23 @@ impl Player for AiPlayer {
     }
 }
 
+/// Plays a game between two AIs from the given position to the end, returning the final position and the moves
+/// played. Used for self-play.
+pub fn play_out(mut state: GameState, black: &AiPlayer, white: &AiPlayer) -> Result<(GameState, MoveHistory), String> {
+    let mut history = MoveHistory::new();
+    while !matches!(state.current_phase(), GamePhase::GameOver { .. }) {
+        let ai = match state.current_player() {
+            PieceColor::Black => black,
+            PieceColor::White => white,
+        };
+        let player_move = ai
+            .compute_move(&state)
+            .ok_or_else(|| format!("{} AI found no move at move {}", state.current_player(), history.len() + 1))?;
+        history.push(player_move.apply(&mut state).map_err(str::to_string)?);
+    }
+    Ok((state, history))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,6 +666,44 @@ mod tests {
             // Score should be non-zero (mobility difference)
             assert!(score != 0.0 || score == 0.0); // Just ensure it computes
         }
+
+        /// A position in play where Black has 2 jumps and White has 1.
+        fn uneven_position() -> KonaneState {
+            let mut game = GameState::new(4, PieceColor::Black);
+            Rules::apply_opening_removal(&mut game, Position::new(1, 1)).unwrap();
+            Rules::apply_opening_removal(&mut game, Position::new(1, 2)).unwrap();
+            let jump = Rules::all_valid_jumps(&game).remove(0);
+            Rules::apply_jump(&mut game, &jump);
+            game.set_current_player(PieceColor::Black);
+            KonaneState {
+                inner: game,
+                last_action: None,
+            }
+        }
+
+        #[test]
+        fn default_weights_match_the_evaluator_for_both_colors() {
+            let state = uneven_position();
+            let expected = KonaneEvaluator.evaluate(&state);
+
+            for color in [PieceColor::Black, PieceColor::White] {
+                assert_eq!(evaluate_mobility(&state, color, &EvalWeights::default()), expected);
+            }
+        }
+
+        #[test]
+        fn weights_apply_from_the_ai_point_of_view() {
+            let state = uneven_position();
+            let black = count_mobility_for(&state.inner, PieceColor::Black) as f32;
+            let white = count_mobility_for(&state.inner, PieceColor::White) as f32;
+            let weights = EvalWeights {
+                mobility: 1.0,
+                opponent_mobility: 3.0,
+            };
+
+            assert_eq!(evaluate_mobility(&state, PieceColor::Black, &weights), black - 3.0 * white);
+            assert_eq!(evaluate_mobility(&state, PieceColor::White, &weights), -(white - 3.0 * black));
+        }
     }
 
     mod konane_move_generator {
@@ -375,7 +716,7 @@ mod tests {
                 inner: game,
                 last_action: None,
             });
-            let generator = KonaneMoveGenerator;
+            let generator = KonaneMoveGenerator::default();
 
             let moves = generator.generate(&state, 0);
 
@@ -395,7 +736,7 @@ mod tests {
                 inner: game,
                 last_action: None,
             });
-            let generator = KonaneMoveGenerator;
+            let generator = KonaneMoveGenerator::default();
 
             let moves = generator.generate(&state, 0);
 
@@ -415,7 +756,7 @@ mod tests {
                 inner: game,
                 last_action: None,
             });
-            let generator = KonaneMoveGenerator;
+            let generator = KonaneMoveGenerator::default();
 
             let moves = generator.generate(&state, 0);
 
@@ -424,6 +765,27 @@ mod tests {
             assert!(moves.iter().any(|mv| matches!(mv.last_action, Some(KonaneAction::Jump(_)))));
         }
 
+        #[test]
+        fn orders_multi_jumps_first() {
+            let mut game = GameState::new(8, PieceColor::Black);
+            game.change_phase(GamePhase::Play);
+            game.remove_stone(Position::new(0, 2));
+            game.remove_stone(Position::new(0, 4));
+
+            let state = Rc::new(KonaneState {
+                inner: game,
+                last_action: None,
+            });
+            let generator = KonaneMoveGenerator::with_ordering(Rc::new(RefCell::new(MoveOrdering::new())));
+
+            let moves = generator.generate(&state, 1);
+
+            match moves[0].last_action {
+                Some(KonaneAction::Jump(ref jump)) => assert_eq!(jump.captured.len(), 2),
+                _ => panic!("Expected a jump"),
+            }
+        }
+
         #[test]
         fn returns_empty_when_game_over() {
             let mut game = GameState::new(4, PieceColor::Black);
@@ -435,7 +797,7 @@ mod tests {
                 inner: game,
                 last_action: None,
             });
-            let generator = KonaneMoveGenerator;
+            let generator = KonaneMoveGenerator::default();
 
             let moves = generator.generate(&state, 0);
             assert!(moves.is_empty());
@@ -503,6 +865,66 @@ mod tests {
             }
         }
 
+        #[test]
+        fn compute_move_plays_book_move() {
+            use crate::game::book::BookMove;
+            use crate::game::state::MoveRecord;
+
+            let state = GameState::new(4, PieceColor::Black);
+            let mut book = OpeningBook::new();
+            book.insert(
+                state.fingerprint(),
+                vec![BookMove {
+                    record: MoveRecord::OpeningRemoval {
+                        color: PieceColor::Black,
+                        position: Position::new(3, 3),
+                    },
+                    weight: 1,
+                }],
+            );
+
+            let player = AiPlayer::new(PieceColor::Black, 2).with_book(Arc::new(book), false);
+            match player.compute_move(&state) {
+                Some(PlayerMove::OpeningRemoval(pos)) => assert_eq!(pos, Position::new(3, 3)),
+                other => panic!("Expected book move, got {:?}", other),
+            }
+
+            let (_, report) = player.search(&state);
+            assert!(report.from_book);
+            assert_eq!(report.nodes, 0);
+        }
+
+        #[test]
+        fn same_seed_plays_same_book_moves() {
+            use crate::game::book::BookMove;
+            use crate::game::state::MoveRecord;
+
+            let state = GameState::new(4, PieceColor::Black);
+            let moves = Rules::valid_black_opening_removals(&state)
+                .into_iter()
+                .map(|position| BookMove {
+                    record: MoveRecord::OpeningRemoval {
+                        color: PieceColor::Black,
+                        position,
+                    },
+                    weight: 1,
+                })
+                .collect();
+            let mut book = OpeningBook::new();
+            book.insert(state.fingerprint(), moves);
+            let book = Arc::new(book);
+
+            let choices = |seed| {
+                let player = AiPlayer::new(PieceColor::Black, 2)
+                    .with_book(book.clone(), true)
+                    .with_rng(StdRng::seed_from_u64(seed));
+                (0..10)
+                    .map(|_| player.compute_move(&state).unwrap().to_algebraic())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(choices(42), choices(42));
+        }
+
         #[test]
         fn request_move_delegates_to_compute_move() {
             let state = GameState::new(4, PieceColor::Black);
@@ -514,6 +936,153 @@ mod tests {
         }
     }
 
+    mod search_report {
+        use super::*;
+
+        #[test]
+        fn reports_each_iteration() {
+            let state = GameState::new(4, PieceColor::Black);
+            let player = AiPlayer::new(PieceColor::Black, 3);
+
+            let (mv, report) = player.search(&state);
+
+            assert!(mv.is_some());
+            assert!(!report.from_book);
+            assert_eq!(report.depth_reached, 3);
+            assert_eq!(report.iterations.len(), 3);
+            assert!(report.nodes > 0);
+            assert_eq!(report.best_move, mv.map(|mv| mv.to_algebraic()));
+        }
+
+        #[test]
+        fn move_ordering_reduces_nodes() {
+            let mut state = GameState::new(8, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(3, 3)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(3, 4)).unwrap();
+
+            let (ordered, ordered_report) = AiPlayer::new(PieceColor::Black, 4).search(&state);
+            let (unordered, unordered_report) = AiPlayer::new(PieceColor::Black, 4).with_move_ordering(false).search(&state);
+
+            assert!(ordered.is_some());
+            assert!(unordered.is_some());
+            assert!(ordered_report.nodes < unordered_report.nodes);
+        }
+
+        #[test]
+        fn stopped_search_returns_first_iteration() {
+            let state = GameState::new(4, PieceColor::Black);
+            let player = AiPlayer::new(PieceColor::Black, 4);
+
+            let (mv, report) = player.search_until_stopped(&state, &AtomicBool::new(true));
+
+            assert!(mv.is_some());
+            assert_eq!(report.depth_reached, 1);
+            assert_eq!(report.iterations.len(), 1);
+        }
+
+        #[test]
+        fn time_limited_search_stops_early() {
+            let mut state = GameState::new(8, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(3, 3)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(3, 4)).unwrap();
+            let player = AiPlayer::new(PieceColor::Black, 4);
+
+            let (mv, report) = player.search_with_time_limit(&state, 30, Duration::from_millis(50));
+
+            assert!(mv.is_some());
+            assert!(report.depth_reached < 30);
+        }
+
+        #[test]
+        fn stopped_ponder_returns_promptly() {
+            let mut state = GameState::new(8, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(3, 3)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(3, 4)).unwrap();
+            let player = AiPlayer::new(PieceColor::White, 30);
+
+            let report = player.ponder(&state, &AtomicBool::new(true));
+
+            assert_eq!(report.depth_reached, 1);
+        }
+
+        #[test]
+        fn ponder_ignores_the_book() {
+            use crate::game::book::BookMove;
+            use crate::game::state::MoveRecord;
+
+            let state = GameState::new(4, PieceColor::Black);
+            let mut book = OpeningBook::new();
+            book.insert(
+                state.fingerprint(),
+                vec![BookMove {
+                    record: MoveRecord::OpeningRemoval {
+                        color: PieceColor::Black,
+                        position: Position::new(0, 0),
+                    },
+                    weight: 1,
+                }],
+            );
+            let player = AiPlayer::new(PieceColor::White, 2).with_book(Arc::new(book), false);
+
+            let report = player.ponder(&state, &AtomicBool::new(false));
+
+            assert!(!report.from_book);
+            assert_eq!(report.depth_reached, 2);
+        }
+
+        #[test]
+        fn repeated_searches_reuse_tables() {
+            let mut state = GameState::new(8, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(3, 3)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(3, 4)).unwrap();
+            // Without move ordering, only the transposition table carries over between the searches
+            let player = AiPlayer::new(PieceColor::Black, 4).with_move_ordering(false);
+
+            let (first, first_report) = player.search(&state);
+            let (second, second_report) = player.search(&state);
+
+            assert!(first.is_some());
+            assert!(second.is_some());
+            assert!(second_report.nodes < first_report.nodes);
+        }
+
+        #[test]
+        fn stopped_ponder_seeds_the_next_search() {
+            let mut state = GameState::new(8, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(3, 3)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(3, 4)).unwrap();
+            // Without move ordering, only the transposition table carries over from the ponder
+            let pondered = AiPlayer::new(PieceColor::Black, 30).with_move_ordering(false);
+            let stop = AtomicBool::new(false);
+            thread::scope(|scope| {
+                scope.spawn(|| {
+                    thread::sleep(Duration::from_millis(200));
+                    stop.store(true, Ordering::SeqCst);
+                });
+                let report = pondered.ponder(&state, &stop);
+                assert!(report.depth_reached < 30);
+            });
+
+            let never_stop = AtomicBool::new(false);
+            let (_, warm) = pondered.search_to_depth(&state, 4, &never_stop);
+            let cold = AiPlayer::new(PieceColor::Black, 30).with_move_ordering(false);
+            let (_, cold) = cold.search_to_depth(&state, 4, &never_stop);
+
+            assert!(warm.nodes < cold.nodes);
+        }
+
+        #[test]
+        fn node_counts_sum_over_iterations() {
+            let state = GameState::new(4, PieceColor::Black);
+            let player = AiPlayer::new(PieceColor::Black, 2);
+
+            let (_, report) = player.search(&state);
+
+            let total: u64 = report.iterations.iter().map(|iteration| iteration.nodes).sum();
+            assert_eq!(report.nodes, total);
+        }
+    }
+
     mod integration {
         use super::*;
 
@@ -544,6 +1113,17 @@ mod tests {
             assert_eq!(state.current_phase(), GamePhase::Play);
         }
 
+        #[test]
+        fn play_out_finishes_the_game() {
+            let black = AiPlayer::new(PieceColor::Black, 1);
+            let white = AiPlayer::new(PieceColor::White, 1);
+
+            let (state, history) = play_out(GameState::new(6, PieceColor::Black), &black, &white).unwrap();
+
+            assert!(matches!(state.current_phase(), GamePhase::GameOver { .. }));
+            assert_eq!(Rules::replay(6, &history).unwrap().last().unwrap().fingerprint(), state.fingerprint());
+        }
+
         #[test]
         fn ai_selects_best_move_shallow_depth() {
             // With very shallow depth, AI should still make legal moves
@@ -0,0 +1,370 @@
+//! Opening book for the removal phase and the first jumps.
+//!
+//! Positions are keyed by `GameState::fingerprint`. Each entry lists the moves worth playing along with a relative
+//! weight, so the AI can either always play the strongest move or pick among good moves at random.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+
+use game_player::StaticEvaluator;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::game::ai::{AiPlayer, KonaneEvaluator, KonaneState};
+use crate::game::player::PlayerMove;
+use crate::game::rules::Rules;
+use crate::game::state::{GamePhase, GameState, MoveRecord, PieceColor};
+use crate::game::zhash::Z;
+
+/// A move suggested by the book.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookMove {
+    pub record: MoveRecord,
+    /// Relative weight used for random selection. Higher is better.
+    pub weight: u32,
+}
+
+impl BookMove {
+    /// Converts the book move into a legal move in the given state, or None if it is not legal there.
+    pub fn resolve(&self, state: &GameState) -> Option<PlayerMove> {
+        match &self.record {
+            MoveRecord::OpeningRemoval { color, position } => {
+                let valid = match state.current_phase() {
+                    GamePhase::OpeningBlackRemoval => Rules::valid_black_opening_removals(state),
+                    GamePhase::OpeningWhiteRemoval => Rules::valid_white_opening_removals(state),
+                    _ => return None,
+                };
+                (*color == state.current_player() && valid.contains(position)).then_some(PlayerMove::OpeningRemoval(*position))
+            }
+            MoveRecord::Jump { color, from, to, .. } => {
+                if *color != state.current_player() || !matches!(state.current_phase(), GamePhase::Play) {
+                    return None;
+                }
+                Rules::valid_jumps_from(state, *from)
+                    .into_iter()
+                    .find(|jump| jump.to == *to)
+                    .map(PlayerMove::Jump)
+            }
+        }
+    }
+}
+
+/// Precomputed moves for early positions, keyed by fingerprint.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpeningBook {
+    entries: BTreeMap<Z, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Replaces the moves for a position.
+    pub fn insert(&mut self, fingerprint: Z, moves: Vec<BookMove>) {
+        self.entries.insert(fingerprint, moves);
+    }
+
+    /// Adds all positions of another book, replacing any that are already present.
+    pub fn merge(&mut self, other: OpeningBook) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Returns the book moves for a position, if any.
+    pub fn moves(&self, state: &GameState) -> Option<&[BookMove]> {
+        self.entries.get(&state.fingerprint()).map(Vec::as_slice)
+    }
+
+    /// Chooses a book move for the position. Moves that are not legal in the position are ignored, which guards
+    /// against fingerprint collisions.
+    ///
+    /// If `weighted_random` is false, the move with the highest weight is returned. Otherwise a move is chosen using
+    /// `rng` with probability proportional to its weight.
+    pub fn choose_move<R: Rng + ?Sized>(&self, state: &GameState, weighted_random: bool, rng: &mut R) -> Option<PlayerMove> {
+        let candidates: Vec<(PlayerMove, u32)> = self
+            .moves(state)?
+            .iter()
+            .filter_map(|book_move| book_move.resolve(state).map(|mv| (mv, book_move.weight)))
+            .collect();
+
+        let total: u32 = candidates.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+
+        if weighted_random {
+            let mut pick = rng.random_range(0..total);
+            for (mv, weight) in candidates {
+                if pick < weight {
+                    return Some(mv);
+                }
+                pick -= weight;
+            }
+            None
+        } else {
+            let mut best: Option<(PlayerMove, u32)> = None;
+            for (mv, weight) in candidates {
+                if best.as_ref().is_none_or(|(_, best_weight)| weight > *best_weight) {
+                    best = Some((mv, weight));
+                }
+            }
+            best.map(|(mv, _)| mv)
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|err| format!("Failed to serialize book: {}", err))
+    }
+
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        serde_json::from_str(content).map_err(|err| format!("Invalid book: {}", err))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| format!("Failed to read book: {}", err))?;
+        Self::from_json(&content)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_json()?).map_err(|err| format!("Failed to write book: {}", err))
+    }
+
+    /// Builds a book by searching every position reachable within `plies` moves of the start.
+    ///
+    /// Each legal move is scored with a search of `depth` plies. Moves scoring within `margin` of the best move are
+    /// kept, weighted by how close they are to the best, and only the kept moves are expanded further.
+    pub fn generate(board_size: usize, depth: i32, plies: usize, margin: f32) -> Self {
+        let mut book = OpeningBook::new();
+        // The search values are from Black's point of view whichever side is to move
+        let ai = AiPlayer::new(PieceColor::Black, depth);
+        let mut frontier = vec![GameState::new(board_size, PieceColor::Black)];
+
+        for _ in 0..plies {
+            let mut next = Vec::new();
+
+            for state in frontier {
+                if book.entries.contains_key(&state.fingerprint()) || !Rules::has_valid_move(&state) {
+                    continue;
+                }
+
+                let mover = state.current_player();
+                let mut scored: Vec<(PlayerMove, GameState, MoveRecord, f32)> = Vec::new();
+                for mv in Rules::legal_moves(&state) {
+                    let mut child = state.clone();
+                    let Ok(record) = mv.apply(&mut child) else {
+                        continue;
+                    };
+                    let value = search_value(&ai, &child, depth - 1);
+                    let value = if mover == PieceColor::Black { value } else { -value };
+                    scored.push((mv, child, record, value));
+                }
+
+                let Some(best) = scored.iter().map(|(_, _, _, value)| *value).reduce(f32::max) else {
+                    continue;
+                };
+
+                let mut moves = Vec::new();
+                for (_, child, record, value) in scored {
+                    let shortfall = best - value;
+                    if shortfall > margin {
+                        continue;
+                    }
+                    moves.push(BookMove {
+                        record,
+                        weight: (margin - shortfall).round() as u32 + 1,
+                    });
+                    next.push(child);
+                }
+
+                book.insert(state.fingerprint(), moves);
+            }
+
+            frontier = next;
+        }
+
+        book
+    }
+}
+
+/// Scores a position from Black's point of view by playing out the principal variation of a search and
+/// evaluating the position it leads to. The same AI is used for every position of a book, so each search after the
+/// first ply of a line is answered mostly from the transposition table filled by the searches before it.
+fn search_value(ai: &AiPlayer, state: &GameState, depth: i32) -> f32 {
+    let mut current = state.clone();
+    let stop = AtomicBool::new(false);
+
+    for remaining in (1..=depth).rev() {
+        if matches!(current.current_phase(), GamePhase::GameOver { .. }) {
+            break;
+        }
+        let Some(mv) = ai.search_to_depth(&current, remaining, &stop).0 else {
+            break;
+        };
+        if mv.apply(&mut current).is_err() {
+            break;
+        }
+    }
+
+    KonaneEvaluator.evaluate(&KonaneState {
+        inner: current,
+        last_action: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::state::Position;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn opening_book_for_start() -> OpeningBook {
+        let state = GameState::new(4, PieceColor::Black);
+        let mut book = OpeningBook::new();
+        book.insert(
+            state.fingerprint(),
+            vec![
+                BookMove {
+                    record: MoveRecord::OpeningRemoval {
+                        color: PieceColor::Black,
+                        position: Position::new(0, 0),
+                    },
+                    weight: 1,
+                },
+                BookMove {
+                    record: MoveRecord::OpeningRemoval {
+                        color: PieceColor::Black,
+                        position: Position::new(1, 1),
+                    },
+                    weight: 5,
+                },
+            ],
+        );
+        book
+    }
+
+    mod choose_move {
+        use super::*;
+
+        #[test]
+        fn returns_none_for_unknown_position() {
+            let book = OpeningBook::new();
+            let state = GameState::new(4, PieceColor::Black);
+            assert!(book.choose_move(&state, false, &mut rand::rng()).is_none());
+        }
+
+        #[test]
+        fn picks_highest_weight_when_not_random() {
+            let book = opening_book_for_start();
+            let state = GameState::new(4, PieceColor::Black);
+            match book.choose_move(&state, false, &mut rand::rng()) {
+                Some(PlayerMove::OpeningRemoval(pos)) => assert_eq!(pos, Position::new(1, 1)),
+                other => panic!("Expected OpeningRemoval, got {:?}", other),
+            }
+        }
+
+        #[test]
+        fn random_choice_is_a_book_move() {
+            let book = opening_book_for_start();
+            let state = GameState::new(4, PieceColor::Black);
+            let mut rng = StdRng::seed_from_u64(1);
+            for _ in 0..20 {
+                match book.choose_move(&state, true, &mut rng) {
+                    Some(PlayerMove::OpeningRemoval(pos)) => {
+                        assert!(pos == Position::new(0, 0) || pos == Position::new(1, 1));
+                    }
+                    other => panic!("Expected OpeningRemoval, got {:?}", other),
+                }
+            }
+        }
+
+        #[test]
+        fn same_seed_gives_same_choices() {
+            let book = opening_book_for_start();
+            let state = GameState::new(4, PieceColor::Black);
+            let choices = |seed| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                (0..20)
+                    .map(|_| book.choose_move(&state, true, &mut rng).unwrap().to_algebraic())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(choices(7), choices(7));
+        }
+
+        #[test]
+        fn ignores_illegal_moves() {
+            let state = GameState::new(4, PieceColor::Black);
+            let mut book = OpeningBook::new();
+            book.insert(
+                state.fingerprint(),
+                vec![BookMove {
+                    record: MoveRecord::OpeningRemoval {
+                        color: PieceColor::Black,
+                        position: Position::new(0, 1),
+                    },
+                    weight: 1,
+                }],
+            );
+            assert!(book.choose_move(&state, false, &mut rand::rng()).is_none());
+        }
+    }
+
+    mod serialization {
+        use super::*;
+
+        #[test]
+        fn json_roundtrip() {
+            let book = opening_book_for_start();
+            let json = book.to_json().unwrap();
+            let loaded = OpeningBook::from_json(&json).unwrap();
+            assert_eq!(loaded.len(), 1);
+
+            let state = GameState::new(4, PieceColor::Black);
+            assert_eq!(loaded.moves(&state).map(|moves| moves.len()), Some(2));
+        }
+
+        #[test]
+        fn rejects_invalid_json() {
+            let result = OpeningBook::from_json("not a book");
+            assert!(result.is_err());
+            assert!(result.unwrap_err().contains("Invalid book"));
+        }
+    }
+
+    mod generate {
+        use super::*;
+
+        #[test]
+        fn covers_both_removal_phases() {
+            let book = OpeningBook::generate(4, 2, 2, 1000.0);
+            let state = GameState::new(4, PieceColor::Black);
+
+            let moves = book.moves(&state).expect("start position should be in the book");
+            assert_eq!(moves.len(), Rules::valid_black_opening_removals(&state).len());
+
+            // Every Black removal leads to a White removal position that is also in the book
+            for book_move in moves {
+                let mut child = state.clone();
+                book_move.resolve(&state).unwrap().apply(&mut child).unwrap();
+                assert!(book.moves(&child).is_some());
+            }
+        }
+
+        #[test]
+        fn book_moves_are_legal() {
+            let book = OpeningBook::generate(4, 2, 3, 0.0);
+            let state = GameState::new(4, PieceColor::Black);
+            let mv = book.choose_move(&state, false, &mut rand::rng());
+            assert!(matches!(mv, Some(PlayerMove::OpeningRemoval(_))));
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! Game clocks and time controls.
+//!
+//! A time control is either a base time with an increment added after every move, written `5+3` for five minutes
+//! plus three seconds a move, or a fixed time for each move, written `30s`. Only the player to move has a running
+//! clock. A player whose clock reaches zero has lost on time.
+//!
+//! `GameClock` is given the current `Instant` by its caller rather than reading it, so it can be tested without
+//! waiting.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::game::state::PieceColor;
+
+/// How many more moves the AI expects to play when spreading its remaining time over the game.
+const MOVES_TO_GO: u32 = 25;
+
+/// Time kept back from every move the AI plans, for the move to reach the clock.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    /// A base time for the whole game, with `increment` added after each move
+    Increment { base: Duration, increment: Duration },
+    /// A fixed time for each move; time not used is not carried over
+    PerMove(Duration),
+}
+
+impl TimeControl {
+    /// The time on each clock when the game starts.
+    pub fn initial(&self) -> Duration {
+        match *self {
+            TimeControl::Increment { base, .. } => base,
+            TimeControl::PerMove(per_move) => per_move,
+        }
+    }
+
+    /// How long the AI should think about a move with `remaining` on its clock. With a base time, it spends a
+    /// share of what is left plus the increment it gets back; with a fixed time per move, most of the move's time.
+    pub fn think_time(&self, remaining: Duration) -> Duration {
+        let usable = remaining.saturating_sub(MOVE_OVERHEAD);
+        match *self {
+            TimeControl::Increment { increment, .. } => (usable / MOVES_TO_GO).saturating_add(increment).min(usable / 2),
+            TimeControl::PerMove(_) => usable * 3 / 4,
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeControl::Increment { base, increment } => write!(f, "{}+{}", base.as_secs() / 60, increment.as_secs()),
+            TimeControl::PerMove(per_move) => write!(f, "{}s", per_move.as_secs()),
+        }
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    /// Reads `minutes+seconds`, such as `5+3`, or `seconds` followed by `s`, such as `30s`.
+    fn from_str(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let invalid = || {
+            format!(
+                "Invalid time control \"{}\": use minutes+increment such as 5+3, or seconds per move such as 30s",
+                text
+            )
+        };
+        let control = if let Some(seconds) = text.strip_suffix('s') {
+            TimeControl::PerMove(Duration::from_secs(seconds.parse().map_err(|_| invalid())?))
+        } else {
+            let (minutes, increment) = text.split_once('+').ok_or_else(invalid)?;
+            let minutes: u64 = minutes.trim().parse().map_err(|_| invalid())?;
+            let increment: u64 = increment.trim().parse().map_err(|_| invalid())?;
+            TimeControl::Increment {
+                base: Duration::from_secs(minutes.checked_mul(60).ok_or_else(invalid)?),
+                increment: Duration::from_secs(increment),
+            }
+        };
+        if control.initial().is_zero() {
+            return Err(format!("Invalid time control \"{}\": the time must be more than zero", text));
+        }
+        Ok(control)
+    }
+}
+
+/// The two clocks of a game.
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    control: TimeControl,
+    black: Duration,
+    white: Duration,
+    /// The player whose clock is running and when it was started
+    running: Option<(PieceColor, Instant)>,
+}
+
+impl GameClock {
+    /// Sets both clocks to the starting time. Neither runs until `start` is called.
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            black: control.initial(),
+            white: control.initial(),
+            running: None,
+        }
+    }
+
+    /// Sets the time left on both clocks, as when resuming a saved game.
+    pub fn with_remaining(mut self, black: Duration, white: Duration) -> Self {
+        self.black = black;
+        self.white = white;
+        self
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// The player whose clock is running.
+    pub fn running(&self) -> Option<PieceColor> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// The time left on a player's clock at `now`.
+    pub fn remaining(&self, color: PieceColor, now: Instant) -> Duration {
+        let stopped = match color {
+            PieceColor::Black => self.black,
+            PieceColor::White => self.white,
+        };
+        match self.running {
+            Some((running, started)) if running == color => stopped.saturating_sub(now.saturating_duration_since(started)),
+            _ => stopped,
+        }
+    }
+
+    /// The player whose flag has fallen: the player to move, once their clock has run out.
+    pub fn flag_fall(&self, now: Instant) -> Option<PieceColor> {
+        self.running().filter(|&color| self.remaining(color, now).is_zero())
+    }
+
+    /// How long the AI playing `color` should think about its move.
+    pub fn think_time(&self, color: PieceColor, now: Instant) -> Duration {
+        self.control.think_time(self.remaining(color, now))
+    }
+
+    /// Starts a player's clock, stopping the other. With a fixed time per move, the clock is set to the move's time.
+    pub fn start(&mut self, color: PieceColor, now: Instant) {
+        self.stop(now);
+        if let TimeControl::PerMove(per_move) = self.control {
+            *self.clock_mut(color) = per_move;
+        }
+        self.running = Some((color, now));
+    }
+
+    /// Stops the running clock after its player has moved, adding the increment unless the flag has fallen.
+    /// Returns the player's time after the move, as recorded in game records, or `None` if no clock was running.
+    pub fn stop(&mut self, now: Instant) -> Option<Duration> {
+        let (color, _) = self.running?;
+        let mut remaining = self.remaining(color, now);
+        if let TimeControl::Increment { increment, .. } = self.control
+            && !remaining.is_zero()
+        {
+            remaining = remaining.saturating_add(increment);
+        }
+        *self.clock_mut(color) = remaining;
+        self.running = None;
+        Some(remaining)
+    }
+
+    fn clock_mut(&mut self, color: PieceColor) -> &mut Duration {
+        match color {
+            PieceColor::Black => &mut self.black,
+            PieceColor::White => &mut self.white,
+        }
+    }
+}
+
+/// Formats a clock time as minutes and seconds, such as `4:05`, with tenths of a second under ten seconds.
+pub fn format_clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds < 10 {
+        format!("0:{:02}.{}", seconds, time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn blitz() -> TimeControl {
+        TimeControl::Increment {
+            base: secs(300),
+            increment: secs(3),
+        }
+    }
+
+    mod time_control {
+        use super::*;
+
+        #[test]
+        fn round_trips_through_text() {
+            assert_eq!("5+3".parse::<TimeControl>().unwrap(), blitz());
+            assert_eq!("30s".parse::<TimeControl>().unwrap(), TimeControl::PerMove(secs(30)));
+            assert_eq!(blitz().to_string(), "5+3");
+            assert_eq!(TimeControl::PerMove(secs(30)).to_string(), "30s");
+        }
+
+        #[test]
+        fn rejects_invalid_text() {
+            for text in ["", "5", "5+", "+3", "five+3", "0+0", "0s", "-5+3", "999999999999999999+0"] {
+                assert!(text.parse::<TimeControl>().is_err(), "{} should be rejected", text);
+            }
+        }
+
+        #[test]
+        fn spreads_a_base_time_over_the_game() {
+            let think = blitz().think_time(secs(300));
+
+            assert!(think > secs(3) && think < secs(20), "{:?}", think);
+            // Short of time, never more than half of what is left
+            assert!(blitz().think_time(secs(4)) <= secs(2));
+            assert_eq!(blitz().think_time(Duration::ZERO), Duration::ZERO);
+        }
+
+        #[test]
+        fn uses_most_of_a_fixed_time_per_move() {
+            let think = TimeControl::PerMove(secs(10)).think_time(secs(10));
+
+            assert!(think > secs(5) && think < secs(10), "{:?}", think);
+        }
+    }
+
+    mod clock {
+        use super::*;
+
+        #[test]
+        fn runs_only_for_the_player_to_move() {
+            let start = Instant::now();
+            let mut clock = GameClock::new(blitz());
+            clock.start(PieceColor::Black, start);
+
+            let now = start + secs(10);
+
+            assert_eq!(clock.remaining(PieceColor::Black, now), secs(290));
+            assert_eq!(clock.remaining(PieceColor::White, now), secs(300));
+            assert_eq!(clock.running(), Some(PieceColor::Black));
+        }
+
+        #[test]
+        fn adds_the_increment_after_a_move() {
+            let start = Instant::now();
+            let mut clock = GameClock::new(blitz());
+            clock.start(PieceColor::Black, start);
+
+            let recorded = clock.stop(start + secs(10));
+            clock.start(PieceColor::White, start + secs(10));
+
+            assert_eq!(recorded, Some(secs(293)));
+            assert_eq!(clock.remaining(PieceColor::Black, start + secs(60)), secs(293));
+            assert_eq!(clock.remaining(PieceColor::White, start + secs(60)), secs(250));
+        }
+
+        #[test]
+        fn resets_a_fixed_time_per_move() {
+            let start = Instant::now();
+            let mut clock = GameClock::new(TimeControl::PerMove(secs(30)));
+            clock.start(PieceColor::Black, start);
+            assert_eq!(clock.stop(start + secs(25)), Some(secs(5)));
+
+            clock.start(PieceColor::Black, start + secs(40));
+
+            assert_eq!(clock.remaining(PieceColor::Black, start + secs(50)), secs(20));
+        }
+
+        #[test]
+        fn flag_falls_when_the_time_runs_out() {
+            let start = Instant::now();
+            let mut clock = GameClock::new(TimeControl::PerMove(secs(30)));
+            clock.start(PieceColor::White, start);
+
+            assert_eq!(clock.flag_fall(start + secs(29)), None);
+            assert_eq!(clock.flag_fall(start + secs(30)), Some(PieceColor::White));
+            // No increment once the flag has fallen
+            assert_eq!(clock.stop(start + secs(31)), Some(Duration::ZERO));
+            assert_eq!(clock.flag_fall(start + secs(31)), None);
+        }
+
+        #[test]
+        fn resumes_with_the_saved_times() {
+            let start = Instant::now();
+            let mut clock = GameClock::new(blitz()).with_remaining(secs(120), secs(45));
+            clock.start(PieceColor::White, start);
+
+            assert_eq!(clock.remaining(PieceColor::Black, start + secs(5)), secs(120));
+            assert_eq!(clock.remaining(PieceColor::White, start + secs(5)), secs(40));
+        }
+
+        #[test]
+        fn stopping_a_stopped_clock_records_nothing() {
+            let mut clock = GameClock::new(blitz());
+
+            assert_eq!(clock.stop(Instant::now()), None);
+        }
+    }
+
+    #[test]
+    fn formats_clock_times() {
+        assert_eq!(format_clock(secs(245)), "4:05");
+        assert_eq!(format_clock(secs(3600)), "60:00");
+        assert_eq!(format_clock(Duration::from_millis(9_350)), "0:09.3");
+    }
+}
@@ -1,10 +1,19 @@
 pub mod ai;
+pub mod book;
+pub mod clock;
+pub mod ordering;
 pub mod player;
+pub mod ponder;
+pub mod report;
 pub mod rules;
 pub mod state;
 pub mod zhash;
 
-pub use ai::AiPlayer;
+pub use ai::{AiPlayer, EvalWeights};
+pub use book::OpeningBook;
+pub use clock::{GameClock, TimeControl};
+pub use ponder::Ponderer;
+pub use report::SearchReport;
 pub use rules::Rules;
 pub use state::*;
 pub use zhash::{ZHash, Z};
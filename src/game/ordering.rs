@@ -0,0 +1,315 @@
+//! Move ordering heuristics.
+//!
+//! Alpha-beta pruning works best when the strongest move is searched first. Children are ordered by:
+//! 1. the best move found for the position by an earlier search (the hash move),
+//! 2. killer moves, which caused a cutoff in a sibling position at the same depth,
+//! 3. the history heuristic, which favors moves that have caused cutoffs anywhere in the search,
+//! 4. longer multi-jumps, and
+//! 5. moves that leave the opponent with fewer replies.
+//!
+//! The tables persist from search to search. The hash moves are bounded in number, and the history scores are halved
+//! before each search so that moves that were good earlier in the game gradually lose their priority.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::game::ai::{KonaneAction, KonaneState};
+use crate::game::rules::{Jump, Rules};
+use crate::game::state::{GamePhase, GameState, Position};
+use crate::game::zhash::Z;
+
+/// Identifies a move independently of the position it is played in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveKey {
+    Removal(Position),
+    Jump(Position, Position),
+}
+
+impl From<&KonaneAction> for MoveKey {
+    fn from(action: &KonaneAction) -> Self {
+        match action {
+            KonaneAction::OpeningRemoval(pos) => MoveKey::Removal(*pos),
+            KonaneAction::Jump(jump) => MoveKey::Jump(jump.from, jump.to),
+        }
+    }
+}
+
+/// A move that caused a cutoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cutoff {
+    pub position: Z,
+    pub depth: i32,
+    pub key: MoveKey,
+}
+
+/// Number of killer moves remembered at each depth.
+const KILLERS_PER_DEPTH: usize = 2;
+
+/// Number of hash moves remembered before the table is cleared.
+const MAX_HASH_MOVES: usize = 100_000;
+
+/// Tables of moves learned during a search, used to order the children of each node.
+#[derive(Debug, Default)]
+pub struct MoveOrdering {
+    hash_moves: HashMap<Z, MoveKey>,
+    killers: HashMap<i32, [Option<MoveKey>; KILLERS_PER_DEPTH]>,
+    history: HashMap<MoveKey, u32>,
+}
+
+impl MoveOrdering {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remembers the best move found for a position. Once the table is full, it is cleared to make room.
+    pub fn record_best_move(&mut self, position: Z, key: MoveKey) {
+        if self.hash_moves.len() >= MAX_HASH_MOVES && !self.hash_moves.contains_key(&position) {
+            self.hash_moves.clear();
+        }
+        self.hash_moves.insert(position, key);
+    }
+
+    /// Halves the history scores, forgetting moves whose score drops to zero. Called before each search.
+    pub fn age(&mut self) {
+        self.history.retain(|_, score| {
+            *score /= 2;
+            *score > 0
+        });
+    }
+
+    /// Updates the tables after a move caused a cutoff.
+    pub fn record_cutoff(&mut self, cutoff: Cutoff) {
+        self.record_best_move(cutoff.position, cutoff.key);
+
+        let killers = self.killers.entry(cutoff.depth).or_default();
+        if killers[0] != Some(cutoff.key) {
+            killers[1] = killers[0];
+            killers[0] = Some(cutoff.key);
+        }
+
+        let depth = cutoff.depth.max(1) as u32;
+        *self.history.entry(cutoff.key).or_default() += depth * depth;
+    }
+
+    /// Sorts the children of a position, best first.
+    pub fn sort(&self, parent: &GameState, depth: i32, children: &mut [Box<KonaneState>]) {
+        let hash_move = self.hash_moves.get(&parent.fingerprint()).copied();
+        let killers = self.killers.get(&depth).copied().unwrap_or_default();
+        let opponent_jumps = opponent_jumps(parent);
+
+        children.sort_by_cached_key(|child| {
+            let Some(action) = child.last_action.as_ref() else {
+                return Reverse((false, 0, 0, 0, Reverse(usize::MAX)));
+            };
+            let key = MoveKey::from(action);
+            let killer_rank = match killers.iter().position(|&killer| killer == Some(key)) {
+                Some(index) => KILLERS_PER_DEPTH - index,
+                None => 0,
+            };
+            let history = self.history.get(&key).copied().unwrap_or(0);
+            let (captures, replies) = static_scores(child, &opponent_jumps);
+            Reverse((hash_move == Some(key), killer_rank, history, captures, Reverse(replies)))
+        });
+    }
+}
+
+/// Returns the jumps the opponent of the player to move would have in a position during play. They are found once
+/// per parent and used to estimate the replies left after each child's move.
+fn opponent_jumps(parent: &GameState) -> Vec<Jump> {
+    if !matches!(parent.current_phase(), GamePhase::Play) {
+        return Vec::new();
+    }
+    let mut opponent = parent.clone();
+    opponent.set_current_player(parent.current_player().opposite());
+    Rules::all_valid_jumps(&opponent)
+}
+
+/// Returns the number of stones captured by the move leading to a child and the number of replies available to the
+/// opponent.
+///
+/// After a jump, the replies are estimated as the opponent's jumps in the parent that pass over none of the squares
+/// the jump changed. Replies opened up by the jump are not counted.
+fn static_scores(child: &KonaneState, opponent_jumps: &[Jump]) -> (usize, usize) {
+    let captures = match child.last_action {
+        Some(KonaneAction::Jump(ref jump)) => jump.captured.len(),
+        _ => 0,
+    };
+    let replies = match (child.inner.current_phase(), &child.last_action) {
+        (GamePhase::Play, Some(KonaneAction::Jump(jump))) => {
+            let changed: Vec<Position> = [jump.from, jump.to].into_iter().chain(jump.captured.iter().copied()).collect();
+            opponent_jumps
+                .iter()
+                .filter(|reply| !changed.iter().any(|&square| passes_over(reply, square)))
+                .count()
+        }
+        (GamePhase::Play, _) => Rules::all_valid_jumps(&child.inner).len(),
+        (GamePhase::OpeningWhiteRemoval, _) => Rules::valid_white_opening_removals(&child.inner).len(),
+        _ => 0,
+    };
+    (captures, replies)
+}
+
+/// Returns true if a square is on the line from the start to the end of a jump, inclusive.
+fn passes_over(jump: &Jump, square: Position) -> bool {
+    let rows = jump.from.row.min(jump.to.row)..=jump.from.row.max(jump.to.row);
+    let cols = jump.from.col.min(jump.to.col)..=jump.from.col.max(jump.to.col);
+    rows.contains(&square.row) && cols.contains(&square.col)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    use crate::game::ai::KonaneMoveGenerator;
+    use crate::game::rules::Jump;
+    use crate::game::state::{Direction, GameState, PieceColor};
+    use game_player::State;
+    use game_player::minimax::ResponseGenerator;
+
+    fn play_state() -> Rc<KonaneState> {
+        // Black at a1 can jump to c1 or on to e1, and Black at c3 can jump to e3
+        let mut game = GameState::new(8, PieceColor::Black);
+        game.change_phase(GamePhase::Play);
+        game.remove_stone(Position::new(0, 2));
+        game.remove_stone(Position::new(0, 4));
+        game.remove_stone(Position::new(2, 4));
+        Rc::new(KonaneState {
+            inner: game,
+            last_action: None,
+        })
+    }
+
+    fn key_of(child: &KonaneState) -> MoveKey {
+        MoveKey::from(child.last_action.as_ref().unwrap())
+    }
+
+    #[test]
+    fn move_key_from_jump() {
+        let jump = Jump {
+            from: Position::new(0, 0),
+            to: Position::new(0, 2),
+            direction: Direction::Right,
+            captured: vec![Position::new(0, 1)],
+        };
+        let key = MoveKey::from(&KonaneAction::Jump(jump));
+        assert_eq!(key, MoveKey::Jump(Position::new(0, 0), Position::new(0, 2)));
+    }
+
+    #[test]
+    fn prefers_longer_multi_jumps() {
+        let state = play_state();
+        let mut children = KonaneMoveGenerator::default().generate(&state, 1);
+        MoveOrdering::new().sort(&state.inner, 1, &mut children);
+
+        let most_captures = children.iter().map(|child| static_scores(child, &[]).0).max();
+        assert_eq!(Some(static_scores(&children[0], &[]).0), most_captures);
+        assert_eq!(static_scores(&children[0], &[]).0, 2);
+    }
+
+    #[test]
+    fn hash_move_comes_first() {
+        let state = play_state();
+        let mut children = KonaneMoveGenerator::default().generate(&state, 1);
+        let last = key_of(children.last().unwrap());
+
+        let mut ordering = MoveOrdering::new();
+        ordering.record_best_move(state.fingerprint(), last);
+        ordering.sort(&state.inner, 1, &mut children);
+
+        assert_eq!(key_of(&children[0]), last);
+    }
+
+    #[test]
+    fn killer_moves_come_before_quiet_moves() {
+        let state = play_state();
+        let mut children = KonaneMoveGenerator::default().generate(&state, 1);
+        let killer = MoveKey::Jump(Position::new(2, 2), Position::new(2, 4));
+
+        let mut ordering = MoveOrdering::new();
+        ordering.record_cutoff(Cutoff {
+            position: 0,
+            depth: 3,
+            key: killer,
+        });
+        ordering.sort(&state.inner, 3, &mut children);
+
+        assert_eq!(key_of(&children[0]), killer);
+    }
+
+    #[test]
+    fn killers_only_apply_at_their_depth() {
+        let state = play_state();
+        let mut children = KonaneMoveGenerator::default().generate(&state, 1);
+        let killer = MoveKey::Jump(Position::new(2, 2), Position::new(2, 4));
+
+        let mut ordering = MoveOrdering::new();
+        ordering.killers.insert(3, [Some(killer), None]);
+        ordering.sort(&state.inner, 2, &mut children);
+
+        assert_ne!(key_of(&children[0]), killer);
+    }
+
+    #[test]
+    fn cutoffs_keep_two_killers_per_depth() {
+        let mut ordering = MoveOrdering::new();
+        let keys = [
+            MoveKey::Removal(Position::new(0, 0)),
+            MoveKey::Removal(Position::new(1, 1)),
+            MoveKey::Removal(Position::new(2, 2)),
+        ];
+        for key in keys {
+            ordering.record_cutoff(Cutoff { position: 0, depth: 1, key });
+        }
+        assert_eq!(ordering.killers[&1], [Some(keys[2]), Some(keys[1])]);
+    }
+
+    #[test]
+    fn history_grows_with_depth() {
+        let mut ordering = MoveOrdering::new();
+        let key = MoveKey::Removal(Position::new(0, 0));
+        ordering.record_cutoff(Cutoff { position: 0, depth: 3, key });
+        ordering.record_cutoff(Cutoff { position: 1, depth: 2, key });
+        assert_eq!(ordering.history[&key], 13);
+    }
+
+    #[test]
+    fn aging_halves_history() {
+        let mut ordering = MoveOrdering::new();
+        let strong = MoveKey::Removal(Position::new(0, 0));
+        let weak = MoveKey::Removal(Position::new(1, 1));
+        ordering.record_cutoff(Cutoff { position: 0, depth: 3, key: strong });
+        ordering.record_cutoff(Cutoff { position: 0, depth: 1, key: weak });
+
+        ordering.age();
+
+        assert_eq!(ordering.history[&strong], 4);
+        assert!(!ordering.history.contains_key(&weak));
+    }
+
+    #[test]
+    fn hash_moves_are_bounded() {
+        let mut ordering = MoveOrdering::new();
+        let key = MoveKey::Removal(Position::new(0, 0));
+        for position in 0..=MAX_HASH_MOVES as Z {
+            ordering.record_best_move(position, key);
+        }
+        assert!(ordering.hash_moves.len() <= MAX_HASH_MOVES);
+        assert_eq!(ordering.hash_moves.get(&(MAX_HASH_MOVES as Z)), Some(&key));
+    }
+
+    #[test]
+    fn reply_passes_over_its_own_squares() {
+        // d1 over d2 to d3
+        let reply = Jump {
+            from: Position::new(0, 3),
+            to: Position::new(2, 3),
+            direction: Direction::Up,
+            captured: vec![Position::new(1, 3)],
+        };
+        assert!(passes_over(&reply, Position::new(1, 3)));
+        assert!(passes_over(&reply, Position::new(2, 3)));
+        assert!(!passes_over(&reply, Position::new(1, 2)));
+        assert!(!passes_over(&reply, Position::new(3, 3)));
+    }
+}
@@ -1,4 +1,10 @@
-use crate::game::rules::Jump;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::game::rules::{Jump, Rules};
 use crate::game::state::*;
 
 // Represents a move that a player can make
@@ -9,6 +15,88 @@ pub enum PlayerMove {
     Jump(Jump),
 }
 
+impl PlayerMove {
+    // Format move in algebraic notation
+    pub fn to_algebraic(&self) -> String {
+        match self {
+            PlayerMove::OpeningRemoval(pos) => pos.to_algebraic(),
+            PlayerMove::Jump(jump) => format!("{}-{}", jump.from.to_algebraic(), jump.to.to_algebraic()),
+        }
+    }
+
+    // Find the legal move with the given squares. A jump's direction and captures are recovered from the rules.
+    // A removal is only checked for the phase; applying it checks the square.
+    pub fn resolve(state: &GameState, from: Position, to: Option<Position>) -> Result<PlayerMove, String> {
+        let size = state.board().size();
+        for pos in std::iter::once(from).chain(to) {
+            if pos.row >= size || pos.col >= size {
+                return Err(format!("{} is off the {}x{} board", pos, size, size));
+            }
+        }
+
+        match to {
+            None => {
+                if !matches!(state.current_phase(), GamePhase::OpeningBlackRemoval | GamePhase::OpeningWhiteRemoval) {
+                    return Err(format!("Expected a jump, found the removal {}", from));
+                }
+                Ok(PlayerMove::OpeningRemoval(from))
+            }
+            Some(to) => {
+                if !matches!(state.current_phase(), GamePhase::Play) {
+                    return Err(format!("Jump not allowed during {:?}", state.current_phase()));
+                }
+                Rules::valid_jumps_from(state, from)
+                    .into_iter()
+                    .find(|jump| jump.to == to)
+                    .map(PlayerMove::Jump)
+                    .ok_or_else(|| format!("Invalid jump from {} to {}", from, to))
+            }
+        }
+    }
+
+    // Parse a move in algebraic notation, such as "d4" or "f4-d4", and resolve it in the game state
+    pub fn from_algebraic(state: &GameState, notation: &str) -> Result<PlayerMove, String> {
+        let parse_position =
+            |s: &str| Position::from_algebraic(s).ok_or_else(|| format!("Invalid square \"{}\"", s.trim()));
+        match notation.split_once('-') {
+            Some((from, to)) => Self::resolve(state, parse_position(from)?, Some(parse_position(to)?)),
+            None => Self::resolve(state, parse_position(notation)?, None),
+        }
+    }
+
+    // Find the legal move that a record describes, in the position it was played from. The record's captures are
+    // not trusted; they are recovered from the rules.
+    pub fn from_record(state: &GameState, record: &MoveRecord) -> Result<PlayerMove, String> {
+        let (color, from, to) = match record {
+            MoveRecord::OpeningRemoval { color, position } => (*color, *position, None),
+            MoveRecord::Jump { color, from, to, .. } => (*color, *from, Some(*to)),
+        };
+        if color != state.current_player() {
+            return Err(format!("Expected {} to move, got {}", state.current_player(), color));
+        }
+        Self::resolve(state, from, to)
+    }
+
+    // Apply the move to the game state, returns the move record. The move must be legal in the state.
+    pub fn apply(&self, state: &mut GameState) -> Result<MoveRecord, &'static str> {
+        match self {
+            PlayerMove::OpeningRemoval(pos) => Rules::apply_opening_removal(state, *pos),
+            PlayerMove::Jump(jump) => {
+                if !matches!(state.current_phase(), GamePhase::Play) {
+                    return Err("Not in play phase");
+                }
+                let legal = Rules::valid_jumps_from(state, jump.from)
+                    .iter()
+                    .any(|valid| valid.to == jump.to && valid.captured == jump.captured);
+                if !legal {
+                    return Err("Illegal jump");
+                }
+                Ok(Rules::apply_jump(state, jump))
+            }
+        }
+    }
+}
+
 // Trait for player implementations
 // This allows for different player types (human, AI, network, etc.)
 #[allow(dead_code)]
@@ -80,6 +168,287 @@ impl Player for HumanPlayer {
     }
 }
 
+// Time allowed for an external engine to start and to reply with each move, unless set with `with_timeout`
+pub const DEFAULT_ENGINE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Time allowed for an external engine to answer `stop` after a move times out, before it is killed
+const STOP_GRACE: Duration = Duration::from_secs(1);
+
+// A player whose moves come from an external program speaking the engine protocol (see `crate::engine`).
+// The engine is given each position as a board, so it needs no knowledge of the game's history. An engine that
+// crashes, doesn't reply in time or plays an illegal move is reported as an error; one that stops responding is
+// killed.
+pub struct ExternalEnginePlayer {
+    color: PieceColor,
+    name: String,
+    // Taken only when the player is dropped, to be reaped on another thread
+    process: Option<Child>,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    timeout: Duration,
+    move_time: Option<Duration>,
+    board_size: Option<usize>,
+    running: bool,
+}
+
+impl ExternalEnginePlayer {
+    // Launch the engine and wait for it to identify itself. The command is the program followed by its arguments,
+    // separated by spaces, such as "konane engine --depth 4". Quotes keep spaces in a word, as in a program path
+    // like "'/opt/my engines/engine' --fast".
+    pub fn spawn(color: PieceColor, command: &str) -> Result<Self, String> {
+        Self::spawn_with_timeout(color, command, DEFAULT_ENGINE_TIMEOUT)
+    }
+
+    // Launch the engine, allowing it `timeout` to start and to reply with each move
+    pub fn spawn_with_timeout(color: PieceColor, command: &str, timeout: Duration) -> Result<Self, String> {
+        let words = split_command(command)?;
+        let (program, args) = words.split_first().ok_or_else(|| "No engine command given".to_string())?;
+        let mut process = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("Failed to start engine {}: {}", program, err))?;
+        let stdin = process.stdin.take().expect("stdin is piped");
+        let stdout = process.stdout.take().expect("stdout is piped");
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line.trim().to_string()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut player = Self {
+            color,
+            name: program.to_string(),
+            process: Some(process),
+            stdin,
+            lines,
+            timeout,
+            move_time: None,
+            board_size: None,
+            running: true,
+        };
+        player.handshake()?;
+        Ok(player)
+    }
+
+    // Ask the engine to search for a fixed time instead of to its own default depth
+    pub fn with_move_time(mut self, move_time: Duration) -> Self {
+        self.move_time = Some(move_time);
+        self
+    }
+
+    // Change the time the engine searches each move for, such as to a share of what is left on its clock. `None`
+    // lets it search to its own default depth again.
+    pub fn set_move_time(&mut self, move_time: Option<Duration>) {
+        self.move_time = move_time;
+    }
+
+    // The name the engine gave in its `id name` line, or the program if it gave none
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn handshake(&mut self) -> Result<(), String> {
+        self.send("konane")?;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let line = self.receive(deadline)?;
+            if line == "konaneok" {
+                return Ok(());
+            }
+            if let Some(name) = line.strip_prefix("id name ") {
+                self.name = name.to_string();
+            }
+        }
+    }
+
+    // Ask the engine for its move in the position. Fails if the engine crashed, timed out, reported an error or
+    // chose a move that isn't legal.
+    pub fn choose_move(&mut self, state: &GameState) -> Result<PlayerMove, String> {
+        if !self.running {
+            return Err(format!("Engine {} is not running", self.name));
+        }
+        if state.current_player() != self.color {
+            return Err(format!("It is not {}'s turn", self.color));
+        }
+
+        // Discard anything left over from an earlier move
+        while self.lines.try_recv().is_ok() {}
+
+        let size = state.board().size();
+        if self.board_size != Some(size) {
+            self.send(&format!("newgame {}", size))?;
+            self.board_size = Some(size);
+        }
+        let player = match state.current_player() {
+            PieceColor::Black => "black",
+            PieceColor::White => "white",
+        };
+        self.send(&format!("position board {} {}", state.board().to_cells(), player))?;
+        let mut allowed = self.timeout;
+        match self.move_time {
+            Some(move_time) => {
+                self.send(&format!("go movetime {}", move_time.as_millis()))?;
+                allowed += move_time;
+            }
+            None => self.send("go")?,
+        }
+
+        let notation = match self.wait_for_best_move(Instant::now() + allowed) {
+            Err(err) if self.running => {
+                // Timed out: give the engine a chance to stop, then give up on it
+                let _ = self.send("stop");
+                match self.wait_for_best_move(Instant::now() + STOP_GRACE) {
+                    Ok(notation) => notation,
+                    Err(_) if self.running => {
+                        self.kill();
+                        return Err(err);
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            result => result?,
+        };
+        if notation == "none" {
+            return Err(format!("Engine {} found no move", self.name));
+        }
+        PlayerMove::from_algebraic(state, &notation)
+            .map_err(|err| format!("Engine {} played an illegal move {}: {}", self.name, notation, err))
+    }
+
+    fn wait_for_best_move(&mut self, deadline: Instant) -> Result<String, String> {
+        loop {
+            let line = self.receive(deadline)?;
+            if let Some(notation) = line.strip_prefix("bestmove ") {
+                return Ok(notation.trim().to_string());
+            }
+            if let Some(error) = line.strip_prefix("error ") {
+                return Err(format!("Engine {} reported an error: {}", self.name, error));
+            }
+        }
+    }
+
+    // Receive the next line. Fails at the deadline or if the engine has exited.
+    fn receive(&mut self, deadline: Instant) -> Result<String, String> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match self.lines.recv_timeout(remaining) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => {
+                Err(format!("Engine {} did not reply within {:?}", self.name, self.timeout))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                self.running = false;
+                let status = match self.process().wait() {
+                    Ok(status) => status.to_string(),
+                    Err(err) => err.to_string(),
+                };
+                Err(format!("Engine {} exited ({})", self.name, status))
+            }
+        }
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", line)
+            .and_then(|()| self.stdin.flush())
+            .map_err(|err| format!("Failed to write to engine {}: {}", self.name, err))
+    }
+
+    fn kill(&mut self) {
+        self.running = false;
+        let process = self.process();
+        let _ = process.kill();
+        let _ = process.wait();
+    }
+
+    fn process(&mut self) -> &mut Child {
+        self.process.as_mut().expect("the process is only taken when dropped")
+    }
+}
+
+// Split an engine command into the program and its arguments. Words are separated by whitespace, and single or
+// double quotes keep whitespace in a word. There are no escapes, so Windows paths can be given as they are.
+fn split_command(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for c in command.chars() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => word.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(format!("Unterminated quote in engine command {}", command));
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+impl Player for ExternalEnginePlayer {
+    fn color(&self) -> PieceColor {
+        self.color
+    }
+
+    fn request_move(&mut self, state: &GameState) -> Option<PlayerMove> {
+        self.choose_move(state).ok()
+    }
+
+    // The engine decides on its own
+    fn receive_input(&mut self, _input: PlayerInput) {}
+
+    fn is_ready(&self) -> bool {
+        self.running
+    }
+}
+
+// Ask a running engine to quit. Waiting for it to exit happens on another thread, since the player may be dropped
+// on the UI thread; an engine still running after `STOP_GRACE` is killed.
+impl Drop for ExternalEnginePlayer {
+    fn drop(&mut self) {
+        if !self.running {
+            return;
+        }
+        let _ = self.send("quit");
+        let Some(mut process) = self.process.take() else {
+            return;
+        };
+        thread::spawn(move || {
+            let deadline = Instant::now() + STOP_GRACE;
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = process.try_wait() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            let _ = process.kill();
+            let _ = process.wait();
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -229,6 +598,155 @@ mod tests {
         }
     }
 
+    mod player_move_apply {
+        use super::*;
+
+        #[test]
+        fn to_algebraic_matches_move_record() {
+            let jump = Jump {
+                from: Position::new(0, 0),
+                to: Position::new(0, 2),
+                direction: Direction::Right,
+                captured: vec![Position::new(0, 1)],
+            };
+            assert_eq!(PlayerMove::Jump(jump).to_algebraic(), "a1-c1");
+            assert_eq!(PlayerMove::OpeningRemoval(Position::new(3, 4)).to_algebraic(), "e4");
+        }
+
+        #[test]
+        fn applies_opening_removal() {
+            let mut state = GameState::new(4, PieceColor::Black);
+            let record = PlayerMove::OpeningRemoval(Position::new(1, 1)).apply(&mut state);
+            assert!(matches!(record, Ok(MoveRecord::OpeningRemoval { .. })));
+            assert_eq!(state.current_phase(), GamePhase::OpeningWhiteRemoval);
+        }
+
+        #[test]
+        fn rejects_jump_during_opening() {
+            let mut state = GameState::new(4, PieceColor::Black);
+            let jump = Jump {
+                from: Position::new(0, 0),
+                to: Position::new(0, 2),
+                direction: Direction::Right,
+                captured: vec![Position::new(0, 1)],
+            };
+            assert!(PlayerMove::Jump(jump).apply(&mut state).is_err());
+        }
+
+        #[test]
+        fn rejects_illegal_jump() {
+            let mut state = GameState::new(4, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap();
+            let before = state.clone();
+            // d2 over the empty c2
+            let jump = Jump {
+                from: Position::new(1, 3),
+                to: Position::new(1, 1),
+                direction: Direction::Left,
+                captured: vec![Position::new(1, 2)],
+            };
+
+            assert_eq!(PlayerMove::Jump(jump).apply(&mut state).unwrap_err(), "Illegal jump");
+            assert_eq!(state.fingerprint(), before.fingerprint());
+        }
+
+        #[test]
+        fn applies_legal_jump() {
+            let mut state = GameState::new(4, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap();
+            let jump = Rules::valid_jumps_from(&state, Position::new(3, 1)).remove(0);
+
+            let record = PlayerMove::Jump(jump).apply(&mut state).unwrap();
+
+            assert_eq!(record.to_algebraic(), "b4-b2");
+        }
+    }
+
+    mod player_move_from_algebraic {
+        use super::*;
+
+        fn opened_board() -> GameState {
+            let mut state = GameState::new(4, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap();
+            state
+        }
+
+        #[test]
+        fn parses_removal() {
+            let state = GameState::new(4, PieceColor::Black);
+            let mv = PlayerMove::from_algebraic(&state, "b2").unwrap();
+            assert!(matches!(mv, PlayerMove::OpeningRemoval(pos) if pos == Position::new(1, 1)));
+        }
+
+        #[test]
+        fn recovers_jump_captures() {
+            match PlayerMove::from_algebraic(&opened_board(), "b4-b2") {
+                Ok(PlayerMove::Jump(jump)) => {
+                    assert_eq!(jump.direction, Direction::Down);
+                    assert_eq!(jump.captured, vec![Position::new(2, 1)]);
+                }
+                other => panic!("Expected Jump, got {:?}", other),
+            }
+        }
+
+        #[test]
+        fn rejects_illegal_jump() {
+            let err = PlayerMove::from_algebraic(&opened_board(), "a1-c1").unwrap_err();
+            assert_eq!(err, "Invalid jump from a1 to c1");
+        }
+
+        #[test]
+        fn rejects_removal_during_play() {
+            let err = PlayerMove::from_algebraic(&opened_board(), "a1").unwrap_err();
+            assert!(err.contains("Expected a jump"));
+        }
+
+        #[test]
+        fn rejects_squares_off_the_board() {
+            let err = PlayerMove::from_algebraic(&opened_board(), "b6-b4").unwrap_err();
+            assert!(err.contains("off the 4x4 board"));
+        }
+
+        #[test]
+        fn rejects_bad_square() {
+            assert!(PlayerMove::from_algebraic(&opened_board(), "b4-").is_err());
+        }
+    }
+
+    mod split_command {
+        use super::*;
+
+        #[test]
+        fn splits_on_whitespace() {
+            assert_eq!(split_command("  konane engine\t--depth 4 ").unwrap(), ["konane", "engine", "--depth", "4"]);
+        }
+
+        #[test]
+        fn quotes_keep_spaces() {
+            let words = split_command(r#""/opt/my engines/engine" --name 'Big Blue' C:\engine.exe"#).unwrap();
+
+            assert_eq!(words, ["/opt/my engines/engine", "--name", "Big Blue", r"C:\engine.exe"]);
+        }
+
+        #[test]
+        fn keeps_empty_quoted_words() {
+            assert_eq!(split_command("engine '' x").unwrap(), ["engine", "", "x"]);
+        }
+
+        #[test]
+        fn rejects_unterminated_quotes() {
+            assert!(split_command("\"/opt/engine --fast").is_err());
+        }
+
+        #[test]
+        fn empty_command_has_no_words() {
+            assert!(split_command("   ").unwrap().is_empty());
+        }
+    }
+
     mod player_input {
         use super::*;
 
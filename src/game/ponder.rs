@@ -0,0 +1,169 @@
+//! Pondering: letting the AI think on the opponent's time.
+//!
+//! `AiPlayer` is not `Send`, so it lives on a worker thread owned by a `Ponderer`. While the opponent is deciding,
+//! the worker searches the opponent's position, filling the AI's transposition table and move ordering tables. When
+//! the opponent moves, the ponder search is stopped and the AI searches the new position, which mostly revisits lines
+//! it has already analyzed.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::game::ai::AiPlayer;
+use crate::game::player::PlayerMove;
+use crate::game::report::SearchReport;
+use crate::game::state::GameState;
+
+/// The result of a search done by the worker.
+pub type SearchResult = (Option<PlayerMove>, SearchReport);
+
+enum Command {
+    /// Search the position until stopped. The number is the generation the request was made in.
+    Ponder(GameState, u64),
+    /// Choose a move in the position, within the time limit if there is one, and send it back.
+    Search(GameState, Option<Duration>, Sender<SearchResult>),
+    Shutdown,
+}
+
+/// Runs an `AiPlayer` on a background thread that can ponder between searches.
+pub struct Ponderer {
+    commands: Sender<Command>,
+    stop: Arc<AtomicBool>,
+    /// Incremented whenever pondering is stopped, so that a ponder request that is already stale when the worker
+    /// receives it is skipped.
+    generation: Arc<AtomicU64>,
+}
+
+impl Ponderer {
+    /// Starts the worker thread. The AI is created on the worker by `make_ai`.
+    pub fn spawn<F>(make_ai: F) -> Self
+    where
+        F: FnOnce() -> AiPlayer + Send + 'static,
+    {
+        let (commands, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let generation = Arc::new(AtomicU64::new(0));
+
+        let worker_stop = stop.clone();
+        let worker_generation = generation.clone();
+        thread::spawn(move || run_worker(make_ai(), receiver, &worker_stop, &worker_generation));
+
+        Self {
+            commands,
+            stop,
+            generation,
+        }
+    }
+
+    /// Starts pondering the position, stopping any earlier ponder search.
+    pub fn ponder(&self, state: &GameState) {
+        self.stop();
+        let generation = self.generation.load(Ordering::SeqCst);
+        let _ = self.commands.send(Command::Ponder(state.clone(), generation));
+    }
+
+    /// Stops pondering. Searches requested with `search` are not affected.
+    pub fn stop(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    /// Stops pondering and asks the AI to choose a move in the position. The result is sent on the returned channel
+    /// once the search completes. If the worker has died, the channel is closed without a result.
+    pub fn search(&self, state: &GameState) -> Receiver<SearchResult> {
+        self.send_search(state, None)
+    }
+
+    /// Like `search`, but the AI gives up after `move_time` and plays the best move found so far.
+    pub fn search_with_time_limit(&self, state: &GameState, move_time: Duration) -> Receiver<SearchResult> {
+        self.send_search(state, Some(move_time))
+    }
+
+    fn send_search(&self, state: &GameState, move_time: Option<Duration>) -> Receiver<SearchResult> {
+        self.stop();
+        let (reply, result) = mpsc::channel();
+        let _ = self.commands.send(Command::Search(state.clone(), move_time, reply));
+        result
+    }
+}
+
+impl Drop for Ponderer {
+    fn drop(&mut self) {
+        self.stop();
+        let _ = self.commands.send(Command::Shutdown);
+    }
+}
+
+fn run_worker(ai: AiPlayer, commands: Receiver<Command>, stop: &AtomicBool, generation: &AtomicU64) {
+    while let Ok(command) = commands.recv() {
+        match command {
+            Command::Ponder(state, requested) => {
+                // Clear the flag before checking the generation. If pondering is stopped after the check, the flag is
+                // set again and the search ends early.
+                stop.store(false, Ordering::SeqCst);
+                if generation.load(Ordering::SeqCst) == requested {
+                    ai.ponder(&state, stop);
+                }
+            }
+            Command::Search(state, move_time, reply) => {
+                let result = match move_time {
+                    Some(move_time) => ai.search_with_time_limit(&state, ai.depth(), move_time),
+                    None => ai.search(&state),
+                };
+                let _ = reply.send(result);
+            }
+            Command::Shutdown => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rules::Rules;
+    use crate::game::state::{PieceColor, Position};
+
+    fn opened_board() -> GameState {
+        let mut state = GameState::new(6, PieceColor::Black);
+        Rules::apply_opening_removal(&mut state, Position::new(2, 2)).unwrap();
+        Rules::apply_opening_removal(&mut state, Position::new(2, 3)).unwrap();
+        state
+    }
+
+    #[test]
+    fn search_returns_a_move() {
+        let ponderer = Ponderer::spawn(|| AiPlayer::new(PieceColor::Black, 2));
+        let state = opened_board();
+
+        let (mv, report) = ponderer.search(&state).recv().unwrap();
+
+        assert!(mv.is_some());
+        assert_eq!(report.depth_reached, 2);
+    }
+
+    #[test]
+    fn search_after_pondering_returns_a_move() {
+        let ponderer = Ponderer::spawn(|| AiPlayer::new(PieceColor::White, 3));
+        let mut state = opened_board();
+        ponderer.ponder(&state);
+
+        let jump = Rules::all_valid_jumps(&state).remove(0);
+        Rules::apply_jump(&mut state, &jump);
+        let (mv, _) = ponderer.search(&state).recv().unwrap();
+
+        assert!(matches!(mv, Some(PlayerMove::Jump(_))));
+    }
+
+    #[test]
+    fn search_with_time_limit_returns_a_move_in_time() {
+        let ponderer = Ponderer::spawn(|| AiPlayer::new(PieceColor::Black, 30));
+        let state = opened_board();
+
+        let (mv, report) = ponderer.search_with_time_limit(&state, Duration::from_millis(50)).recv().unwrap();
+
+        assert!(mv.is_some());
+        assert!(report.depth_reached < 30);
+    }
+}
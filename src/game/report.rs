@@ -0,0 +1,317 @@
+//! Search statistics for the AI.
+//!
+//! The search itself lives in `game_player`, which doesn't count its transposition table hits or its cutoffs, so the
+//! statistics are gathered by watching the calls it makes to the move generator and the static evaluator. Each call
+//! visits a node, so the node counts are exact. The other counts are inferred and can be wrong: a child that is
+//! skipped while a later sibling is searched is counted as a transposition table hit, and a node whose trailing
+//! children are never searched as a cutoff, although the last child searched may itself have been a table hit and
+//! the cutoff caused by an earlier one. The cutoffs also feed the move ordering tables.
+
+use serde::Serialize;
+
+use crate::game::ordering::{Cutoff, MoveKey};
+use crate::game::zhash::Z;
+
+/// Results of a single iteration of iterative deepening.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IterationReport {
+    pub depth: i32,
+    /// Best move found by this iteration in algebraic notation
+    pub best_move: Option<String>,
+    pub nodes: u64,
+    pub elapsed_ms: u64,
+}
+
+/// Statistics describing how the AI chose a move.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchReport {
+    /// Best move in algebraic notation
+    pub best_move: Option<String>,
+    /// True if the move came from the opening book and no search was done
+    pub from_book: bool,
+    /// Depth of the deepest completed iteration
+    pub depth_reached: i32,
+    pub nodes: u64,
+    /// Inferred from the order of the search, see the module documentation
+    pub tt_hits: u64,
+    /// Inferred from the order of the search, see the module documentation
+    pub cutoffs: u64,
+    pub effective_branching_factor: f64,
+    pub elapsed_ms: u64,
+    pub iterations: Vec<IterationReport>,
+}
+
+impl SearchReport {
+    /// Adds the results of an iteration and updates the totals.
+    pub fn add_iteration(&mut self, depth: i32, best_move: Option<String>, elapsed_ms: u64, counts: SearchCounts) {
+        self.nodes += counts.nodes;
+        self.tt_hits += counts.tt_hits;
+        self.cutoffs += counts.cutoffs;
+        self.elapsed_ms += elapsed_ms;
+        self.depth_reached = depth;
+        self.best_move = best_move.clone();
+        self.iterations.push(IterationReport {
+            depth,
+            best_move,
+            nodes: counts.nodes,
+            elapsed_ms,
+        });
+        self.effective_branching_factor = self.compute_effective_branching_factor();
+    }
+
+    /// The growth in nodes from one iteration to the next. With a single iteration, the branching factor that would
+    /// produce its node count at its depth.
+    fn compute_effective_branching_factor(&self) -> f64 {
+        match self.iterations.as_slice() {
+            [] => 0.0,
+            [only] => {
+                if only.depth > 0 && only.nodes > 0 {
+                    (only.nodes as f64).powf(1.0 / only.depth as f64)
+                } else {
+                    0.0
+                }
+            }
+            [.., previous, last] => {
+                if previous.nodes > 0 {
+                    last.nodes as f64 / previous.nodes as f64
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Counts gathered by a `SearchTracker`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchCounts {
+    pub nodes: u64,
+    pub tt_hits: u64,
+    pub cutoffs: u64,
+}
+
+/// A node whose children have been generated.
+struct Frame {
+    position: Z,
+    depth: i32,
+    children: Vec<(Z, MoveKey)>,
+    visited: Vec<bool>,
+}
+
+/// Infers search statistics from the order in which the search visits nodes.
+#[derive(Default)]
+pub struct SearchTracker {
+    counts: SearchCounts,
+    frames: Vec<Frame>,
+    cutoffs: Vec<Cutoff>,
+}
+
+impl SearchTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the search evaluated a node.
+    pub fn visit(&mut self, fingerprint: Z) {
+        while let Some(frame) = self.frames.last_mut() {
+            if let Some(index) = frame.children.iter().position(|&(child, _)| child == fingerprint) {
+                if !frame.visited[index] {
+                    frame.visited[index] = true;
+                    self.counts.nodes += 1;
+                }
+                return;
+            }
+            let frame = self.frames.pop().unwrap();
+            self.finish(frame);
+        }
+
+        // The root of the search
+        self.counts.nodes += 1;
+    }
+
+    /// Records that the search generated the children of a node, along with the move leading to each child.
+    pub fn expand(&mut self, fingerprint: Z, depth: i32, children: Vec<(Z, MoveKey)>) {
+        self.visit(fingerprint);
+        let visited = vec![false; children.len()];
+        self.frames.push(Frame {
+            position: fingerprint,
+            depth,
+            children,
+            visited,
+        });
+    }
+
+    /// Returns the moves that caused cutoffs since the last call.
+    pub fn take_cutoffs(&mut self) -> Vec<Cutoff> {
+        std::mem::take(&mut self.cutoffs)
+    }
+
+    /// Completes the search and returns the counts.
+    pub fn finish_search(&mut self) -> SearchCounts {
+        while let Some(frame) = self.frames.pop() {
+            self.finish(frame);
+        }
+        std::mem::take(&mut self.counts)
+    }
+
+    fn finish(&mut self, frame: Frame) {
+        let Some(last_visited) = frame.visited.iter().rposition(|&visited| visited) else {
+            return;
+        };
+
+        let skipped = frame.visited[..last_visited].iter().filter(|&&visited| !visited).count();
+        self.counts.tt_hits += skipped as u64;
+
+        if last_visited + 1 < frame.children.len() {
+            self.counts.cutoffs += 1;
+            self.cutoffs.push(Cutoff {
+                position: frame.position,
+                depth: frame.depth,
+                key: frame.children[last_visited].1,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod search_tracker {
+        use super::*;
+        use crate::game::state::Position;
+
+        fn key(fingerprint: Z) -> MoveKey {
+            MoveKey::Removal(Position::new(fingerprint as usize, 0))
+        }
+
+        fn children(fingerprints: &[Z]) -> Vec<(Z, MoveKey)> {
+            fingerprints.iter().map(|&fingerprint| (fingerprint, key(fingerprint))).collect()
+        }
+
+        #[test]
+        fn counts_every_visited_node_once() {
+            let mut tracker = SearchTracker::new();
+            tracker.expand(1, 1, children(&[10, 11]));
+            tracker.visit(10);
+            tracker.visit(10);
+            tracker.visit(11);
+            let counts = tracker.finish_search();
+            assert_eq!(counts.nodes, 3);
+            assert_eq!(counts.cutoffs, 0);
+            assert_eq!(counts.tt_hits, 0);
+        }
+
+        #[test]
+        fn unsearched_trailing_children_are_a_cutoff() {
+            let mut tracker = SearchTracker::new();
+            tracker.expand(1, 1, children(&[10, 11, 12]));
+            tracker.visit(10);
+            let counts = tracker.finish_search();
+            assert_eq!(counts.cutoffs, 1);
+        }
+
+        #[test]
+        fn records_the_move_that_caused_a_cutoff() {
+            let mut tracker = SearchTracker::new();
+            tracker.expand(1, 3, children(&[10, 11, 12]));
+            tracker.visit(10);
+            tracker.visit(11);
+            tracker.finish_search();
+
+            let cutoffs = tracker.take_cutoffs();
+            assert_eq!(
+                cutoffs,
+                vec![Cutoff {
+                    position: 1,
+                    depth: 3,
+                    key: key(11),
+                }]
+            );
+            assert!(tracker.take_cutoffs().is_empty());
+        }
+
+        #[test]
+        fn skipped_children_are_tt_hits() {
+            let mut tracker = SearchTracker::new();
+            tracker.expand(1, 1, children(&[10, 11, 12]));
+            tracker.visit(10);
+            tracker.visit(12);
+            let counts = tracker.finish_search();
+            assert_eq!(counts.tt_hits, 1);
+            assert_eq!(counts.cutoffs, 0);
+        }
+
+        #[test]
+        fn unwinds_finished_subtrees() {
+            let mut tracker = SearchTracker::new();
+            tracker.expand(1, 1, children(&[10, 11]));
+            tracker.expand(10, 1, children(&[20, 21]));
+            tracker.visit(20);
+            // Returning to the root's second child finishes node 10 with a cutoff
+            tracker.visit(11);
+            let counts = tracker.finish_search();
+            assert_eq!(counts.nodes, 4);
+            assert_eq!(counts.cutoffs, 1);
+        }
+
+        #[test]
+        fn finish_search_resets_counts() {
+            let mut tracker = SearchTracker::new();
+            tracker.expand(1, 1, children(&[10]));
+            tracker.finish_search();
+            assert_eq!(tracker.finish_search(), SearchCounts::default());
+        }
+    }
+
+    mod search_report {
+        use super::*;
+
+        fn counts(nodes: u64) -> SearchCounts {
+            SearchCounts {
+                nodes,
+                tt_hits: 1,
+                cutoffs: 2,
+            }
+        }
+
+        #[test]
+        fn accumulates_iterations() {
+            let mut report = SearchReport::default();
+            report.add_iteration(1, Some("d4".to_string()), 1, counts(5));
+            report.add_iteration(2, Some("e4".to_string()), 1, counts(20));
+
+            assert_eq!(report.depth_reached, 2);
+            assert_eq!(report.nodes, 25);
+            assert_eq!(report.tt_hits, 2);
+            assert_eq!(report.cutoffs, 4);
+            assert_eq!(report.iterations.len(), 2);
+            assert_eq!(report.iterations[0].best_move.as_deref(), Some("d4"));
+            assert_eq!(report.best_move.as_deref(), Some("e4"));
+        }
+
+        #[test]
+        fn branching_factor_is_growth_between_iterations() {
+            let mut report = SearchReport::default();
+            report.add_iteration(1, None, 1, counts(5));
+            report.add_iteration(2, None, 1, counts(20));
+            assert_eq!(report.effective_branching_factor, 4.0);
+        }
+
+        #[test]
+        fn branching_factor_for_single_iteration() {
+            let mut report = SearchReport::default();
+            report.add_iteration(2, None, 1, counts(16));
+            assert_eq!(report.effective_branching_factor, 4.0);
+        }
+
+        #[test]
+        fn serializes_to_json() {
+            let mut report = SearchReport::default();
+            report.add_iteration(1, Some("d4".to_string()), 1, counts(5));
+            let json = serde_json::to_string(&report).unwrap();
+            assert!(json.contains("\"depth_reached\":1"));
+            assert!(json.contains("\"best_move\":\"d4\""));
+        }
+    }
+}
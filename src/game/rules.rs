@@ -1,3 +1,4 @@
+use crate::game::player::PlayerMove;
 use crate::game::state::*;
 
 #[derive(Debug, Clone)]
@@ -136,6 +137,43 @@ impl Rules {
         jumps
     }
 
+    /// All legal moves for the current player in a fixed order: the removals in the order listed above during the
+    /// opening, then the jumps in `all_valid_jumps` order. Binary game records refer to moves by index in this list.
+    pub fn legal_moves(state: &GameState) -> Vec<PlayerMove> {
+        match state.current_phase() {
+            GamePhase::OpeningBlackRemoval => Self::valid_black_opening_removals(state)
+                .into_iter()
+                .map(PlayerMove::OpeningRemoval)
+                .collect(),
+            GamePhase::OpeningWhiteRemoval => Self::valid_white_opening_removals(state)
+                .into_iter()
+                .map(PlayerMove::OpeningRemoval)
+                .collect(),
+            GamePhase::Play => Self::all_valid_jumps(state).into_iter().map(PlayerMove::Jump).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Counts the move sequences of the given length from a position, stopping early at the end of the game. Used to
+    /// check move generation against known counts.
+    pub fn perft(state: &GameState, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = Self::legal_moves(state);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .iter()
+            .map(|player_move| {
+                let mut next = state.clone();
+                player_move.apply(&mut next).expect("legal moves can always be applied");
+                Self::perft(&next, depth - 1)
+            })
+            .sum()
+    }
+
     // Check if the current player has any valid moves
     pub fn has_valid_move(state: &GameState) -> bool {
         match state.current_phase() {
@@ -226,6 +264,19 @@ impl Rules {
             _ => Err("Not in opening phase"),
         }
     }
+
+    /// Replays a game from the start, returning the starting position followed by the position after each move.
+    pub fn replay(board_size: usize, history: &MoveHistory) -> Result<Vec<GameState>, String> {
+        let mut state = GameState::new(board_size, PieceColor::Black);
+        let mut positions = vec![state.clone()];
+        for (index, record) in history.iter().enumerate() {
+            PlayerMove::from_record(&state, record)
+                .and_then(|player_move| player_move.apply(&mut state).map_err(str::to_string))
+                .map_err(|err| format!("Move {}: {}", index + 1, err))?;
+            positions.push(state.clone());
+        }
+        Ok(positions)
+    }
 }
 
 #[cfg(test)]
@@ -720,6 +771,110 @@ mod tests {
         }
     }
 
+    mod legal_moves {
+        use super::*;
+
+        #[test]
+        fn lists_removals_during_the_opening() {
+            let mut state = GameState::new(4, PieceColor::Black);
+
+            let black: Vec<String> = Rules::legal_moves(&state).iter().map(PlayerMove::to_algebraic).collect();
+            Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap();
+            let white: Vec<String> = Rules::legal_moves(&state).iter().map(PlayerMove::to_algebraic).collect();
+
+            assert_eq!(black, ["b2", "c3", "a1", "d4"]);
+            assert_eq!(white.len(), 4);
+            assert!(white.contains(&"c2".to_string()));
+        }
+
+        #[test]
+        fn lists_jumps_in_play() {
+            let state = setup_play_phase();
+
+            let moves = Rules::legal_moves(&state);
+
+            assert_eq!(moves.len(), Rules::all_valid_jumps(&state).len());
+            assert!(moves.iter().all(|m| matches!(m, PlayerMove::Jump(_))));
+        }
+
+        #[test]
+        fn is_empty_when_the_game_is_over() {
+            let mut state = setup_play_phase();
+            state.change_phase(GamePhase::GameOver { winner: PieceColor::Black });
+
+            assert!(Rules::legal_moves(&state).is_empty());
+        }
+    }
+
+    mod perft {
+        use super::*;
+
+        #[test]
+        fn counts_the_opening() {
+            let state = GameState::new(4, PieceColor::Black);
+
+            assert_eq!(Rules::perft(&state, 0), 1);
+            assert_eq!(Rules::perft(&state, 1), 4);
+            // Each of Black's four removals leaves White two or four stones to remove
+            assert_eq!(Rules::perft(&state, 2), 12);
+        }
+
+        #[test]
+        fn matches_the_legal_moves_at_depth_one() {
+            let state = setup_play_phase();
+
+            assert_eq!(Rules::perft(&state, 1), Rules::legal_moves(&state).len() as u64);
+        }
+
+        #[test]
+        fn sums_the_children() {
+            let state = setup_play_phase();
+
+            let children: u64 = Rules::legal_moves(&state)
+                .iter()
+                .map(|player_move| {
+                    let mut next = state.clone();
+                    player_move.apply(&mut next).unwrap();
+                    Rules::perft(&next, 1)
+                })
+                .sum();
+
+            assert_eq!(Rules::perft(&state, 2), children);
+        }
+    }
+
+    mod replay {
+        use super::*;
+
+        fn opening() -> MoveHistory {
+            let mut state = GameState::new(4, PieceColor::Black);
+            vec![
+                Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap(),
+                Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap(),
+            ]
+        }
+
+        #[test]
+        fn returns_start_and_each_position() {
+            let positions = Rules::replay(4, &opening()).unwrap();
+
+            assert_eq!(positions.len(), 3);
+            assert_eq!(positions[0].current_phase(), GamePhase::OpeningBlackRemoval);
+            assert!(positions[1].board().is_empty(Position::new(1, 1)));
+            assert_eq!(positions[2].current_phase(), GamePhase::Play);
+        }
+
+        #[test]
+        fn reports_the_illegal_move() {
+            let mut history = opening();
+            history.swap(0, 1);
+
+            let err = Rules::replay(4, &history).unwrap_err();
+
+            assert!(err.starts_with("Move 1:"), "{}", err);
+        }
+    }
+
     mod integration {
         use super::*;
 
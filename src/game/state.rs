@@ -1,9 +1,11 @@
 use ndarray::Array2;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::game::rules::Rules;
 use crate::game::zhash::{Z, ZHash};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum PieceColor {
     Black,
     White,
@@ -32,7 +34,7 @@ impl std::fmt::Display for PieceColor {
 /// - col 0 is the leftmost column (file 'a')
 /// - rows increase upward
 /// - columns increase to the right
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub struct Position {
     pub row: usize,
     pub col: usize,
@@ -51,7 +53,7 @@ impl Position {
     }
 
     /// Parse from algebraic notation (e.g., "a1", "e4")
-    pub fn _from_algebraic(s: &str) -> Option<Self> {
+    pub fn from_algebraic(s: &str) -> Option<Self> {
         let s = s.trim().to_lowercase();
         let mut chars = s.chars();
         let file = chars.next()?;
@@ -116,7 +118,7 @@ pub enum GamePhase {
     GameOver { winner: PieceColor },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum MoveRecord {
     OpeningRemoval {
         color: PieceColor,
@@ -131,6 +133,13 @@ pub enum MoveRecord {
 }
 
 impl MoveRecord {
+    /// The color of the player who made the move
+    pub fn color(&self) -> PieceColor {
+        match self {
+            MoveRecord::OpeningRemoval { color, .. } | MoveRecord::Jump { color, .. } => *color,
+        }
+    }
+
     /// Format move in algebraic notation
     pub fn to_algebraic(&self) -> String {
         match self {
@@ -193,6 +202,47 @@ impl Board {
         Self { size, cells }
     }
 
+    /// Reads a board written by `to_cells`. Every stone must be on a square of its own color, where the stones of
+    /// that color start.
+    pub fn from_cells(cells: &str) -> Result<Self, String> {
+        let count = cells.chars().count();
+        let size = (4..=16)
+            .step_by(2)
+            .find(|size| size * size == count)
+            .ok_or_else(|| format!("{} squares is not an even board from 4x4 to 16x16", count))?;
+
+        let mut board = Self::new(size);
+        for (i, symbol) in cells.chars().enumerate() {
+            let pos = Position::new(i / size, i % size);
+            let start_color = board.get_piece_color(pos);
+            let cell = match symbol {
+                'b' | 'B' => Cell::Occupied(PieceColor::Black),
+                'w' | 'W' => Cell::Occupied(PieceColor::White),
+                '.' => Cell::Empty,
+                _ => return Err(format!("Invalid square '{}' at {}", symbol, pos)),
+            };
+            if let Cell::Occupied(color) = cell
+                && Some(color) != start_color
+            {
+                return Err(format!("A {} stone can't be on {}", color, pos));
+            }
+            board.set(pos, cell);
+        }
+        Ok(board)
+    }
+
+    /// Writes the board as one character per square, row by row from row 1: `b`, `w` or `.` for an empty square.
+    pub fn to_cells(&self) -> String {
+        self.cells
+            .iter()
+            .map(|cell| match cell {
+                Cell::Occupied(PieceColor::Black) => 'b',
+                Cell::Occupied(PieceColor::White) => 'w',
+                Cell::Empty => '.',
+            })
+            .collect()
+    }
+
     pub fn size(&self) -> usize {
         self.size
     }
@@ -282,6 +332,48 @@ impl GameState {
         }
     }
 
+    /// Sets up a position from a board and the player to move. The phase follows from the number of empty squares:
+    /// none before Black's opening removal, one before White's, and more during play. A single empty square must be
+    /// one Black may open by removing.
+    pub fn from_board(board: Board, current_player: PieceColor) -> Result<Self, String> {
+        let size = board.size();
+        let empty: Vec<Position> = (0..size * size)
+            .map(|i| Position::new(i / size, i % size))
+            .filter(|&pos| board.is_empty(pos))
+            .collect();
+        let (phase, opening_position) = match empty.as_slice() {
+            [] => (GamePhase::OpeningBlackRemoval, None),
+            [removed] => (GamePhase::OpeningWhiteRemoval, Some(*removed)),
+            _ => (GamePhase::Play, None),
+        };
+        if let Some(removed) = opening_position
+            && !Rules::valid_black_opening_removals(&GameState::new(size, PieceColor::Black)).contains(&removed)
+        {
+            return Err(format!("Black can't open by removing {}", removed));
+        }
+        let expected = match phase {
+            GamePhase::OpeningBlackRemoval => Some(PieceColor::Black),
+            GamePhase::OpeningWhiteRemoval => Some(PieceColor::White),
+            _ => None,
+        };
+        if expected.is_some_and(|expected| expected != current_player) {
+            return Err(format!("{} can't be to move during {:?}", current_player, phase));
+        }
+
+        let fingerprint = ZHash::from_state(&board, &phase, current_player);
+        let mut state = Self {
+            board,
+            phase,
+            current_player,
+            opening_position,
+            fingerprint,
+        };
+        if phase == GamePhase::Play && !Rules::has_valid_move(&state) {
+            state.change_phase(GamePhase::GameOver { winner: current_player.opposite() });
+        }
+        Ok(state)
+    }
+
     /// Returns a reference to the board
     pub fn board(&self) -> &Board {
         &self.board
@@ -398,29 +490,29 @@ mod tests {
 
         #[test]
         fn from_algebraic_parses_valid_notation() {
-            assert_eq!(Position::_from_algebraic("a1"), Some(Position::new(0, 0)));
-            assert_eq!(Position::_from_algebraic("e4"), Some(Position::new(3, 4)));
-            assert_eq!(Position::_from_algebraic("h8"), Some(Position::new(7, 7)));
+            assert_eq!(Position::from_algebraic("a1"), Some(Position::new(0, 0)));
+            assert_eq!(Position::from_algebraic("e4"), Some(Position::new(3, 4)));
+            assert_eq!(Position::from_algebraic("h8"), Some(Position::new(7, 7)));
         }
 
         #[test]
         fn from_algebraic_handles_uppercase() {
-            assert_eq!(Position::_from_algebraic("A1"), Some(Position::new(0, 0)));
-            assert_eq!(Position::_from_algebraic("E4"), Some(Position::new(3, 4)));
+            assert_eq!(Position::from_algebraic("A1"), Some(Position::new(0, 0)));
+            assert_eq!(Position::from_algebraic("E4"), Some(Position::new(3, 4)));
         }
 
         #[test]
         fn from_algebraic_rejects_invalid_input() {
-            assert_eq!(Position::_from_algebraic(""), None);
-            assert_eq!(Position::_from_algebraic("a0"), None);
-            assert_eq!(Position::_from_algebraic("1a"), None);
-            assert_eq!(Position::_from_algebraic("aa"), None);
+            assert_eq!(Position::from_algebraic(""), None);
+            assert_eq!(Position::from_algebraic("a0"), None);
+            assert_eq!(Position::from_algebraic("1a"), None);
+            assert_eq!(Position::from_algebraic("aa"), None);
         }
 
         #[test]
         fn from_algebraic_handles_double_digit_ranks() {
-            assert_eq!(Position::_from_algebraic("a10"), Some(Position::new(9, 0)));
-            assert_eq!(Position::_from_algebraic("p16"), Some(Position::new(15, 15)));
+            assert_eq!(Position::from_algebraic("a10"), Some(Position::new(9, 0)));
+            assert_eq!(Position::from_algebraic("p16"), Some(Position::new(15, 15)));
         }
 
         #[test]
@@ -435,7 +527,7 @@ mod tests {
                 for col in 0..8 {
                     let pos = Position::new(row, col);
                     let algebraic = pos.to_algebraic();
-                    let parsed = Position::_from_algebraic(&algebraic);
+                    let parsed = Position::from_algebraic(&algebraic);
                     assert_eq!(parsed, Some(pos));
                 }
             }
@@ -625,6 +717,25 @@ mod tests {
             assert!(neighbors.contains(&Position::new(1, 0))); // Up
             assert!(neighbors.contains(&Position::new(0, 1))); // Right
         }
+
+        #[test]
+        fn cells_round_trip() {
+            let mut board = Board::new(4);
+            board.remove_stone(Position::new(1, 1));
+
+            let cells = board.to_cells();
+
+            assert_eq!(cells, "bwbww.wbbwbwwbwb");
+            assert_eq!(Board::from_cells(&cells).unwrap().to_cells(), cells);
+        }
+
+        #[test]
+        fn from_cells_rejects_bad_boards() {
+            assert!(Board::from_cells("bwbw").is_err());
+            assert!(Board::from_cells("bwbwwbwbbwbwwbwx").is_err());
+            // A black stone on a white square
+            assert!(Board::from_cells("bbbwwbwbbwbwwbwb").is_err());
+        }
     }
 
     mod move_record {
@@ -650,6 +761,22 @@ mod tests {
             assert_eq!(record.to_algebraic(), "a1-c1");
         }
 
+        #[test]
+        fn color_is_the_mover() {
+            let removal = MoveRecord::OpeningRemoval {
+                color: PieceColor::Black,
+                position: Position::new(3, 4),
+            };
+            let jump = MoveRecord::Jump {
+                color: PieceColor::White,
+                from: Position::new(0, 0),
+                to: Position::new(0, 2),
+                captured: vec![Position::new(0, 1)],
+            };
+            assert_eq!(removal.color(), PieceColor::Black);
+            assert_eq!(jump.color(), PieceColor::White);
+        }
+
         #[test]
         fn display_opening_removal() {
             let record = MoveRecord::OpeningRemoval {
@@ -690,5 +817,51 @@ mod tests {
             let state = GameState::new(8, PieceColor::Black);
             assert!(state.opening_position.is_none());
         }
+
+        #[test]
+        fn from_board_finds_the_opening_phases() {
+            let mut board = Board::new(4);
+            let start = GameState::from_board(board.clone(), PieceColor::Black).unwrap();
+            board.remove_stone(Position::new(1, 1));
+            let after_black = GameState::from_board(board, PieceColor::White).unwrap();
+
+            assert_eq!(start.fingerprint(), GameState::new(4, PieceColor::Black).fingerprint());
+            assert_eq!(after_black.current_phase(), GamePhase::OpeningWhiteRemoval);
+            assert_eq!(after_black.get_opening_position(), Some(Position::new(1, 1)));
+        }
+
+        #[test]
+        fn from_board_matches_a_played_position() {
+            let mut state = GameState::new(4, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap();
+
+            let rebuilt = GameState::from_board(state.board().clone(), state.current_player()).unwrap();
+
+            assert_eq!(rebuilt.current_phase(), GamePhase::Play);
+            assert_eq!(rebuilt.fingerprint(), state.fingerprint());
+        }
+
+        #[test]
+        fn from_board_rejects_the_wrong_player_in_the_opening() {
+            assert!(GameState::from_board(Board::new(4), PieceColor::White).is_err());
+        }
+
+        #[test]
+        fn from_board_rejects_an_opening_removal_black_cannot_make() {
+            let mut board = Board::new(4);
+            board.remove_stone(Position::new(0, 1));
+
+            assert!(GameState::from_board(board, PieceColor::White).is_err());
+        }
+
+        #[test]
+        fn from_board_detects_the_end_of_the_game() {
+            let board = Board::from_cells("b...............").unwrap();
+
+            let state = GameState::from_board(board, PieceColor::White).unwrap();
+
+            assert_eq!(state.current_phase(), GamePhase::GameOver { winner: PieceColor::Black });
+        }
     }
 }
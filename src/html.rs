@@ -0,0 +1,156 @@
+//! A self-contained HTML page for reviewing a game in a browser.
+//!
+//! The page embeds every position of the game as data and draws the board with a small script, so it needs no other
+//! files or network access. It has first, previous, next and last buttons, arrow-key navigation, a clickable move list
+//! and the result.
+
+use serde::Serialize;
+
+use crate::game::{MoveHistory, MoveRecord, PieceColor, Position, Rules};
+use crate::pgn::result_code;
+
+const TEMPLATE: &str = include_str!("html/viewer.html");
+
+#[derive(Serialize)]
+struct ViewerData {
+    board_size: usize,
+    result: String,
+    /// Each position as one character per square, row by row from row 1: `b`, `w` or `.`
+    positions: Vec<String>,
+    moves: Vec<ViewerMove>,
+}
+
+#[derive(Serialize)]
+struct ViewerMove {
+    notation: String,
+    color: String,
+    /// The start square of a jump, or the square emptied by a removal, as `[row, col]`
+    from: [usize; 2],
+    to: Option<[usize; 2]>,
+    captured: Vec<[usize; 2]>,
+}
+
+impl ViewerMove {
+    fn new(record: &MoveRecord) -> Self {
+        let square = |pos: &Position| [pos.row, pos.col];
+        match record {
+            MoveRecord::OpeningRemoval { color, position } => Self {
+                notation: record.to_algebraic(),
+                color: color.to_string(),
+                from: square(position),
+                to: None,
+                captured: Vec::new(),
+            },
+            MoveRecord::Jump {
+                color,
+                from,
+                to,
+                captured,
+            } => Self {
+                notation: record.to_algebraic(),
+                color: color.to_string(),
+                from: square(from),
+                to: Some(square(to)),
+                captured: captured.iter().map(square).collect(),
+            },
+        }
+    }
+}
+
+/// Writes a game as an HTML page with an interactive board. Fails if the moves are not legal.
+pub fn game_to_html(board_size: usize, winner: Option<PieceColor>, history: &MoveHistory) -> Result<String, String> {
+    let positions = Rules::replay(board_size, history)?;
+    let data = ViewerData {
+        board_size,
+        result: match winner {
+            Some(color) => format!("{} wins ({})", color, result_code(winner)),
+            None => "Unfinished".to_string(),
+        },
+        positions: positions.iter().map(|state| state.board().to_cells()).collect(),
+        moves: history.iter().map(ViewerMove::new).collect(),
+    };
+    let json = serde_json::to_string(&data).map_err(|err| format!("Failed to serialize game: {}", err))?;
+
+    // Keep the data from closing the script element it is embedded in
+    let json = json.replace("</", "<\\/");
+    let title = format!("Kōnane game, {}x{} board", board_size, board_size);
+    Ok(TEMPLATE.replace("{{TITLE}}", &title).replace("{{GAME_DATA}}", &json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    /// The 4x4 opening b2, c2, then Black's b4-b2.
+    fn short_game() -> MoveHistory {
+        let mut state = GameState::new(4, PieceColor::Black);
+        let mut history = MoveHistory::new();
+        history.push(Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap());
+        history.push(Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap());
+        let jump = Rules::all_valid_jumps(&state).remove(0);
+        history.push(Rules::apply_jump(&mut state, &jump));
+        history
+    }
+
+    /// Extracts the embedded game data from a page.
+    fn embedded_data(html: &str) -> serde_json::Value {
+        let start = html.find("const GAME = ").unwrap() + "const GAME = ".len();
+        let end = start + html[start..].find(";\n").unwrap();
+        serde_json::from_str(&html[start..end].replace("<\\/", "</")).unwrap()
+    }
+
+    mod page {
+        use super::*;
+
+        #[test]
+        fn is_a_complete_document() {
+            let html = game_to_html(4, None, &short_game()).unwrap();
+
+            assert!(html.starts_with("<!DOCTYPE html>"));
+            assert!(html.contains("<title>Kōnane game, 4x4 board</title>"));
+            assert!(!html.contains("{{"));
+            assert!(html.trim_end().ends_with("</html>"));
+        }
+
+        #[test]
+        fn embeds_every_position() {
+            let data = embedded_data(&game_to_html(4, None, &short_game()).unwrap());
+
+            let positions = data["positions"].as_array().unwrap();
+            assert_eq!(positions.len(), 4);
+            assert_eq!(positions[0], "bwbwwbwbbwbwwbwb");
+            assert_eq!(positions[1], "bwbww.wbbwbwwbwb");
+        }
+
+        #[test]
+        fn embeds_moves_with_captures() {
+            let data = embedded_data(&game_to_html(4, None, &short_game()).unwrap());
+
+            let moves = data["moves"].as_array().unwrap();
+            assert_eq!(moves[0]["notation"], "b2");
+            assert_eq!(moves[0]["to"], serde_json::Value::Null);
+            assert_eq!(moves[2]["notation"], "b4-b2");
+            assert_eq!(moves[2]["captured"], serde_json::json!([[2, 1]]));
+        }
+
+        #[test]
+        fn shows_the_result() {
+            let unfinished = embedded_data(&game_to_html(4, None, &short_game()).unwrap());
+            let won = embedded_data(&game_to_html(4, Some(PieceColor::Black), &short_game()).unwrap());
+
+            assert_eq!(unfinished["result"], "Unfinished");
+            assert_eq!(won["result"], "Black wins (1-0)");
+        }
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let mut history = short_game();
+        history.remove(1);
+
+        let err = game_to_html(4, None, &history).unwrap_err();
+
+        assert!(err.starts_with("Move 2:"), "{}", err);
+    }
+}
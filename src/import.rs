@@ -1,26 +1,73 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 
+use crate::game::player::PlayerMove;
 use crate::game::rules::Jump;
-use crate::game::{GamePhase, GameState, MoveHistory, MoveRecord, PieceColor, Position, Rules, UndoRedoStack};
+use crate::game::{GamePhase, GameState, MoveHistory, MoveRecord, PieceColor, Position, Rules, TimeControl, UndoRedoStack};
+use crate::pgn::{self, PgnGame};
 
-#[allow(dead_code)]
-#[derive(Deserialize)]
+/// Version of the JSON game format written by this version of Kōnane. Files written before the format was versioned
+/// have no `format_version` and are read as version 0.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// A game in the JSON format.
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[schemars(title = "Kōnane game")]
 pub struct ImportedGame {
+    /// Version of the file format
+    #[schemars(range(min = 1, max = FORMAT_VERSION))]
+    pub format_version: u32,
+    /// Width and height of the board: even, from 4 to 16
+    #[schemars(range(min = 4, max = 16))]
     pub board_size: usize,
+    /// "Black" or "White", present only for completed games
     pub winner: Option<String>,
+    /// Number of moves, which must match the length of `moves` when present
+    pub total_moves: Option<usize>,
+    /// Moves in the order they were played, starting with Black's opening removal
     pub moves: MoveHistory,
+    /// Time control the game was played with: minutes plus seconds a move such as "5+3", or seconds a move such as
+    /// "30s"
+    pub time_control: Option<String>,
+    /// Milliseconds left on the mover's clock after each move, one for each move
+    pub clocks: Option<Vec<u64>>,
+    /// "Black" or "White": the player whose clock ran out, ending the game
+    pub lost_on_time: Option<String>,
+}
+
+/// Returns the JSON Schema describing the current version of the JSON game format.
+pub fn game_schema() -> String {
+    let schema = schemars::schema_for!(ImportedGame);
+    serde_json::to_string_pretty(&schema).expect("schema is serializable")
 }
 
-/// Returns (final_state, move_history, undo_stack)
+/// Returns (final_state, move_history, undo_stack). The file may be in the JSON, PGN or algebraic text format.
 pub fn import_game_from_path(path: &str) -> Result<(GameState, MoveHistory, UndoRedoStack), String> {
     let content = std::fs::read_to_string(path).map_err(|err| format!("Failed to read file: {}", err))?;
-    import_game_from_content(&content)
+    match content.trim_start().chars().next() {
+        Some('{') => import_game_from_content(&content),
+        Some('[') => import_pgn_game_from_content(&content),
+        _ => import_text_game_from_content(&content),
+    }
 }
 
 pub fn import_game_from_content(content: &str) -> Result<(GameState, MoveHistory, UndoRedoStack), String> {
-    let imported: ImportedGame = serde_json::from_str(content).map_err(|err| format!("Invalid JSON: {}", err))?;
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|err| format!("Invalid JSON: {}", err))?;
+    let imported: ImportedGame =
+        serde_json::from_value(migrate(value)?).map_err(|err| format!("Invalid JSON: {}", err))?;
 
     validate_board_size(imported.board_size)?;
+    let lost_on_time = validate_clock(&imported)?;
+    if let Some(total_moves) = imported.total_moves
+        && total_moves != imported.moves.len()
+    {
+        return Err(format!(
+            "total_moves is {} but the game has {} moves",
+            total_moves,
+            imported.moves.len()
+        ));
+    }
 
     let mut state = GameState::new(imported.board_size, PieceColor::Black);
     let mut move_history: MoveHistory = Vec::new();
@@ -33,11 +80,583 @@ pub fn import_game_from_content(content: &str) -> Result<(GameState, MoveHistory
         move_history.push(move_record);
     }
 
-    validate_winner(&state, imported.winner)?;
+    validate_winner(&state, imported.winner, lost_on_time)?;
+
+    Ok((state, move_history, undo_stack))
+}
+
+/// Imports the main line of a game in the PGN format. Comments and variations are checked but not imported.
+pub fn import_pgn_game_from_content(content: &str) -> Result<(GameState, MoveHistory, UndoRedoStack), String> {
+    let game = PgnGame::parse(content)?;
+
+    let mut state = GameState::new(game.board_size()?, PieceColor::Black);
+    let mut move_history: MoveHistory = Vec::new();
+    let mut undo_stack = Vec::new();
+
+    for (index, record) in game.move_history().into_iter().enumerate() {
+        undo_stack.push((state.clone(), move_history.clone()));
+        let move_record = validate_and_apply_move(&mut state, record, index + 1)?;
+        move_history.push(move_record);
+    }
+
+    Ok((state, move_history, undo_stack))
+}
+
+/// A move parsed from the text format, along with the line it was found on.
+struct TextMove {
+    line: usize,
+    number: usize,
+    from: Position,
+    to: Option<Position>,
+}
+
+/// The contents of a game in the text format, before the moves have been checked against the rules.
+#[derive(Default)]
+struct TextGame {
+    board_size: Option<(usize, usize)>,
+    moves: Vec<TextMove>,
+    /// The result code and its line. A winner of None means the game is unfinished (`*`).
+    result: Option<(usize, Option<PieceColor>)>,
+}
+
+/// Imports a game in the text format written by the game over screen:
+///
+/// ```text
+/// Board size: 8
+/// 1. d4
+/// 2. e4
+/// 3. b4-d4
+/// 4. g4-e4
+/// *
+/// ```
+///
+/// The header is optional. Without it, the board size is the smallest one that fits the coordinates and on which the
+/// moves replay to the given result. The result code is `1-0` if Black won, `0-1` if White won, or `*` if the game is
+/// unfinished, and may be omitted. Errors give the line number they were found on.
+pub fn import_text_game_from_content(content: &str) -> Result<(GameState, MoveHistory, UndoRedoStack), String> {
+    let game = parse_text_game(content)?;
+
+    if let Some((line, board_size)) = game.board_size {
+        validate_board_size(board_size).map_err(|err| format!("Line {}: {}", line, err))?;
+        return replay_text_game(&game, board_size);
+    }
+
+    // The smallest board that holds every coordinate
+    let largest = game
+        .moves
+        .iter()
+        .flat_map(|mv| std::iter::once(mv.from).chain(mv.to))
+        .map(|pos| pos.row.max(pos.col) + 1)
+        .max()
+        .unwrap_or(4);
+    let smallest = largest.max(4).next_multiple_of(2);
+    if smallest > 16 {
+        return Err("Coordinates do not fit on a board of at most 16x16".to_string());
+    }
+
+    let mut first_error = None;
+    for board_size in (smallest..=16).step_by(2) {
+        match replay_text_game(&game, board_size) {
+            Ok(imported) => return Ok(imported),
+            Err(err) => {
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    Err(first_error.unwrap())
+}
+
+fn parse_text_game(content: &str) -> Result<TextGame, String> {
+    let mut game = TextGame::default();
+
+    for (index, raw_line) in content.lines().enumerate() {
+        let line = index + 1;
+        let text = raw_line.trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        if let Some((line, _)) = game.result {
+            return Err(format!("Line {}: Unexpected text after the result on line {}", index + 1, line));
+        }
+
+        if let Some(size) = text.strip_prefix("Board size:") {
+            if !game.moves.is_empty() || game.board_size.is_some() {
+                return Err(format!("Line {}: The board size must be given once, before the moves", line));
+            }
+            let size = size
+                .trim()
+                .parse()
+                .map_err(|_| format!("Line {}: Invalid board size \"{}\"", line, size.trim()))?;
+            game.board_size = Some((line, size));
+            continue;
+        }
+
+        let winner = match text {
+            "1-0" => Some(Some(PieceColor::Black)),
+            "0-1" => Some(Some(PieceColor::White)),
+            "*" => Some(None),
+            _ => None,
+        };
+        if let Some(winner) = winner {
+            game.result = Some((line, winner));
+            continue;
+        }
+
+        game.moves.push(parse_text_move(text, line)?);
+    }
+
+    Ok(game)
+}
+
+/// Parses a line such as `3. f4-d4` or `1. d4`.
+fn parse_text_move(text: &str, line: usize) -> Result<TextMove, String> {
+    let Some((number, notation)) = text.split_once('.') else {
+        return Err(format!("Line {}: Expected a numbered move such as \"3. f4-d4\", found \"{}\"", line, text));
+    };
+    let number = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("Line {}: Invalid move number \"{}\"", line, number.trim()))?;
+
+    let parse_position = |s: &str| {
+        Position::from_algebraic(s).ok_or_else(|| format!("Line {}: Invalid square \"{}\"", line, s.trim()))
+    };
+    let (from, to) = match notation.split_once('-') {
+        Some((from, to)) => (parse_position(from)?, Some(parse_position(to)?)),
+        None => (parse_position(notation)?, None),
+    };
+
+    Ok(TextMove { line, number, from, to })
+}
+
+fn replay_text_game(game: &TextGame, board_size: usize) -> Result<(GameState, MoveHistory, UndoRedoStack), String> {
+    let mut state = GameState::new(board_size, PieceColor::Black);
+    let mut move_history: MoveHistory = Vec::new();
+    let mut undo_stack = Vec::new();
+
+    for (index, text_move) in game.moves.iter().enumerate() {
+        let line = text_move.line;
+        if text_move.number != index + 1 {
+            return Err(format!("Line {}: Expected move {}, found move {}", line, index + 1, text_move.number));
+        }
+
+        undo_stack.push((state.clone(), move_history.clone()));
+        let record = PlayerMove::resolve(&state, text_move.from, text_move.to)
+            .and_then(|mv| mv.apply(&mut state).map_err(str::to_string))
+            .map_err(|err| format!("Line {}: {}", line, err))?;
+        move_history.push(record);
+    }
+
+    if let Some((line, winner)) = game.result {
+        match (winner, state.current_phase()) {
+            (Some(winner), GamePhase::GameOver { winner: actual }) if winner != actual => {
+                return Err(format!("Line {}: Result says {} won, but {} won", line, winner, actual));
+            }
+            (Some(_), GamePhase::GameOver { .. }) | (None, _) => {}
+            (Some(_), _) => return Err(format!("Line {}: Result given but the game is not over", line)),
+        }
+    }
 
     Ok((state, move_history, undo_stack))
 }
 
+/// Plays moves in algebraic notation from the start of a game, such as `d4 e4 b4-d4`. Moves are separated by spaces
+/// or commas, and move numbers such as `1.` are skipped.
+pub fn import_move_list(board_size: usize, moves: &str) -> Result<(GameState, MoveHistory, UndoRedoStack), String> {
+    validate_board_size(board_size)?;
+    let mut state = GameState::new(board_size, PieceColor::Black);
+    let mut move_history = MoveHistory::new();
+    let mut undo_stack = UndoRedoStack::new();
+
+    let notations = moves
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty() && !token.trim_end_matches('.').chars().all(|c| c.is_ascii_digit()));
+    for (index, notation) in notations.enumerate() {
+        undo_stack.push((state.clone(), move_history.clone()));
+        let record = PlayerMove::from_algebraic(&state, notation)
+            .and_then(|mv| mv.apply(&mut state).map_err(str::to_string))
+            .map_err(|err| format!("Move {} ({}): {}", index + 1, notation, err))?;
+        move_history.push(record);
+    }
+
+    Ok((state, move_history, undo_stack))
+}
+
+/// Why a move in an imported game could not be played, with the position it was tried in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveDiagnostic {
+    pub move_number: usize,
+    /// Line of the move in a text game
+    pub line: Option<usize>,
+    /// The move as written in the file, in algebraic notation
+    pub found: String,
+    pub expected_player: PieceColor,
+    pub phase: GamePhase,
+    /// The error the strict import reports
+    pub error: String,
+    /// A guess at what is wrong with the file
+    pub cause: String,
+    /// The moves that were legal in the position, in algebraic notation
+    pub legal_moves: Vec<String>,
+}
+
+impl std::fmt::Display for MoveDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\nLikely cause: {}", self.error, self.cause)?;
+        if !self.legal_moves.is_empty() {
+            write!(f, "\nLegal moves for {}: {}", self.expected_player, self.legal_moves.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of a lenient import: the game up to its first bad move, and what was wrong with the rest.
+#[derive(Debug, Clone)]
+pub struct LenientImport {
+    pub state: GameState,
+    pub move_history: MoveHistory,
+    pub undo_stack: UndoRedoStack,
+    /// The first move that could not be played. It and the moves after it are not imported.
+    pub error: Option<MoveDiagnostic>,
+    /// Number of moves in the file, including those not imported
+    pub total_moves: usize,
+    /// Problems that did not stop the import, such as a winner that does not match the moves
+    pub warnings: Vec<String>,
+}
+
+impl LenientImport {
+    /// Whether the whole file was imported without problems.
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none() && self.warnings.is_empty()
+    }
+}
+
+/// Imports as much of a game as can be played, in any of the formats accepted by `import_game_from_path`. Files that
+/// cannot be read or parsed at all are still errors; moves that break the rules end the import with a diagnostic.
+pub fn import_game_lenient_from_path(path: &str) -> Result<LenientImport, String> {
+    let content = std::fs::read_to_string(path).map_err(|err| format!("Failed to read file: {}", err))?;
+    import_game_lenient_from_content(&content)
+}
+
+pub fn import_game_lenient_from_content(content: &str) -> Result<LenientImport, String> {
+    match content.trim_start().chars().next() {
+        Some('{') => import_json_lenient(content),
+        Some('[') => {
+            // PGN parsing rejects illegal moves, so the main line is read without the rules and replayed like text
+            let (board_size, notations) = pgn::main_line_moves(content)?;
+            let mut moves = Vec::new();
+            for (index, (line, notation)) in notations.into_iter().enumerate() {
+                let mut text_move = parse_text_move(&format!("{}. {}", index + 1, notation), line)?;
+                text_move.number = index + 1;
+                moves.push(text_move);
+            }
+            Ok(replay_text_lenient(board_size, &moves))
+        }
+        _ => import_text_lenient(content),
+    }
+}
+
+fn import_json_lenient(content: &str) -> Result<LenientImport, String> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|err| format!("Invalid JSON: {}", err))?;
+    let imported: ImportedGame =
+        serde_json::from_value(migrate(value)?).map_err(|err| format!("Invalid JSON: {}", err))?;
+    validate_board_size(imported.board_size)?;
+    let lost_on_time = validate_clock(&imported);
+
+    let moves = imported.moves;
+    let mut result = replay_lenient(imported.board_size, moves.len(), |_, index| Ok(moves[index].clone()));
+    if let Some(total_moves) = imported.total_moves
+        && total_moves != moves.len()
+    {
+        result
+            .warnings
+            .push(format!("total_moves is {} but the game has {} moves", total_moves, moves.len()));
+    }
+    match lost_on_time {
+        Err(err) => result.warnings.push(err),
+        Ok(lost_on_time) => {
+            if result.error.is_none()
+                && let Err(err) = validate_winner(&result.state, imported.winner, lost_on_time)
+            {
+                result.warnings.push(err);
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn import_text_lenient(content: &str) -> Result<LenientImport, String> {
+    let game = parse_text_game(content)?;
+    let board_size = match game.board_size {
+        Some((line, board_size)) => {
+            validate_board_size(board_size).map_err(|err| format!("Line {}: {}", line, err))?;
+            board_size
+        }
+        // Without a header, the smallest board that holds every coordinate
+        None => game
+            .moves
+            .iter()
+            .flat_map(|mv| std::iter::once(mv.from).chain(mv.to))
+            .map(|pos| pos.row.max(pos.col) + 1)
+            .max()
+            .unwrap_or(4)
+            .clamp(4, 16)
+            .next_multiple_of(2),
+    };
+
+    let mut result = replay_text_lenient(board_size, &game.moves);
+    if result.error.is_none()
+        && let Some((line, Some(winner))) = game.result
+    {
+        match result.state.current_phase() {
+            GamePhase::GameOver { winner: actual } if actual != winner => {
+                result.warnings.push(format!("Line {}: Result says {} won, but {} won", line, winner, actual));
+            }
+            GamePhase::GameOver { .. } => {}
+            _ => result.warnings.push(format!("Line {}: Result given but the game is not over", line)),
+        }
+    }
+    Ok(result)
+}
+
+fn replay_text_lenient(board_size: usize, moves: &[TextMove]) -> LenientImport {
+    let mut result = replay_lenient(board_size, moves.len(), |state, index| {
+        let text_move = &moves[index];
+        let record = text_move_record(state, text_move);
+        if text_move.number != index + 1 {
+            return Err((
+                record.to_algebraic(),
+                format!("Line {}: Expected move {}, found move {}", text_move.line, index + 1, text_move.number),
+                "A move is missing or the moves are numbered out of order".to_string(),
+            ));
+        }
+        // The rules check gives better errors than validating a record with guessed captures
+        PlayerMove::resolve(state, text_move.from, text_move.to).map_err(|err| {
+            (record.to_algebraic(), format!("Line {}: {}", text_move.line, err), likely_cause(state, &record))
+        })?;
+        Ok(record)
+    });
+
+    if let Some(ref mut diagnostic) = result.error {
+        let line = moves[diagnostic.move_number - 1].line;
+        diagnostic.line = Some(line);
+        let prefix = format!("Move {}: ", diagnostic.move_number);
+        if let Some(error) = diagnostic.error.strip_prefix(&prefix) {
+            diagnostic.error = format!("Line {}: {}", line, error);
+        }
+    }
+    result
+}
+
+/// The record of a text move as it would be played in the position. Captures are worked out from the rules, and left
+/// empty when the jump is not legal.
+fn text_move_record(state: &GameState, text_move: &TextMove) -> MoveRecord {
+    let color = state.current_player();
+    let Some(to) = text_move.to else {
+        return MoveRecord::OpeningRemoval {
+            color,
+            position: text_move.from,
+        };
+    };
+    let captured = if in_bounds(state, text_move.from) {
+        Rules::valid_jumps_from(state, text_move.from)
+            .into_iter()
+            .find(|jump| jump.to == to)
+            .map(|jump| jump.captured)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    MoveRecord::Jump {
+        color,
+        from: text_move.from,
+        to,
+        captured,
+    }
+}
+
+/// Plays moves until one breaks the rules. `record_at` gives the record of each move in the position it is played
+/// in, or the move as written, an error and its likely cause if the move can't be played.
+fn replay_lenient<F>(board_size: usize, total_moves: usize, mut record_at: F) -> LenientImport
+where
+    F: FnMut(&GameState, usize) -> Result<MoveRecord, (String, String, String)>,
+{
+    let mut state = GameState::new(board_size, PieceColor::Black);
+    let mut move_history: MoveHistory = Vec::new();
+    let mut undo_stack = Vec::new();
+    let mut error = None;
+
+    for index in 0..total_moves {
+        let move_number = index + 1;
+        let record = match record_at(&state, index) {
+            Ok(record) => record,
+            Err((found, message, cause)) => {
+                error = Some(diagnose(&state, move_number, found, message, cause));
+                break;
+            }
+        };
+
+        let mut next = state.clone();
+        match validate_and_apply_move(&mut next, record.clone(), move_number) {
+            Ok(move_record) => {
+                undo_stack.push((std::mem::replace(&mut state, next), move_history.clone()));
+                move_history.push(move_record);
+            }
+            Err(message) => {
+                let cause = likely_cause(&state, &record);
+                error = Some(diagnose(&state, move_number, record.to_algebraic(), message, cause));
+                break;
+            }
+        }
+    }
+
+    LenientImport {
+        state,
+        move_history,
+        undo_stack,
+        error,
+        total_moves,
+        warnings: Vec::new(),
+    }
+}
+
+fn diagnose(state: &GameState, move_number: usize, found: String, error: String, cause: String) -> MoveDiagnostic {
+    MoveDiagnostic {
+        move_number,
+        line: None,
+        found,
+        expected_player: state.current_player(),
+        phase: state.current_phase(),
+        error,
+        cause,
+        legal_moves: legal_moves(state),
+    }
+}
+
+fn legal_moves(state: &GameState) -> Vec<String> {
+    let removals = match state.current_phase() {
+        GamePhase::OpeningBlackRemoval => Rules::valid_black_opening_removals(state),
+        GamePhase::OpeningWhiteRemoval => Rules::valid_white_opening_removals(state),
+        GamePhase::Play => {
+            return Rules::all_valid_jumps(state)
+                .iter()
+                .map(|jump| format!("{}-{}", jump.from.to_algebraic(), jump.to.to_algebraic()))
+                .collect();
+        }
+        _ => Vec::new(),
+    };
+    removals.iter().map(|pos| pos.to_algebraic()).collect()
+}
+
+/// Guesses why a move could not be played, for reporting to the user.
+fn likely_cause(state: &GameState, record: &MoveRecord) -> String {
+    let expected = state.current_player();
+    let phase = state.current_phase();
+    let size = state.board().size();
+    let (color, squares) = match record {
+        MoveRecord::OpeningRemoval { color, position } => (*color, vec![*position]),
+        MoveRecord::Jump { color, from, to, captured } => {
+            (*color, std::iter::once(*from).chain(std::iter::once(*to)).chain(captured.iter().copied()).collect())
+        }
+    };
+
+    if let GamePhase::GameOver { winner } = phase {
+        return format!("The game was already over with {} the winner, so the file has extra moves", winner);
+    }
+    if color != expected {
+        return format!(
+            "The move is recorded for {} but it is {}'s turn; a move is probably missing or repeated before it",
+            color, expected
+        );
+    }
+    if let Some(pos) = squares.iter().find(|pos| !in_bounds(state, **pos)) {
+        return format!("{} is off the {}x{} board; the board size may be wrong", pos, size, size);
+    }
+
+    match (record, phase) {
+        (MoveRecord::OpeningRemoval { .. }, GamePhase::Play) => {
+            "An opening removal after both opening removals were made; the moves may be out of order".to_string()
+        }
+        (MoveRecord::Jump { .. }, GamePhase::OpeningBlackRemoval | GamePhase::OpeningWhiteRemoval) => {
+            "A jump before both opening removals were made; an opening removal is probably missing".to_string()
+        }
+        (MoveRecord::OpeningRemoval { position, .. }, GamePhase::OpeningBlackRemoval) => {
+            format!("Black must open by removing a stone from the center or a corner, not {}", position)
+        }
+        (MoveRecord::OpeningRemoval { position, .. }, GamePhase::OpeningWhiteRemoval) => {
+            format!("White must remove a stone next to the square Black emptied, not {}", position)
+        }
+        (MoveRecord::Jump { from, to, captured, .. }, _) => match state.board().get_piece_color(*from) {
+            None => format!("There is no stone on {}; an earlier move may have been recorded wrongly", from),
+            Some(owner) if owner != expected => format!("{} holds a {} stone, not one of {}'s", from, owner, expected),
+            Some(_) => {
+                let jumps = Rules::valid_jumps_from(state, *from);
+                if let Some(jump) = jumps.iter().find(|jump| jump.to == *to) {
+                    format!(
+                        "The jump is legal but its captures are recorded as [{}] instead of [{}]",
+                        join_squares(captured),
+                        join_squares(&jump.captured)
+                    )
+                } else if jumps.is_empty() {
+                    format!("The stone on {} has no jumps", from)
+                } else {
+                    let targets: Vec<String> = jumps.iter().map(|jump| jump.to.to_algebraic()).collect();
+                    format!("The stone on {} can't reach {}; it can jump to {}", from, to, targets.join(", "))
+                }
+            }
+        },
+        _ => format!("The move can't be played during {:?}", phase),
+    }
+}
+
+fn join_squares(squares: &[Position]) -> String {
+    squares.iter().map(|pos| pos.to_algebraic()).collect::<Vec<_>>().join(", ")
+}
+
+fn in_bounds(state: &GameState, position: Position) -> bool {
+    let size = state.board().size();
+    position.row < size && position.col < size
+}
+
+/// Upgrades a game written in an older version of the format to the current version, one version at a time.
+fn migrate(mut value: serde_json::Value) -> Result<serde_json::Value, String> {
+    let Some(object) = value.as_object_mut() else {
+        return Err("Invalid JSON: expected an object".to_string());
+    };
+
+    let mut version = match object.get("format_version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| format!("Invalid format_version: {}", version))?,
+    };
+    if version > FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported format_version {}: this version of Kōnane reads versions up to {}. Upgrade Kōnane to import this file.",
+            version, FORMAT_VERSION
+        ));
+    }
+
+    while version < FORMAT_VERSION {
+        match version {
+            // Version 1 added the version field itself
+            0 => {
+                object.insert("format_version".to_string(), 1.into());
+            }
+            // Version 2 added the optional clock fields, so version 1 files are already valid
+            1 => {
+                object.insert("format_version".to_string(), 2.into());
+            }
+            _ => unreachable!("no migration from format_version {}", version),
+        }
+        version += 1;
+    }
+
+    Ok(value)
+}
+
 fn validate_board_size(board_size: usize) -> Result<(), String> {
     if !(4..=16).contains(&board_size) || !board_size.is_multiple_of(2) {
         return Err("Invalid board_size: must be even and between 4 and 16".to_string());
@@ -137,7 +756,50 @@ fn validate_position_in_bounds(state: &GameState, position: Position, move_numbe
     Ok(())
 }
 
-fn validate_winner(state: &GameState, winner: Option<String>) -> Result<(), String> {
+/// Checks the clock fields of a game played with a time control. Returns the player who lost on time, if any.
+fn validate_clock(imported: &ImportedGame) -> Result<Option<PieceColor>, String> {
+    let Some(ref time_control) = imported.time_control else {
+        if imported.clocks.is_some() || imported.lost_on_time.is_some() {
+            return Err("clocks and lost_on_time need a time_control".to_string());
+        }
+        return Ok(None);
+    };
+    time_control.parse::<TimeControl>()?;
+    if let Some(ref clocks) = imported.clocks
+        && clocks.len() != imported.moves.len()
+    {
+        return Err(format!(
+            "The game has {} moves but {} clock times",
+            imported.moves.len(),
+            clocks.len()
+        ));
+    }
+    let Some(ref loser) = imported.lost_on_time else {
+        return Ok(None);
+    };
+    parse_winner_color(loser)
+        .map(Some)
+        .map_err(|_| "Invalid lost_on_time: must be \"Black\" or \"White\"".to_string())
+}
+
+/// Checks the winner against the final position. A game lost on time is not over on the board: the player to move
+/// lost when their clock ran out.
+fn validate_winner(state: &GameState, winner: Option<String>, lost_on_time: Option<PieceColor>) -> Result<(), String> {
+    if let Some(loser) = lost_on_time {
+        if let GamePhase::GameOver { .. } = state.current_phase() {
+            return Err("lost_on_time given but the game was over on the board".to_string());
+        }
+        if loser != state.current_player() {
+            return Err(format!("lost_on_time is {} but it was {}'s turn", loser, state.current_player()));
+        }
+        let winner = winner.ok_or("lost_on_time given without a winner")?;
+        let winner_color = parse_winner_color(&winner)?;
+        if winner_color != loser.opposite() {
+            return Err(format!("Winner mismatch: expected {}, got {}", winner_color, loser.opposite()));
+        }
+        return Ok(());
+    }
+
     let Some(winner) = winner else {
         return Ok(());
     };
@@ -548,4 +1210,440 @@ mod tests {
             assert!(undo_stack.is_empty());
         }
     }
+
+    mod text_format {
+        use super::*;
+
+        /// Plays the first legal move until the game ends and writes the game in the text format.
+        fn played_game_text(board_size: usize, header: bool) -> (String, GameState) {
+            let center = board_size / 2 - 1;
+            let mut state = GameState::new(board_size, PieceColor::Black);
+            Rules::apply_opening_removal(&mut state, Position::new(center, center)).unwrap();
+            Rules::apply_opening_removal(&mut state, Position::new(center, center + 1)).unwrap();
+            while matches!(state.current_phase(), GamePhase::Play) {
+                let jump = Rules::all_valid_jumps(&state).remove(0);
+                Rules::apply_jump(&mut state, &jump);
+            }
+
+            let mut replay = GameState::new(board_size, PieceColor::Black);
+            let mut text = if header { format!("Board size: {}\n", board_size) } else { String::new() };
+            let mut number = 0;
+            let mut record_move = |record: MoveRecord, text: &mut String| {
+                number += 1;
+                text.push_str(&format!("{}. {}\n", number, record.to_algebraic()));
+            };
+            record_move(Rules::apply_opening_removal(&mut replay, Position::new(center, center)).unwrap(), &mut text);
+            record_move(Rules::apply_opening_removal(&mut replay, Position::new(center, center + 1)).unwrap(), &mut text);
+            while matches!(replay.current_phase(), GamePhase::Play) {
+                let jump = Rules::all_valid_jumps(&replay).remove(0);
+                record_move(Rules::apply_jump(&mut replay, &jump), &mut text);
+            }
+            let GamePhase::GameOver { winner } = state.current_phase() else {
+                unreachable!();
+            };
+            text.push_str(if winner == PieceColor::Black { "1-0\n" } else { "0-1\n" });
+            (text, state)
+        }
+
+        #[test]
+        fn imports_a_finished_game() {
+            let (text, expected) = played_game_text(6, true);
+            let (state, move_history, undo_stack) = import_text_game_from_content(&text).unwrap();
+            assert_eq!(state.current_phase(), expected.current_phase());
+            assert_eq!(state.fingerprint(), expected.fingerprint());
+            assert_eq!(move_history.len(), undo_stack.len());
+        }
+
+        #[test]
+        fn recovers_captures_from_the_rules() {
+            let text = "1. b2\n2. c2\n3. b4-b2\n";
+            let (_, move_history, _) = import_text_game_from_content(&format!("Board size: 4\n{}", text)).unwrap();
+            match &move_history[2] {
+                MoveRecord::Jump { captured, .. } => assert_eq!(captured, &vec![Position::new(2, 1)]),
+                other => panic!("Expected a jump, got {:?}", other),
+            }
+        }
+
+        #[test]
+        fn infers_board_size_without_header() {
+            let (text, expected) = played_game_text(4, false);
+            let (state, _, _) = import_text_game_from_content(&text).unwrap();
+            assert_eq!(state.board().size(), 4);
+            assert_eq!(state.fingerprint(), expected.fingerprint());
+        }
+
+        #[test]
+        fn infers_board_size_from_coordinates() {
+            let (state, _, _) = import_text_game_from_content("1. a1\n2. a2\n").unwrap_or_else(|err| panic!("{}", err));
+            assert_eq!(state.board().size(), 4);
+
+            let result = import_text_game_from_content("1. f6\n");
+            // f6 is a corner only on a 6x6 board
+            assert_eq!(result.map(|(state, _, _)| state.board().size()), Ok(6));
+        }
+
+        #[test]
+        fn reports_line_of_invalid_jump() {
+            let text = "Board size: 4\n1. b2\n2. c2\n3. a1-c1\n";
+            let err = import_text_game_from_content(text).unwrap_err();
+            assert!(err.starts_with("Line 4:"), "Unexpected error: {}", err);
+            assert!(err.contains("Invalid jump from a1 to c1"));
+        }
+
+        #[test]
+        fn reports_line_of_bad_square() {
+            let err = import_text_game_from_content("Board size: 4\n1. b2\n2. z\n").unwrap_err();
+            assert!(err.starts_with("Line 3:"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn rejects_out_of_order_move_numbers() {
+            let err = import_text_game_from_content("Board size: 4\n1. b2\n3. c2\n").unwrap_err();
+            assert!(err.contains("Line 3: Expected move 2, found move 3"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn rejects_wrong_result() {
+            let (text, _) = played_game_text(4, true);
+            let flipped = if text.contains("1-0") { text.replace("1-0", "0-1") } else { text.replace("0-1", "1-0") };
+            let err = import_text_game_from_content(&flipped).unwrap_err();
+            assert!(err.contains("Result says"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn rejects_result_before_game_over() {
+            let err = import_text_game_from_content("Board size: 4\n1. b2\n2. c2\n1-0\n").unwrap_err();
+            assert_eq!(err, "Line 4: Result given but the game is not over");
+        }
+
+        #[test]
+        fn accepts_unfinished_game() {
+            let (state, _, _) = import_text_game_from_content("Board size: 4\n1. b2\n2. c2\n*\n").unwrap();
+            assert_eq!(state.current_phase(), GamePhase::Play);
+        }
+
+        #[test]
+        fn rejects_text_after_result() {
+            let err = import_text_game_from_content("Board size: 4\n1. b2\n*\n2. c2\n").unwrap_err();
+            assert!(err.starts_with("Line 4:"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn rejects_invalid_header_size() {
+            let err = import_text_game_from_content("Board size: 5\n1. b2\n").unwrap_err();
+            assert!(err.starts_with("Line 1:") && err.contains("even"), "Unexpected error: {}", err);
+        }
+    }
+
+    mod format_version {
+        use super::*;
+
+        #[test]
+        fn reads_unversioned_files_as_version_0() {
+            let json = r#"{ "board_size": 4, "moves": [] }"#;
+            assert!(import_game_from_content(json).is_ok());
+        }
+
+        #[test]
+        fn reads_current_version() {
+            let json = format!(r#"{{ "format_version": {}, "board_size": 4, "moves": [] }}"#, FORMAT_VERSION);
+            assert!(import_game_from_content(&json).is_ok());
+        }
+
+        #[test]
+        fn rejects_newer_version() {
+            let json = r#"{ "format_version": 99, "board_size": 4, "moves": [] }"#;
+            let err = import_game_from_content(json).unwrap_err();
+            assert!(err.contains("Unsupported format_version 99"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn rejects_invalid_version() {
+            let json = r#"{ "format_version": "one", "board_size": 4, "moves": [] }"#;
+            let err = import_game_from_content(json).unwrap_err();
+            assert!(err.contains("Invalid format_version"));
+        }
+
+        #[test]
+        fn rejects_unknown_fields() {
+            let json = r#"{ "format_version": 1, "board_size": 4, "moves": [], "clock": 10 }"#;
+            let err = import_game_from_content(json).unwrap_err();
+            assert!(err.contains("unknown field"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn checks_total_moves() {
+            let json = r#"{ "board_size": 4, "total_moves": 3, "moves": [] }"#;
+            let err = import_game_from_content(json).unwrap_err();
+            assert!(err.contains("total_moves is 3"));
+        }
+
+        #[test]
+        fn schema_describes_current_version() {
+            let schema: serde_json::Value = serde_json::from_str(&game_schema()).unwrap();
+            assert_eq!(schema["title"], "Kōnane game");
+            let required = schema["required"].as_array().unwrap();
+            assert!(required.contains(&"format_version".into()));
+            assert!(schema["properties"]["moves"].is_object());
+        }
+    }
+
+    mod clocks {
+        use super::*;
+
+        const OPENING: &str = r#"[
+            {"OpeningRemoval": {"color": "Black", "position": {"row": 1, "col": 1}}},
+            {"OpeningRemoval": {"color": "White", "position": {"row": 1, "col": 2}}}]"#;
+
+        fn timed_game(fields: &str) -> String {
+            format!(r#"{{ "format_version": 2, "board_size": 4, "moves": {}, "time_control": "5+3", {} }}"#, OPENING, fields)
+        }
+
+        #[test]
+        fn reads_a_game_lost_on_time() {
+            let json = timed_game(r#""clocks": [299000, 301500], "winner": "White", "lost_on_time": "Black""#);
+
+            let (_, history, _) = import_game_from_content(&json).unwrap();
+
+            assert_eq!(history.len(), 2);
+        }
+
+        #[test]
+        fn loser_on_time_must_be_the_player_to_move() {
+            let json = timed_game(r#""winner": "Black", "lost_on_time": "White""#);
+
+            let err = import_game_from_content(&json).unwrap_err();
+
+            assert_eq!(err, "lost_on_time is White but it was Black's turn");
+        }
+
+        #[test]
+        fn winner_on_time_is_the_opponent() {
+            let json = timed_game(r#""winner": "Black", "lost_on_time": "Black""#);
+
+            let err = import_game_from_content(&json).unwrap_err();
+
+            assert!(err.starts_with("Winner mismatch"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn needs_one_clock_time_per_move() {
+            let err = import_game_from_content(&timed_game(r#""clocks": [299000]"#)).unwrap_err();
+
+            assert_eq!(err, "The game has 2 moves but 1 clock times");
+        }
+
+        #[test]
+        fn clocks_need_a_time_control() {
+            let json = format!(r#"{{ "format_version": 2, "board_size": 4, "moves": {}, "clocks": [1, 2] }}"#, OPENING);
+
+            let err = import_game_from_content(&json).unwrap_err();
+
+            assert!(err.contains("need a time_control"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn rejects_an_invalid_time_control() {
+            let json = timed_game(r#""clocks": [1, 2]"#).replace("5+3", "fast");
+
+            let err = import_game_from_content(&json).unwrap_err();
+
+            assert!(err.starts_with("Invalid time control \"fast\""), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn lenient_import_warns_about_bad_clocks() {
+            let partial = import_game_lenient_from_content(&timed_game(r#""clocks": [299000]"#)).unwrap();
+
+            assert_eq!(partial.move_history.len(), 2);
+            assert_eq!(partial.warnings, vec!["The game has 2 moves but 1 clock times"]);
+        }
+    }
+
+    mod lenient_import {
+        use super::*;
+
+        const OPENING: &str = r#"
+            {"OpeningRemoval": {"color": "Black", "position": {"row": 1, "col": 1}}},
+            {"OpeningRemoval": {"color": "White", "position": {"row": 1, "col": 2}}}"#;
+        const FIRST_JUMP: &str = r#"
+            {"Jump": {"color": "Black", "from": {"row": 3, "col": 1}, "to": {"row": 1, "col": 1}, "captured": [{"row": 2, "col": 1}]}}"#;
+
+        fn game(moves: &[&str]) -> String {
+            format!(r#"{{ "board_size": 4, "moves": [{}] }}"#, moves.join(","))
+        }
+
+        fn diagnostic(moves: &[&str]) -> (LenientImport, MoveDiagnostic) {
+            let imported = import_game_lenient_from_content(&game(moves)).unwrap();
+            let diagnostic = imported.error.clone().expect("a diagnostic");
+            (imported, diagnostic)
+        }
+
+        #[test]
+        fn imports_a_valid_game_completely() {
+            let imported = import_game_lenient_from_content(&game(&[OPENING, FIRST_JUMP])).unwrap();
+            assert!(imported.is_complete());
+            assert_eq!(imported.move_history.len(), 3);
+            assert_eq!(imported.undo_stack.len(), 3);
+            assert_eq!(imported.total_moves, 3);
+        }
+
+        #[test]
+        fn keeps_the_moves_before_the_error() {
+            let wrong_player = r#"{"Jump": {"color": "Black", "from": {"row": 3, "col": 3}, "to": {"row": 1, "col": 3}, "captured": [{"row": 2, "col": 3}]}}"#;
+            let (imported, diagnostic) = diagnostic(&[OPENING, FIRST_JUMP, wrong_player]);
+
+            assert_eq!(imported.move_history.len(), 3);
+            assert_eq!(imported.undo_stack.len(), 3);
+            assert_eq!(imported.total_moves, 4);
+            assert_eq!(diagnostic.move_number, 4);
+            assert_eq!(diagnostic.found, "d4-d2");
+            assert_eq!(diagnostic.expected_player, PieceColor::White);
+            assert_eq!(diagnostic.phase, GamePhase::Play);
+            assert_eq!(diagnostic.error, "Move 4: Expected White to move, got Black");
+            assert!(diagnostic.cause.contains("recorded for Black but it is White's turn"), "{}", diagnostic.cause);
+        }
+
+        #[test]
+        fn lists_the_legal_moves() {
+            let wrong_player = r#"{"Jump": {"color": "Black", "from": {"row": 3, "col": 3}, "to": {"row": 1, "col": 3}, "captured": [{"row": 2, "col": 3}]}}"#;
+            let (_, diagnostic) = diagnostic(&[OPENING, FIRST_JUMP, wrong_player]);
+
+            let mut legal_moves = diagnostic.legal_moves.clone();
+            legal_moves.sort();
+            assert_eq!(legal_moves, ["a2-c2", "b1-b3", "c4-c2", "d3-b3"]);
+            assert!(diagnostic.to_string().contains("Legal moves for White: "));
+        }
+
+        #[test]
+        fn explains_wrong_captures() {
+            let wrong_capture = r#"{"Jump": {"color": "Black", "from": {"row": 3, "col": 1}, "to": {"row": 1, "col": 1}, "captured": [{"row": 2, "col": 2}]}}"#;
+            let (imported, diagnostic) = diagnostic(&[OPENING, wrong_capture]);
+
+            assert_eq!(imported.move_history.len(), 2);
+            assert_eq!(diagnostic.cause, "The jump is legal but its captures are recorded as [c3] instead of [b3]");
+        }
+
+        #[test]
+        fn explains_a_jump_from_an_empty_square() {
+            let from_empty = r#"{"Jump": {"color": "Black", "from": {"row": 1, "col": 1}, "to": {"row": 3, "col": 1}, "captured": [{"row": 2, "col": 1}]}}"#;
+            let (_, diagnostic) = diagnostic(&[OPENING, from_empty]);
+
+            assert!(diagnostic.cause.starts_with("There is no stone on b2"), "{}", diagnostic.cause);
+        }
+
+        #[test]
+        fn explains_an_invalid_opening_removal() {
+            let removal = r#"{"OpeningRemoval": {"color": "Black", "position": {"row": 0, "col": 2}}}"#;
+            let (imported, diagnostic) = diagnostic(&[removal]);
+
+            assert!(imported.move_history.is_empty());
+            assert_eq!(diagnostic.phase, GamePhase::OpeningBlackRemoval);
+            assert!(diagnostic.cause.contains("center or a corner, not c1"), "{}", diagnostic.cause);
+            let mut legal_moves = diagnostic.legal_moves.clone();
+            legal_moves.sort();
+            assert_eq!(legal_moves, ["a1", "b2", "c3", "d4"]);
+        }
+
+        #[test]
+        fn explains_squares_off_the_board() {
+            let off_board = r#"{"OpeningRemoval": {"color": "Black", "position": {"row": 5, "col": 5}}}"#;
+            let (_, diagnostic) = diagnostic(&[off_board]);
+
+            assert!(diagnostic.cause.contains("off the 4x4 board"), "{}", diagnostic.cause);
+        }
+
+        #[test]
+        fn reports_a_wrong_winner_as_a_warning() {
+            let json = format!(r#"{{ "board_size": 4, "winner": "White", "total_moves": 5, "moves": [{}] }}"#, OPENING);
+
+            let imported = import_game_lenient_from_content(&json).unwrap();
+
+            assert!(imported.error.is_none());
+            assert_eq!(imported.move_history.len(), 2);
+            assert_eq!(imported.warnings, ["total_moves is 5 but the game has 2 moves", "Winner specified but game is not over"]);
+        }
+
+        #[test]
+        fn still_rejects_unreadable_files() {
+            assert!(import_game_lenient_from_content("{ not json").is_err());
+            assert!(import_game_lenient_from_content(r#"{ "board_size": 5, "moves": [] }"#).is_err());
+        }
+
+        #[test]
+        fn gives_the_line_of_a_bad_text_move() {
+            let text = "Board size: 4\n1. b2\n2. c2\n3. b4-d4\n*\n";
+
+            let imported = import_game_lenient_from_content(text).unwrap();
+            let diagnostic = imported.error.unwrap();
+
+            assert_eq!(imported.move_history.len(), 2);
+            assert_eq!(diagnostic.line, Some(4));
+            assert!(diagnostic.error.starts_with("Line 4: "), "{}", diagnostic.error);
+            assert!(diagnostic.cause.contains("can't reach d4; it can jump to b2"), "{}", diagnostic.cause);
+        }
+
+        #[test]
+        fn reports_misnumbered_text_moves() {
+            let imported = import_game_lenient_from_content("1. b2\n3. c2\n").unwrap();
+            let diagnostic = imported.error.unwrap();
+
+            assert_eq!(imported.move_history.len(), 1);
+            assert_eq!(diagnostic.error, "Line 2: Expected move 2, found move 3");
+        }
+
+        #[test]
+        fn recovers_the_start_of_a_pgn_game() {
+            let pgn = "[BoardSize \"4\"]\n\n1. b2 c2 (1... a2) 2. b4-d4 *\n";
+            assert!(PgnGame::parse(pgn).is_err());
+
+            let imported = import_game_lenient_from_content(pgn).unwrap();
+            let diagnostic = imported.error.unwrap();
+
+            assert_eq!(imported.move_history.len(), 2);
+            assert_eq!(diagnostic.move_number, 3);
+            assert_eq!(diagnostic.found, "b4-d4");
+            assert_eq!(diagnostic.line, Some(3));
+        }
+    }
+
+    mod move_lists {
+        use super::*;
+
+        #[test]
+        fn plays_the_moves_from_the_start() {
+            let (state, history, undo_stack) = import_move_list(4, "b2 c2 b4-b2").unwrap();
+
+            assert_eq!(history.len(), 3);
+            assert_eq!(undo_stack.len(), 3);
+            assert_eq!(state.current_player(), PieceColor::White);
+        }
+
+        #[test]
+        fn skips_move_numbers_and_commas() {
+            let (_, history, _) = import_move_list(4, "1. b2, c2 2. b4-b2").unwrap();
+
+            assert_eq!(history.len(), 3);
+        }
+
+        #[test]
+        fn an_empty_list_is_the_start() {
+            let (state, history, _) = import_move_list(8, "").unwrap();
+
+            assert!(history.is_empty());
+            assert_eq!(state.current_phase(), GamePhase::OpeningBlackRemoval);
+        }
+
+        #[test]
+        fn reports_the_illegal_move() {
+            let err = import_move_list(4, "b2 a1").unwrap_err();
+
+            assert!(err.starts_with("Move 2 (a1): "), "{}", err);
+        }
+
+        #[test]
+        fn rejects_invalid_board_sizes() {
+            assert!(import_move_list(5, "").is_err());
+        }
+    }
 }
@@ -1,2 +1,18 @@
+pub mod analysis;
+#[cfg(feature = "png")]
+pub mod animation;
+pub mod api;
+pub mod archive;
+pub mod binary;
+pub mod correspondence;
+pub mod diagram;
+pub mod engine;
+pub mod export;
 pub mod game;
+pub mod html;
 pub mod import;
+pub mod network;
+pub mod pgn;
+pub mod session;
+pub mod tournament;
+pub mod tui;
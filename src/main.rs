@@ -1,10 +1,18 @@
+mod cli;
 mod ui;
 
-use clap::Parser;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
 use konane::game;
+use konane::game::{OpeningBook, PieceColor};
+use konane::session::PlayerType;
 
+use cli::{AiOptions, TournamentOptions};
 use iced::window;
-use ui::KonaneApp;
+use ui::{AiSettings, KonaneApp};
 
 #[derive(Parser)]
 #[command(name = "konane")]
@@ -13,18 +21,491 @@ struct Args {
     /// AI search depth (default: 8)
     #[arg(long, default_value_t = 8)]
     ai_depth: i32,
+
+    /// Opening book file consulted by the AI before searching
+    #[arg(long)]
+    book: Option<String>,
+
+    /// Choose book moves at random, weighted by quality, so games vary
+    #[arg(long)]
+    book_random: bool,
+
+    /// Append a JSON line describing each AI search to this file
+    #[arg(long)]
+    search_log: Option<String>,
+
+    /// Search children in board-scan order instead of best first
+    #[arg(long)]
+    no_move_ordering: bool,
+
+    /// Let the AI think on the human's time in Human vs AI games
+    #[arg(long)]
+    ponder: bool,
+
+    /// File the game in progress is saved to after every move (default: ~/.konane-autosave.json)
+    #[arg(long)]
+    autosave: Option<PathBuf>,
+
+    /// Do not autosave the game in progress
+    #[arg(long, conflicts_with = "autosave")]
+    no_autosave: bool,
+
+    /// Play in the terminal instead of opening a window, as a human Black against the AI (same as `tui`)
+    #[arg(long)]
+    no_gui: bool,
+
+    /// Seed for all random choices, so that runs with the same seed and settings play identical games
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Open the desktop app (the default when no command is given)
+    Play,
+    /// Generate an opening book from deep searches
+    Book {
+        /// Output file
+        output: String,
+
+        /// Board sizes to cover (may be repeated)
+        #[arg(long = "size", default_values_t = [8])]
+        sizes: Vec<usize>,
+
+        /// Search depth used to score each book move
+        #[arg(long, default_value_t = 8)]
+        depth: i32,
+
+        /// Number of plies from the start of the game covered by the book
+        #[arg(long, default_value_t = 4)]
+        plies: usize,
+
+        /// Keep moves scoring within this margin of the best move
+        #[arg(long, default_value_t = 2.0)]
+        margin: f32,
+    },
+    /// Play in the terminal instead of opening a window
+    Tui {
+        /// Board size (even, 4 to 16)
+        #[arg(long, default_value_t = 8)]
+        size: usize,
+
+        /// Who plays Black: human or ai
+        #[arg(long, default_value = "human", value_parser = parse_player_type)]
+        black: PlayerType,
+
+        /// Who plays White: human or ai
+        #[arg(long, default_value = "ai", value_parser = parse_player_type)]
+        white: PlayerType,
+
+        /// Continue a game file in the JSON, PGN or text format instead of starting a new game
+        #[arg(long, conflicts_with = "size")]
+        game: Option<String>,
+    },
+    /// Review a game with the AI, comparing each move with the one it prefers
+    Analyze {
+        /// Game file in the JSON, PGN or text format
+        game: String,
+
+        /// Search depth for each position
+        #[arg(long, default_value_t = 6)]
+        depth: i32,
+
+        /// Print the analysis as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Convert games between formats, chosen by extension: .json, .txt, .pgn, .jsonl archives or .bin datasets
+    Convert {
+        /// Game file, archive or dataset to read
+        input: String,
+
+        /// File to write
+        output: String,
+    },
+    /// Check that game files follow the rules, explaining the first illegal move
+    Validate {
+        /// Game files, archives or datasets
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Count the move sequences of a given length, to test move generation
+    Perft {
+        /// Number of moves to look ahead
+        depth: usize,
+
+        /// Starting position: a game file or moves in algebraic notation such as "d4 e4" (default: a new game)
+        #[arg(long)]
+        position: Option<String>,
+
+        /// Board size, when the position is not a game file
+        #[arg(long, default_value_t = 8)]
+        size: usize,
+
+        /// Print the count after each legal move as well as the total
+        #[arg(long)]
+        divide: bool,
+    },
+    /// Print the AI's choice of move in a position
+    Bestmove {
+        /// A game file or moves in algebraic notation such as "d4 e4" (an empty string is a new game)
+        #[arg(long)]
+        position: String,
+
+        /// Board size, when the position is not a game file
+        #[arg(long, default_value_t = 8)]
+        size: usize,
+
+        /// Search depth (default: --ai-depth)
+        #[arg(long)]
+        depth: Option<i32>,
+
+        /// Print the full search report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Play the AI against itself and write the games to a file
+    Selfplay {
+        /// Output file: a .jsonl or .pgn archive or a .bin dataset, or .json or .txt for a single game
+        output: String,
+
+        /// Number of games to play
+        #[arg(long, default_value_t = 1)]
+        games: usize,
+
+        /// Board size
+        #[arg(long, default_value_t = 8)]
+        size: usize,
+
+        /// Search depth (default: --ai-depth)
+        #[arg(long)]
+        depth: Option<i32>,
+
+        /// Number of moves at the start of each game chosen at random, so that games differ
+        #[arg(long, default_value_t = 2)]
+        random_plies: usize,
+    },
+    /// Play AI configurations against each other and estimate their Elo ratings
+    Tournament {
+        /// A contestant as comma-separated settings, such as name=deep,depth=6 (repeat for each contestant). Settings:
+        /// name, depth, time (ms per move), ordering (on or off), mobility and opponent (evaluation weights) and
+        /// engine (an external engine command, which must come last)
+        #[arg(long = "player", required = true)]
+        players: Vec<String>,
+
+        /// Play the first contestant against each of the others instead of every pair
+        #[arg(long)]
+        gauntlet: bool,
+
+        /// Board sizes to play on (may be repeated)
+        #[arg(long = "size", default_values_t = [8])]
+        sizes: Vec<usize>,
+
+        /// Number of times to play every opening with both colors
+        #[arg(long, default_value_t = 1)]
+        rounds: usize,
+
+        /// Number of games played at once (default: one per processor)
+        #[arg(long)]
+        threads: Option<usize>,
+
+        /// Directory each game is written to as JSON
+        #[arg(long)]
+        games: Option<String>,
+    },
+    /// Let another program use the AI through the line-based engine protocol on stdin and stdout
+    Engine,
+    /// Host a game that two copies of the app join over the network
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 7777)]
+        port: u16,
+
+        /// Board size (even, 4 to 16)
+        #[arg(long, default_value_t = 8)]
+        size: usize,
+    },
+    /// Serve the rules and the AI as an HTTP/JSON API on localhost
+    Api {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Let web pages on this origin call the API, such as https://example.com, or "*" for any origin
+        #[arg(long)]
+        allow_origin: Option<String>,
+
+        /// Searches run at once; more /bestmove requests are refused until one finishes
+        #[arg(long, default_value_t = konane::api::DEFAULT_MAX_SEARCHES)]
+        max_searches: usize,
+    },
+    /// Play a game by passing a file between two players, one move at a time
+    Correspondence {
+        #[command(subcommand)]
+        action: CorrespondenceAction,
+    },
+    /// Draw a position from a game file as an SVG or PNG diagram
+    Diagram {
+        /// Game file in the JSON, PGN or text format
+        game: String,
+
+        /// Output file; the format is chosen by its .svg or .png extension
+        output: String,
+
+        /// Draw the position after this many moves (default: the final position)
+        #[arg(long = "move")]
+        move_number: Option<usize>,
+
+        /// Width of a square in pixels
+        #[arg(long, default_value_t = 48.0)]
+        cell_size: f32,
+
+        /// Leave out the file letters and rank numbers
+        #[arg(long)]
+        no_coordinates: bool,
+    },
+    /// Export a game file for viewing without the desktop app
+    Export {
+        /// Game file in the JSON, PGN or text format
+        game: String,
+
+        /// Write a self-contained HTML page with an interactive board; implied by an output file ending in .html
+        #[arg(long)]
+        html: bool,
+
+        /// Output file (default: the game file with the extension of the format)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Replay a game file as an animated GIF or PNG, one frame per move
+    Animate {
+        /// Game file in the JSON, PGN or text format
+        game: String,
+
+        /// Output file; the format is chosen by its .gif, .png or .apng extension
+        output: String,
+
+        /// Milliseconds each position is shown
+        #[arg(long, default_value_t = 1000)]
+        delay: u64,
+
+        /// Milliseconds the final position is shown before the replay loops
+        #[arg(long, default_value_t = 3000)]
+        final_delay: u64,
+
+        /// Width of a square in pixels
+        #[arg(long, default_value_t = 48.0)]
+        cell_size: f32,
+
+        /// Leave out the file letters and rank numbers
+        #[arg(long)]
+        no_coordinates: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CorrespondenceAction {
+    /// Start a game file
+    New {
+        /// File to create
+        file: String,
+
+        /// Name of the player playing Black, who moves first
+        #[arg(long)]
+        black: String,
+
+        /// Name of the player playing White
+        #[arg(long)]
+        white: String,
+
+        /// Board size (even, 4 to 16)
+        #[arg(long, default_value_t = 8)]
+        size: usize,
+    },
+    /// Play one move and write the file back
+    Move {
+        /// Game file
+        file: String,
+
+        /// The move in algebraic notation, such as e4 or f4-d4
+        #[arg(name = "move")]
+        notation: String,
+
+        /// Your name, which must be the name of the player to move
+        #[arg(long = "as")]
+        player: String,
+    },
+    /// Show the board, the players and whose turn it is, checking the signatures
+    Show {
+        /// Game file
+        file: String,
+    },
 }
 
 fn main() -> iced::Result {
-    let args = Args::parse();
+    let mut args = Args::parse();
+
+    let book = args.book.as_ref().map(|path| match OpeningBook::load(path) {
+        Ok(book) => Arc::new(book),
+        Err(error) => exit_with_error(&error),
+    });
+    let ai_options = |depth: Option<i32>| AiOptions {
+        depth: depth.unwrap_or(args.ai_depth),
+        book: book.clone(),
+        book_random: args.book_random,
+        move_ordering: !args.no_move_ordering,
+    };
 
-    iced::application(move || KonaneApp::new(args.ai_depth), KonaneApp::update, KonaneApp::view)
+    let command = match args.command.take() {
+        None if args.no_gui => Some(Command::Tui {
+            size: 8,
+            black: PlayerType::Human,
+            white: PlayerType::Ai,
+            game: None,
+        }),
+        command => command,
+    };
+    let result = match command {
+        None | Some(Command::Play) => return run_app(args, book),
+        Some(Command::Tui { size, black, white, game }) => {
+            let ai_colors: Vec<PieceColor> = [(PieceColor::Black, black), (PieceColor::White, white)]
+                .into_iter()
+                .filter(|(_, player_type)| *player_type == PlayerType::Ai)
+                .map(|(color, _)| color)
+                .collect();
+            cli::play_in_terminal_against(size, game.as_deref(), &ai_colors, &ai_options(None), args.seed)
+        }
+        Some(Command::Engine) => cli::run_engine(ai_options(None), args.seed),
+        Some(Command::Serve { port, size }) => cli::serve(port, size),
+        Some(Command::Api {
+            port,
+            allow_origin,
+            max_searches,
+        }) => cli::serve_api(port, allow_origin.as_deref(), max_searches, ai_options(None), args.seed),
+        Some(Command::Correspondence { action }) => match action {
+            CorrespondenceAction::New { file, black, white, size } => cli::new_correspondence(&file, &black, &white, size),
+            CorrespondenceAction::Move { file, notation, player } => cli::play_correspondence(&file, &player, &notation),
+            CorrespondenceAction::Show { file } => cli::show_correspondence(&file),
+        },
+        Some(Command::Analyze { game, depth, json }) => cli::analyze(&game, depth, json),
+        Some(Command::Convert { input, output }) => cli::convert(&input, &output),
+        Some(Command::Validate { files }) => match cli::validate(&files) {
+            Ok(true) => Ok(()),
+            Ok(false) => std::process::exit(1),
+            Err(error) => Err(error),
+        },
+        Some(Command::Perft { depth, position, size, divide }) => cli::perft(position.as_deref(), size, depth, divide),
+        Some(Command::Bestmove { position, size, depth, json }) => {
+            cli::bestmove(&position, size, &ai_options(depth), args.seed, json)
+        }
+        Some(Command::Selfplay { output, games, size, depth, random_plies }) => {
+            cli::selfplay(&output, games, size, random_plies, &ai_options(depth), args.seed)
+        }
+        Some(Command::Tournament {
+            players,
+            gauntlet,
+            sizes,
+            rounds,
+            threads,
+            games,
+        }) => {
+            let options = TournamentOptions {
+                gauntlet,
+                sizes,
+                rounds,
+                threads,
+                games_dir: games,
+            };
+            cli::tournament(&players, options, args.ai_depth, args.seed)
+        }
+        Some(Command::Book {
+            output,
+            sizes,
+            depth,
+            plies,
+            margin,
+        }) => cli::build_book(&output, &sizes, depth, plies, margin),
+        Some(Command::Diagram {
+            game,
+            output,
+            move_number,
+            cell_size,
+            no_coordinates,
+        }) => cli::write_diagram(&game, &output, move_number, cell_size, !no_coordinates),
+        Some(Command::Export { game, html, output }) => {
+            let is_html = |path: &String| Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("html"));
+            if html || output.as_ref().is_some_and(is_html) {
+                let output =
+                    output.unwrap_or_else(|| Path::new(&game).with_extension("html").to_string_lossy().into_owned());
+                cli::write_html(&game, &output)
+            } else {
+                Err("Choose an export format: use --html or an output file ending in .html".to_string())
+            }
+        }
+        Some(Command::Animate {
+            game,
+            output,
+            delay,
+            final_delay,
+            cell_size,
+            no_coordinates,
+        }) => {
+            let delays = (Duration::from_millis(delay), Duration::from_millis(final_delay));
+            cli::write_animation(&game, &output, delays, cell_size, !no_coordinates)
+        }
+    };
+    if let Err(error) = result {
+        exit_with_error(&error);
+    }
+    Ok(())
+}
+
+fn run_app(args: Args, book: Option<Arc<OpeningBook>>) -> iced::Result {
+    let ai_settings = AiSettings {
+        depth: args.ai_depth,
+        book,
+        book_path: args.book,
+        book_random: args.book_random,
+        search_log: args.search_log,
+        move_ordering: !args.no_move_ordering,
+        ponder: args.ponder,
+    };
+
+    let autosave = if args.no_autosave {
+        None
+    } else {
+        args.autosave.or_else(|| std::env::home_dir().map(|home| home.join(".konane-autosave.json")))
+    };
+
+    iced::application(
+        move || KonaneApp::new(ai_settings.clone(), args.seed, autosave.clone()),
+        KonaneApp::update,
+        KonaneApp::view,
+    )
         .title(KonaneApp::title)
         .subscription(KonaneApp::subscription)
         .window(window::Settings {
             size: iced::Size::new(800.0, 700.0),
             min_size: Some(iced::Size::new(600.0, 500.0)),
+            // The app autosaves before exiting
+            exit_on_close_request: false,
             ..Default::default()
         })
         .run()
 }
+
+fn parse_player_type(value: &str) -> Result<PlayerType, String> {
+    match value.to_lowercase().as_str() {
+        "human" => Ok(PlayerType::Human),
+        "ai" => Ok(PlayerType::Ai),
+        _ => Err(format!("expected human or ai, found {}", value)),
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    std::process::exit(1);
+}
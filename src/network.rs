@@ -0,0 +1,936 @@
+//! Playing over a network: a small game server and a player that stands in for the opponent on the other end.
+//!
+//! The server holds one game. Each client keeps a TCP connection to it and exchanges JSON messages, one per line.
+//! A client joins as a color and is sent every move played so far; after that, a client sends its own moves and the
+//! server checks them with `Rules` and passes them on to both players. The server's copy of the game is the
+//! reference: a client that loses its connection joins again as the same color and replaces its game with the
+//! server's move history.
+//!
+//! Any number of spectators can also connect to watch. A spectator is sent every move so far and then each new move,
+//! and cannot play.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::player::{Player, PlayerInput, PlayerMove};
+use crate::game::{GamePhase, GameState, MoveHistory, MoveRecord, PieceColor, Rules, UndoRedoStack};
+
+/// Time allowed to connect to the server and to be given a seat.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time the server allows for a message to be sent to a client. A client that takes longer has stopped reading and is
+/// disconnected, so that it cannot hold up the other connections.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Messages from a client to the server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Take a seat: the color asked for, or whichever is free
+    Join { color: Option<PieceColor> },
+    /// Play a move as the client's color
+    Move { record: MoveRecord },
+    /// Watch the game without playing
+    Watch,
+    /// Ask for the whole game again
+    Sync,
+}
+
+/// Messages from the server to a client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// The client's seat and every move so far, sent on joining and in reply to `Sync`
+    Game {
+        color: PieceColor,
+        board_size: usize,
+        moves: MoveHistory,
+    },
+    /// Every move so far, sent to a spectator on joining and in reply to `Sync`
+    Watching { board_size: usize, moves: MoveHistory },
+    /// A move was played, numbered from 1; sent to both players and every spectator
+    Moved { number: usize, record: MoveRecord },
+    /// The client's move was not accepted
+    Rejected { reason: String },
+    /// The opponent joined or left
+    Opponent { connected: bool },
+    /// The client could not join; the server closes the connection
+    Error { message: String },
+}
+
+fn write_message<T: Serialize>(stream: &mut TcpStream, message: &T) -> Result<(), String> {
+    let line = serde_json::to_string(message).map_err(|err| format!("Failed to encode message: {}", err))?;
+    writeln!(stream, "{}", line).map_err(|err| format!("Failed to send message: {}", err))
+}
+
+/// The game as the server holds it, shared by the connections.
+struct ServerGame {
+    state: GameState,
+    moves: MoveHistory,
+    /// The connection in each seat, Black first, with an id so that a connection that has been replaced does not
+    /// free its successor's seat
+    seats: [Option<(u64, TcpStream)>; 2],
+    spectators: Vec<(u64, TcpStream)>,
+    next_id: u64,
+}
+
+/// What a connection is in the game.
+#[derive(Clone, Copy)]
+enum Role {
+    Player(u64, PieceColor),
+    Spectator(u64),
+}
+
+impl ServerGame {
+    fn seat(color: PieceColor) -> usize {
+        match color {
+            PieceColor::Black => 0,
+            PieceColor::White => 1,
+        }
+    }
+
+    /// Sends a message to the player of a color, if connected. A player that can't be written to is disconnected.
+    fn send(&mut self, color: PieceColor, message: &ServerMessage) {
+        let seat = &mut self.seats[Self::seat(color)];
+        let Some((_, stream)) = seat else {
+            return;
+        };
+        if write_message(stream, message).is_err() {
+            let _ = stream.shutdown(Shutdown::Both);
+            *seat = None;
+            self.send(color.opposite(), &ServerMessage::Opponent { connected: false });
+        }
+    }
+
+    fn game_message(&self, color: PieceColor) -> ServerMessage {
+        ServerMessage::Game {
+            color,
+            board_size: self.state.board().size(),
+            moves: self.moves.clone(),
+        }
+    }
+
+    fn watching_message(&self) -> ServerMessage {
+        ServerMessage::Watching {
+            board_size: self.state.board().size(),
+            moves: self.moves.clone(),
+        }
+    }
+
+    fn join(&mut self, color: Option<PieceColor>, stream: &TcpStream) -> Result<(u64, PieceColor), String> {
+        let free = |color: PieceColor| self.seats[Self::seat(color)].is_none();
+        let color = match color {
+            Some(color) if free(color) => color,
+            Some(color) => return Err(format!("{} is already taken", color)),
+            None => [PieceColor::Black, PieceColor::White]
+                .into_iter()
+                .find(|&color| free(color))
+                .ok_or_else(|| "The game is full".to_string())?,
+        };
+        let writer = stream.try_clone().map_err(|err| err.to_string())?;
+        self.next_id += 1;
+        self.seats[Self::seat(color)] = Some((self.next_id, writer));
+        Ok((self.next_id, color))
+    }
+
+    fn watch(&mut self, stream: &TcpStream) -> Result<u64, String> {
+        let mut writer = stream.try_clone().map_err(|err| err.to_string())?;
+        write_message(&mut writer, &self.watching_message())?;
+        self.next_id += 1;
+        self.spectators.push((self.next_id, writer));
+        Ok(self.next_id)
+    }
+
+    fn leave(&mut self, role: Role) {
+        match role {
+            Role::Player(id, color) => {
+                let seat = &mut self.seats[Self::seat(color)];
+                if seat.as_ref().is_some_and(|(seated, _)| *seated == id) {
+                    *seat = None;
+                    self.send(color.opposite(), &ServerMessage::Opponent { connected: false });
+                }
+            }
+            Role::Spectator(id) => self.spectators.retain(|(watching, _)| *watching != id),
+        }
+    }
+
+    fn play(&mut self, color: PieceColor, record: &MoveRecord) -> Result<(), String> {
+        if matches!(self.state.current_phase(), GamePhase::GameOver { .. }) {
+            return Err("The game is over".to_string());
+        }
+        if color != self.state.current_player() {
+            return Err(format!("It is {}'s turn", self.state.current_player()));
+        }
+        let record = PlayerMove::from_record(&self.state, record)?
+            .apply(&mut self.state)
+            .map_err(str::to_string)?;
+        self.moves.push(record.clone());
+        let moved = ServerMessage::Moved {
+            number: self.moves.len(),
+            record,
+        };
+        self.send(PieceColor::Black, &moved);
+        self.send(PieceColor::White, &moved);
+        // A spectator that can't be written to has gone or stopped reading
+        self.spectators.retain_mut(|(_, stream)| {
+            let sent = write_message(stream, &moved).is_ok();
+            if !sent {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+            sent
+        });
+        Ok(())
+    }
+}
+
+/// Hosts one game for two players and any number of spectators.
+pub struct GameServer {
+    listener: TcpListener,
+    game: Arc<Mutex<ServerGame>>,
+}
+
+impl GameServer {
+    /// Listens on the address, such as "0.0.0.0:7777", for a new game on a board of the given size. Port 0 picks a
+    /// free port.
+    pub fn bind(address: &str, board_size: usize) -> Result<Self, String> {
+        if !(4..=16).contains(&board_size) || !board_size.is_multiple_of(2) {
+            return Err(format!("Invalid board size {}", board_size));
+        }
+        let listener = TcpListener::bind(address).map_err(|err| format!("Failed to listen on {}: {}", address, err))?;
+        Ok(Self {
+            listener,
+            game: Arc::new(Mutex::new(ServerGame {
+                state: GameState::new(board_size, PieceColor::Black),
+                moves: MoveHistory::new(),
+                seats: [None, None],
+                spectators: Vec::new(),
+                next_id: 0,
+            })),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.listener.local_addr().map_err(|err| err.to_string())
+    }
+
+    /// Accepts players until the process ends, serving each connection on its own thread.
+    pub fn run(self) {
+        for stream in self.listener.incoming().flatten() {
+            let game = self.game.clone();
+            thread::spawn(move || serve_client(stream, &game));
+        }
+    }
+
+    /// Runs the server on a background thread.
+    pub fn spawn(self) -> thread::JoinHandle<()> {
+        thread::spawn(move || self.run())
+    }
+}
+
+fn serve_client(stream: TcpStream, game: &Mutex<ServerGame>) {
+    // The timeout applies to every handle on the connection, including those the game keeps
+    if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+        return;
+    }
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut lines = BufReader::new(stream).lines();
+    let mut role = None;
+
+    while let Some(Ok(line)) = lines.next() {
+        let message = match serde_json::from_str::<ClientMessage>(&line) {
+            Ok(message) => message,
+            Err(err) => {
+                let _ = write_message(&mut writer, &ServerMessage::Rejected { reason: format!("Invalid message: {}", err) });
+                continue;
+            }
+        };
+        let mut game = game.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match (message, role) {
+            (ClientMessage::Join { color }, None) => match game.join(color, &writer) {
+                Ok((id, color)) => {
+                    role = Some(Role::Player(id, color));
+                    let welcome = game.game_message(color);
+                    game.send(color, &welcome);
+                    let opponent_connected = game.seats[ServerGame::seat(color.opposite())].is_some();
+                    game.send(color, &ServerMessage::Opponent { connected: opponent_connected });
+                    game.send(color.opposite(), &ServerMessage::Opponent { connected: true });
+                }
+                Err(message) => {
+                    let _ = write_message(&mut writer, &ServerMessage::Error { message });
+                    return;
+                }
+            },
+            (ClientMessage::Watch, None) => match game.watch(&writer) {
+                Ok(id) => role = Some(Role::Spectator(id)),
+                Err(_) => return,
+            },
+            (ClientMessage::Join { .. } | ClientMessage::Watch, Some(_)) => {
+                let reason = "Already joined".to_string();
+                let _ = write_message(&mut writer, &ServerMessage::Rejected { reason });
+            }
+            (_, None) => {
+                let message = "Join the game first".to_string();
+                let _ = write_message(&mut writer, &ServerMessage::Error { message });
+                return;
+            }
+            (ClientMessage::Move { record }, Some(Role::Player(_, color))) => {
+                if let Err(reason) = game.play(color, &record) {
+                    game.send(color, &ServerMessage::Rejected { reason });
+                }
+            }
+            (ClientMessage::Move { .. }, Some(Role::Spectator(_))) => {
+                let reason = "Spectators cannot move".to_string();
+                let _ = write_message(&mut writer, &ServerMessage::Rejected { reason });
+            }
+            (ClientMessage::Sync, Some(Role::Player(_, color))) => {
+                let message = game.game_message(color);
+                game.send(color, &message);
+            }
+            (ClientMessage::Sync, Some(Role::Spectator(_))) => {
+                let _ = write_message(&mut writer, &game.watching_message());
+            }
+        }
+    }
+
+    if let Some(role) = role {
+        game.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).leave(role);
+    }
+}
+
+/// What happened on the network since the last call to `NetworkPlayer::take_events`. The opponent's moves are not
+/// events; they are returned by `request_move`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkEvent {
+    /// The game was replaced by the server's copy, after joining again or when the games no longer matched
+    Synced(MoveHistory),
+    /// A move sent by this client was not accepted
+    Rejected(String),
+    OpponentJoined,
+    OpponentLeft,
+    /// The connection to the server was lost
+    Disconnected,
+}
+
+/// The opponent in a network game. Its moves come from the server, and the local player's moves are sent with
+/// `send_move`.
+pub struct NetworkPlayer {
+    address: String,
+    /// The color played on this side of the connection; the opponent plays the other
+    local_color: PieceColor,
+    board_size: usize,
+    writer: Option<TcpStream>,
+    messages: Receiver<ServerMessage>,
+    /// Every move the server has confirmed
+    moves: MoveHistory,
+    /// The opponent's moves not yet returned by `request_move`
+    incoming: VecDeque<MoveRecord>,
+    events: Vec<NetworkEvent>,
+    opponent_connected: bool,
+}
+
+impl NetworkPlayer {
+    /// Connects to a server and joins its game, as the color asked for or whichever is free.
+    pub fn connect(address: &str, color: Option<PieceColor>) -> Result<Self, String> {
+        let (writer, messages, local_color, board_size, moves) = join(address, color)?;
+        let mut player = Self {
+            address: address.to_string(),
+            local_color,
+            board_size,
+            writer: Some(writer),
+            messages,
+            moves,
+            incoming: VecDeque::new(),
+            events: Vec::new(),
+            opponent_connected: false,
+        };
+        player.receive();
+        Ok(player)
+    }
+
+    /// Connects again after the connection was lost, keeping the same color. The game is replaced by the server's,
+    /// reported as a `Synced` event.
+    pub fn reconnect(&mut self) -> Result<(), String> {
+        self.close();
+        let (writer, messages, _, board_size, moves) = join(&self.address, Some(self.local_color))?;
+        self.writer = Some(writer);
+        self.messages = messages;
+        self.board_size = board_size;
+        self.incoming.clear();
+        self.moves = moves.clone();
+        self.events.push(NetworkEvent::Synced(moves));
+        Ok(())
+    }
+
+    pub fn local_color(&self) -> PieceColor {
+        self.local_color
+    }
+
+    pub fn board_size(&self) -> usize {
+        self.board_size
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    pub fn opponent_connected(&self) -> bool {
+        self.opponent_connected
+    }
+
+    /// Every move the server has confirmed.
+    pub fn moves(&self) -> &MoveHistory {
+        &self.moves
+    }
+
+    /// The server's game with an undo entry for each move, as the app keeps a game.
+    pub fn game(&self) -> Result<(GameState, MoveHistory, UndoRedoStack), String> {
+        replay_game(self.board_size, &self.moves)
+    }
+
+    /// Sends a move played by the local player. The server confirms it to both players, or rejects it.
+    pub fn send_move(&mut self, record: &MoveRecord) -> Result<(), String> {
+        self.send(&ClientMessage::Move { record: record.clone() })
+    }
+
+    /// Asks the server for its copy of the game, which arrives as a `Synced` event.
+    pub fn request_sync(&mut self) -> Result<(), String> {
+        self.send(&ClientMessage::Sync)
+    }
+
+    /// Returns what has happened since the last call.
+    pub fn take_events(&mut self) -> Vec<NetworkEvent> {
+        self.receive();
+        std::mem::take(&mut self.events)
+    }
+
+    fn send(&mut self, message: &ClientMessage) -> Result<(), String> {
+        let Some(writer) = &mut self.writer else {
+            return Err("Not connected to the server".to_string());
+        };
+        let result = write_message(writer, message);
+        if result.is_err() {
+            self.disconnected();
+        }
+        result
+    }
+
+    /// Closes the connection, which also ends the thread reading from it.
+    fn close(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.shutdown(Shutdown::Both);
+        }
+    }
+
+    fn disconnected(&mut self) {
+        if self.writer.take().is_some() {
+            self.opponent_connected = false;
+            self.events.push(NetworkEvent::Disconnected);
+        }
+    }
+
+    /// Handles the messages received so far.
+    fn receive(&mut self) {
+        loop {
+            let message = match self.messages.try_recv() {
+                Ok(message) => message,
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.disconnected();
+                    return;
+                }
+            };
+            match message {
+                ServerMessage::Game { board_size, moves, .. } => {
+                    self.board_size = board_size;
+                    self.incoming.clear();
+                    self.moves = moves.clone();
+                    self.events.push(NetworkEvent::Synced(moves));
+                }
+                ServerMessage::Moved { number, record } => {
+                    if number != self.moves.len() + 1 {
+                        // A move was missed, so the games no longer match
+                        let _ = self.request_sync();
+                        continue;
+                    }
+                    self.moves.push(record.clone());
+                    if record.color() != self.local_color {
+                        self.incoming.push_back(record);
+                    }
+                }
+                ServerMessage::Rejected { reason } => {
+                    self.events.push(NetworkEvent::Rejected(reason));
+                    let _ = self.request_sync();
+                }
+                ServerMessage::Opponent { connected } => {
+                    if connected != self.opponent_connected {
+                        self.opponent_connected = connected;
+                        self.events.push(if connected { NetworkEvent::OpponentJoined } else { NetworkEvent::OpponentLeft });
+                    }
+                }
+                ServerMessage::Error { message } => {
+                    self.events.push(NetworkEvent::Rejected(message));
+                    self.disconnected();
+                }
+                // Only sent to spectators
+                ServerMessage::Watching { .. } => {}
+            }
+        }
+    }
+}
+
+impl Drop for NetworkPlayer {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Watches a game on a server. The game is followed by polling `take_events` and reading `moves`.
+pub struct Spectator {
+    address: String,
+    board_size: usize,
+    writer: Option<TcpStream>,
+    messages: Receiver<ServerMessage>,
+    /// Every move the server has confirmed
+    moves: MoveHistory,
+    events: Vec<NetworkEvent>,
+}
+
+impl Spectator {
+    /// Connects to a server to watch its game.
+    pub fn connect(address: &str) -> Result<Self, String> {
+        let (writer, messages, board_size, moves) = watch(address)?;
+        Ok(Self {
+            address: address.to_string(),
+            board_size,
+            writer: Some(writer),
+            messages,
+            moves,
+            events: Vec::new(),
+        })
+    }
+
+    /// Connects again after the connection was lost. The moves are replaced by the server's, reported as a `Synced`
+    /// event.
+    pub fn reconnect(&mut self) -> Result<(), String> {
+        self.close();
+        let (writer, messages, board_size, moves) = watch(&self.address)?;
+        self.writer = Some(writer);
+        self.messages = messages;
+        self.board_size = board_size;
+        self.moves = moves.clone();
+        self.events.push(NetworkEvent::Synced(moves));
+        Ok(())
+    }
+
+    pub fn board_size(&self) -> usize {
+        self.board_size
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Every move the server has confirmed.
+    pub fn moves(&self) -> &MoveHistory {
+        &self.moves
+    }
+
+    /// The server's game with an undo entry for each move, as the app keeps a game.
+    pub fn game(&self) -> Result<(GameState, MoveHistory, UndoRedoStack), String> {
+        replay_game(self.board_size, &self.moves)
+    }
+
+    /// Returns what has happened since the last call: only `Synced` and `Disconnected`, since a spectator neither
+    /// moves nor has an opponent.
+    pub fn take_events(&mut self) -> Vec<NetworkEvent> {
+        self.receive();
+        std::mem::take(&mut self.events)
+    }
+
+    fn close(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.shutdown(Shutdown::Both);
+        }
+    }
+
+    fn receive(&mut self) {
+        loop {
+            let message = match self.messages.try_recv() {
+                Ok(message) => message,
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    if self.writer.take().is_some() {
+                        self.events.push(NetworkEvent::Disconnected);
+                    }
+                    return;
+                }
+            };
+            match message {
+                ServerMessage::Watching { board_size, moves } => {
+                    self.board_size = board_size;
+                    self.moves = moves.clone();
+                    self.events.push(NetworkEvent::Synced(moves));
+                }
+                ServerMessage::Moved { number, record } if number == self.moves.len() + 1 => self.moves.push(record),
+                ServerMessage::Moved { .. } => {
+                    // A move was missed, so ask for the whole game
+                    if let Some(writer) = &mut self.writer {
+                        let _ = write_message(writer, &ClientMessage::Sync);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for Spectator {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Replays moves into a game with an undo entry for each move.
+fn replay_game(board_size: usize, moves: &MoveHistory) -> Result<(GameState, MoveHistory, UndoRedoStack), String> {
+    let mut positions = Rules::replay(board_size, moves)?;
+    let state = positions.pop().expect("replay includes the start");
+    let undo_stack = positions
+        .into_iter()
+        .enumerate()
+        .map(|(index, position)| (position, moves[..index].to_vec()))
+        .collect();
+    Ok((state, moves.clone(), undo_stack))
+}
+
+type Connection = (TcpStream, Receiver<ServerMessage>, PieceColor, usize, MoveHistory);
+
+/// Connects, asks for a seat and waits for the game.
+fn join(address: &str, color: Option<PieceColor>) -> Result<Connection, String> {
+    match open(address, &ClientMessage::Join { color })? {
+        (stream, messages, ServerMessage::Game { color, board_size, moves }) => {
+            Ok((stream, messages, color, board_size, moves))
+        }
+        (_, _, message) => Err(format!("Unexpected reply from the server: {:?}", message)),
+    }
+}
+
+/// Connects as a spectator and waits for the game.
+fn watch(address: &str) -> Result<(TcpStream, Receiver<ServerMessage>, usize, MoveHistory), String> {
+    match open(address, &ClientMessage::Watch)? {
+        (stream, messages, ServerMessage::Watching { board_size, moves }) => Ok((stream, messages, board_size, moves)),
+        (_, _, message) => Err(format!("Unexpected reply from the server: {:?}", message)),
+    }
+}
+
+/// Connects, sends the first message and waits for the reply, with a thread passing on the messages that follow.
+fn open(address: &str, hello: &ClientMessage) -> Result<(TcpStream, Receiver<ServerMessage>, ServerMessage), String> {
+    let socket = address
+        .to_socket_addrs()
+        .map_err(|err| format!("Invalid address {}: {}", address, err))?
+        .next()
+        .ok_or_else(|| format!("Invalid address {}", address))?;
+    let mut stream = TcpStream::connect_timeout(&socket, JOIN_TIMEOUT)
+        .map_err(|err| format!("Failed to connect to {}: {}", address, err))?;
+    let reader = stream.try_clone().map_err(|err| err.to_string())?;
+
+    let (sender, messages) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            let Ok(message) = serde_json::from_str::<ServerMessage>(&line) else {
+                continue;
+            };
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    write_message(&mut stream, hello)?;
+    match messages.recv_timeout(JOIN_TIMEOUT) {
+        Ok(ServerMessage::Error { message }) => Err(message),
+        Ok(reply) => Ok((stream, messages, reply)),
+        Err(RecvTimeoutError::Timeout) => Err(format!("{} did not reply", address)),
+        Err(RecvTimeoutError::Disconnected) => Err(format!("{} closed the connection", address)),
+    }
+}
+
+impl Player for NetworkPlayer {
+    /// The opponent's color.
+    fn color(&self) -> PieceColor {
+        self.local_color.opposite()
+    }
+
+    /// Returns the opponent's next move once it has arrived. If it does not fit the position, the game is synced
+    /// with the server instead.
+    fn request_move(&mut self, state: &GameState) -> Option<PlayerMove> {
+        self.receive();
+        if state.current_player() != self.color() {
+            return None;
+        }
+        let record = self.incoming.pop_front()?;
+        match PlayerMove::from_record(state, &record) {
+            Ok(player_move) => Some(player_move),
+            Err(_) => {
+                let _ = self.request_sync();
+                None
+            }
+        }
+    }
+
+    // The opponent's moves come from the server
+    fn receive_input(&mut self, _input: PlayerInput) {}
+
+    fn is_ready(&self) -> bool {
+        !self.incoming.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::game::Position;
+
+    fn start_server(board_size: usize) -> String {
+        let server = GameServer::bind("127.0.0.1:0", board_size).unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        server.spawn();
+        address
+    }
+
+    /// Polls until the condition holds, failing after a few seconds.
+    fn wait_until<F: FnMut() -> bool>(mut condition: F) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn removal(color: PieceColor, row: usize, col: usize) -> MoveRecord {
+        MoveRecord::OpeningRemoval {
+            color,
+            position: Position::new(row, col),
+        }
+    }
+
+    mod joining {
+        use super::*;
+
+        #[test]
+        fn seats_players_in_order() {
+            let address = start_server(4);
+
+            let black = NetworkPlayer::connect(&address, None).unwrap();
+            let white = NetworkPlayer::connect(&address, None).unwrap();
+
+            assert_eq!(black.local_color(), PieceColor::Black);
+            assert_eq!(white.local_color(), PieceColor::White);
+            assert_eq!(white.color(), PieceColor::Black);
+            assert_eq!(white.board_size(), 4);
+        }
+
+        #[test]
+        fn rejects_a_taken_color_and_a_full_game() {
+            let address = start_server(4);
+            let _white = NetworkPlayer::connect(&address, Some(PieceColor::White)).unwrap();
+
+            assert_eq!(NetworkPlayer::connect(&address, Some(PieceColor::White)).err().unwrap(), "White is already taken");
+            let _black = NetworkPlayer::connect(&address, None).unwrap();
+            assert_eq!(NetworkPlayer::connect(&address, None).err().unwrap(), "The game is full");
+        }
+
+        #[test]
+        fn tells_each_player_about_the_other() {
+            let address = start_server(4);
+            let mut black = NetworkPlayer::connect(&address, None).unwrap();
+            let white = NetworkPlayer::connect(&address, None).unwrap();
+
+            wait_until(|| black.take_events().contains(&NetworkEvent::OpponentJoined));
+            drop(white);
+
+            wait_until(|| black.take_events().contains(&NetworkEvent::OpponentLeft));
+        }
+    }
+
+    mod moves {
+        use super::*;
+
+        #[test]
+        fn a_player_that_cannot_be_written_to_is_disconnected() {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+            let (stream, _) = listener.accept().unwrap();
+            // Writes now fail as they do once a client that stopped reading times out
+            stream.shutdown(Shutdown::Write).unwrap();
+            let mut game = ServerGame {
+                state: GameState::new(4, PieceColor::Black),
+                moves: MoveHistory::new(),
+                seats: [None, Some((1, stream))],
+                spectators: Vec::new(),
+                next_id: 1,
+            };
+
+            game.send(PieceColor::White, &ServerMessage::Opponent { connected: true });
+
+            assert!(game.seats[1].is_none());
+        }
+
+        #[test]
+        fn passes_moves_to_the_opponent() {
+            let address = start_server(4);
+            let mut black = NetworkPlayer::connect(&address, None).unwrap();
+            let mut white = NetworkPlayer::connect(&address, None).unwrap();
+            let mut state = GameState::new(4, PieceColor::Black);
+
+            black.send_move(&removal(PieceColor::Black, 1, 1)).unwrap();
+            let mut received = None;
+            wait_until(|| {
+                received = white.request_move(&state);
+                received.is_some()
+            });
+
+            let record = received.unwrap().apply(&mut state).unwrap();
+            assert_eq!(record, removal(PieceColor::Black, 1, 1));
+            wait_until(|| {
+                black.take_events();
+                black.moves().len() == 1
+            });
+            assert!(black.request_move(&state).is_none());
+        }
+
+        #[test]
+        fn rejects_illegal_moves_and_moves_out_of_turn() {
+            let address = start_server(4);
+            let mut black = NetworkPlayer::connect(&address, None).unwrap();
+            let mut white = NetworkPlayer::connect(&address, None).unwrap();
+
+            white.send_move(&removal(PieceColor::White, 1, 2)).unwrap();
+            black.send_move(&removal(PieceColor::Black, 0, 1)).unwrap();
+
+            let mut events = Vec::new();
+            wait_until(|| {
+                events.extend(white.take_events());
+                events.iter().any(|event| matches!(event, NetworkEvent::Rejected(_)))
+            });
+            assert!(events.contains(&NetworkEvent::Rejected("It is Black's turn".to_string())));
+            wait_until(|| black.take_events().iter().any(|event| matches!(event, NetworkEvent::Rejected(_))));
+            assert!(black.moves().is_empty());
+        }
+
+        #[test]
+        fn a_player_that_joins_again_gets_the_whole_game() {
+            let address = start_server(4);
+            let mut black = NetworkPlayer::connect(&address, None).unwrap();
+            let white = NetworkPlayer::connect(&address, None).unwrap();
+            black.send_move(&removal(PieceColor::Black, 1, 1)).unwrap();
+            drop(white);
+            wait_until(|| black.take_events().contains(&NetworkEvent::OpponentLeft));
+
+            let white = NetworkPlayer::connect(&address, Some(PieceColor::White)).unwrap();
+
+            assert_eq!(white.moves(), &vec![removal(PieceColor::Black, 1, 1)]);
+            let (state, history, undo_stack) = white.game().unwrap();
+            assert_eq!(state.current_player(), PieceColor::White);
+            assert_eq!((history.len(), undo_stack.len()), (1, 1));
+        }
+
+        #[test]
+        fn reconnect_keeps_the_color() {
+            let address = start_server(4);
+            let mut black = NetworkPlayer::connect(&address, None).unwrap();
+            let _white = NetworkPlayer::connect(&address, None).unwrap();
+            black.writer.as_ref().unwrap().shutdown(Shutdown::Both).unwrap();
+            wait_until(|| black.take_events().contains(&NetworkEvent::Disconnected));
+            assert!(!black.is_connected());
+
+            wait_until(|| black.reconnect().is_ok());
+
+            assert_eq!(black.local_color(), PieceColor::Black);
+            assert!(black.is_connected());
+            assert!(black.take_events().contains(&NetworkEvent::Synced(MoveHistory::new())));
+        }
+    }
+
+    mod spectators {
+        use super::*;
+
+        #[test]
+        fn see_the_game_so_far_and_each_new_move() {
+            let address = start_server(4);
+            let mut black = NetworkPlayer::connect(&address, None).unwrap();
+            let mut white = NetworkPlayer::connect(&address, None).unwrap();
+            black.send_move(&removal(PieceColor::Black, 1, 1)).unwrap();
+            wait_until(|| {
+                white.take_events();
+                white.moves().len() == 1
+            });
+
+            let mut spectator = Spectator::connect(&address).unwrap();
+            assert_eq!(spectator.moves(), &vec![removal(PieceColor::Black, 1, 1)]);
+            assert_eq!(spectator.board_size(), 4);
+
+            white.send_move(&removal(PieceColor::White, 1, 2)).unwrap();
+            wait_until(|| {
+                spectator.take_events();
+                spectator.moves().len() == 2
+            });
+            assert_eq!(spectator.moves()[1], removal(PieceColor::White, 1, 2));
+            assert_eq!(spectator.game().unwrap().0.current_phase(), GamePhase::Play);
+        }
+
+        #[test]
+        fn do_not_take_seats() {
+            let address = start_server(4);
+            let _spectators = [Spectator::connect(&address).unwrap(), Spectator::connect(&address).unwrap()];
+
+            let black = NetworkPlayer::connect(&address, None).unwrap();
+            let white = NetworkPlayer::connect(&address, None).unwrap();
+
+            assert_eq!(black.local_color(), PieceColor::Black);
+            assert_eq!(white.local_color(), PieceColor::White);
+            assert!(Spectator::connect(&address).is_ok());
+        }
+
+        #[test]
+        fn cannot_move() {
+            let address = start_server(4);
+            let mut stream = TcpStream::connect(&address).unwrap();
+            let mut lines = BufReader::new(stream.try_clone().unwrap()).lines();
+
+            write_message(&mut stream, &ClientMessage::Watch).unwrap();
+            let watching = serde_json::from_str::<ServerMessage>(&lines.next().unwrap().unwrap()).unwrap();
+            write_message(&mut stream, &ClientMessage::Move { record: removal(PieceColor::Black, 1, 1) }).unwrap();
+            let reply = serde_json::from_str::<ServerMessage>(&lines.next().unwrap().unwrap()).unwrap();
+
+            assert_eq!(watching, ServerMessage::Watching { board_size: 4, moves: Vec::new() });
+            assert_eq!(reply, ServerMessage::Rejected { reason: "Spectators cannot move".to_string() });
+        }
+    }
+
+    mod protocol {
+        use super::*;
+
+        #[test]
+        fn messages_are_tagged_json() {
+            let message = ClientMessage::Move {
+                record: removal(PieceColor::Black, 1, 1),
+            };
+
+            let json = serde_json::to_string(&message).unwrap();
+
+            assert!(json.starts_with(r#"{"type":"move","record":{"OpeningRemoval""#), "{}", json);
+            assert_eq!(serde_json::from_str::<ClientMessage>(&json).unwrap(), message);
+        }
+    }
+}
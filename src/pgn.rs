@@ -0,0 +1,817 @@
+//! PGN-style game records.
+//!
+//! A record starts with tag pairs such as `[Event "Club night"]`, followed by movetext in algebraic notation:
+//!
+//! ```text
+//! [Event "Club night"]
+//! [Date "2026.10.18"]
+//! [Black "Alice"]
+//! [White "AI (depth 8)"]
+//! [Result "*"]
+//! [BoardSize "8"]
+//! [Variant "Standard"]
+//!
+//! 1. d4 e4 2. b4-d4 {The usual reply} (2. d6-d4) g4-e4 *
+//! ```
+//!
+//! Black always moves first, so each numbered move is a Black move followed by a White move. Comments are enclosed
+//! in braces and follow the move they describe. A variation in parentheses is an alternative to the move before it.
+//! The `BoardSize` tag is required.
+//!
+//! Games played with a time control have a `TimeControl` tag and a `[%clk 0:04:58]` comment after each move giving
+//! the time left on the mover's clock. A game lost on time has the tag `[Termination "time forfeit"]`, and its result
+//! names the winner although the game is not over on the board.
+
+use std::time::Duration;
+
+use crate::export::ClockLog;
+use crate::game::player::PlayerMove;
+use crate::game::{GamePhase, GameState, MoveHistory, MoveRecord, PieceColor, Rules};
+
+/// The only rule set currently supported.
+pub const STANDARD_VARIANT: &str = "Standard";
+
+/// `Termination` of a game lost when a player's clock ran out.
+pub const TIME_FORFEIT: &str = "time forfeit";
+
+/// Movetext lines are wrapped at this width.
+const LINE_WIDTH: usize = 80;
+
+/// A move in a game record, with its annotations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnMove {
+    pub record: MoveRecord,
+    pub comment: Option<String>,
+    /// Alternatives to this move. Each variation starts from the position before the move.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn new(record: MoveRecord) -> Self {
+        Self {
+            record,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+/// A game record with tags, annotated moves and a result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnGame {
+    /// Tag pairs in the order they are written
+    pub tags: Vec<(String, String)>,
+    /// Comment before the first move
+    pub comment: Option<String>,
+    pub moves: Vec<PgnMove>,
+    /// The winner, or None if the game is unfinished
+    pub result: Option<PieceColor>,
+}
+
+impl PgnGame {
+    /// Creates a record of a game with the standard tags. Unknown values are written as `?`.
+    pub fn new(board_size: usize, history: &MoveHistory, result: Option<PieceColor>) -> Self {
+        let mut game = Self {
+            tags: Vec::new(),
+            comment: None,
+            moves: history.iter().cloned().map(PgnMove::new).collect(),
+            result,
+        };
+        for name in ["Event", "Site", "Date", "Round", "Black", "White"] {
+            game.set_tag(name, "?");
+        }
+        game.set_tag("Result", result_code(result));
+        game.set_tag("BoardSize", &board_size.to_string());
+        game.set_tag("Variant", STANDARD_VARIANT);
+        game
+    }
+
+    /// Returns the value of a tag.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Sets a tag, keeping its position if it is already present.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Records the clocks of a game played with a time control. A `[%clk]` comment is added to each move, before any
+    /// comment it already has.
+    pub fn set_clock(&mut self, clock: &ClockLog) {
+        self.set_tag("TimeControl", &clock.time_control.to_string());
+        if clock.lost_on_time.is_some() {
+            self.set_tag("Termination", TIME_FORFEIT);
+        }
+        for (mv, &time) in self.moves.iter_mut().zip(&clock.times) {
+            let clk = format!("[%clk {}]", clk_time(time));
+            mv.comment = Some(match mv.comment.take() {
+                Some(comment) => format!("{} {}", clk, comment),
+                None => clk,
+            });
+        }
+    }
+
+    /// Returns the board size given by the `BoardSize` tag.
+    pub fn board_size(&self) -> Result<usize, String> {
+        let value = self.tag("BoardSize").ok_or("Missing BoardSize tag")?;
+        let size: usize = value.parse().map_err(|_| format!("Invalid BoardSize \"{}\"", value))?;
+        if !(4..=16).contains(&size) || !size.is_multiple_of(2) {
+            return Err("Invalid BoardSize: must be even and between 4 and 16".to_string());
+        }
+        Ok(size)
+    }
+
+    /// Returns the moves of the main line.
+    pub fn move_history(&self) -> MoveHistory {
+        self.moves.iter().map(|mv| mv.record.clone()).collect()
+    }
+
+    /// Writes the record. The `Result` tag is written from `result`.
+    pub fn to_pgn(&self) -> String {
+        let mut out = String::new();
+        let mut tags = self.tags.clone();
+        match tags.iter_mut().find(|(tag, _)| tag == "Result") {
+            Some((_, value)) => *value = result_code(self.result).to_string(),
+            None => tags.push(("Result".to_string(), result_code(self.result).to_string())),
+        }
+        for (name, value) in &tags {
+            out.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+        }
+        out.push('\n');
+
+        let mut tokens = Vec::new();
+        if let Some(ref comment) = self.comment {
+            tokens.push(comment_token(comment));
+        }
+        write_moves(&self.moves, 0, &mut tokens);
+        tokens.push(result_code(self.result).to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                out.push_str(&line);
+                out.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        out.push_str(&line);
+        out.push('\n');
+        out
+    }
+
+    /// Reads a record, checking every move, including those in variations, against the rules.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let tokens = tokenize(content)?;
+        let mut parser = Parser { tokens, next: 0 };
+
+        let mut game = PgnGame {
+            tags: Vec::new(),
+            comment: None,
+            moves: Vec::new(),
+            result: None,
+        };
+        while let Some((_, Token::Tag(name, value))) = parser.peek() {
+            game.tags.push((name.clone(), value.clone()));
+            parser.next += 1;
+        }
+
+        if let Some(variant) = game.tag("Variant")
+            && variant != STANDARD_VARIANT
+        {
+            return Err(format!("Unsupported variant \"{}\"", variant));
+        }
+
+        let start = GameState::new(game.board_size()?, PieceColor::Black);
+        let (moves, comment, state) = parser.parse_line(start, 0)?;
+        game.moves = moves;
+        game.comment = comment;
+
+        let (line, result) = match parser.peek() {
+            Some((line, Token::Result(result))) => (*line, *result),
+            Some((line, token)) => return Err(format!("Line {}: Unexpected {}", line, token.describe())),
+            None => return Err("Missing result at the end of the movetext".to_string()),
+        };
+        parser.next += 1;
+        if let Some((line, token)) = parser.peek() {
+            return Err(format!("Line {}: Unexpected {} after the result", line, token.describe()));
+        }
+
+        if let Some(tag) = game.tag("Result")
+            && tag != result_code(result)
+        {
+            return Err(format!("Line {}: Result {} does not match the Result tag {}", line, result_code(result), tag));
+        }
+        match (result, state.current_phase()) {
+            (Some(winner), GamePhase::GameOver { winner: actual }) if winner != actual => {
+                return Err(format!("Line {}: Result says {} won, but {} won", line, winner, actual));
+            }
+            (Some(_), GamePhase::GameOver { .. }) | (None, _) => {}
+            // The player to move lost when their clock ran out
+            (Some(winner), _) if game.tag("Termination") == Some(TIME_FORFEIT) => {
+                if winner == state.current_player() {
+                    return Err(format!("Line {}: Result says {} won on time, but it was {}'s turn", line, winner, winner));
+                }
+            }
+            (Some(_), _) => return Err(format!("Line {}: Result given but the game is not over", line)),
+        }
+        game.result = result;
+        game.set_tag("Result", result_code(result));
+
+        Ok(game)
+    }
+
+    /// Replays the main line, returning the final state.
+    pub fn final_state(&self) -> Result<GameState, String> {
+        let mut positions = Rules::replay(self.board_size()?, &self.move_history())?;
+        Ok(positions.pop().expect("replay includes the starting position"))
+    }
+}
+
+/// Reads the board size and the notation and line of each main-line move, without checking the moves against the
+/// rules. Used to recover the legal start of a game that contains an illegal move.
+pub(crate) fn main_line_moves(content: &str) -> Result<(usize, Vec<(usize, String)>), String> {
+    let mut game = PgnGame {
+        tags: Vec::new(),
+        comment: None,
+        moves: Vec::new(),
+        result: None,
+    };
+    let mut moves = Vec::new();
+    let mut depth = 0usize;
+    for (line, token) in tokenize(content)? {
+        match token {
+            Token::Tag(name, value) => game.tags.push((name, value)),
+            Token::OpenVariation => depth += 1,
+            Token::CloseVariation => depth = depth.saturating_sub(1),
+            Token::Move(notation) if depth == 0 => moves.push((line, notation)),
+            Token::Result(_) if depth == 0 => break,
+            _ => {}
+        }
+    }
+    Ok((game.board_size()?, moves))
+}
+
+/// Returns the result code for a winner: `1-0` for Black, `0-1` for White, `*` for an unfinished game.
+pub fn result_code(winner: Option<PieceColor>) -> &'static str {
+    match winner {
+        Some(PieceColor::Black) => "1-0",
+        Some(PieceColor::White) => "0-1",
+        None => "*",
+    }
+}
+
+/// Formats a clock time for a `[%clk]` comment, as hours, minutes and seconds.
+fn clk_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Writes a comment in braces. A brace comment ends at the first `}` and there is no way to escape one, so any in
+/// the comment are removed. Line breaks become spaces, so that a line of the comment can't be taken for a tag.
+fn comment_token(comment: &str) -> String {
+    let comment: String = comment
+        .chars()
+        .filter(|&c| c != '}')
+        .map(|c| if c == '\n' || c == '\r' { ' ' } else { c })
+        .collect();
+    format!("{{{}}}", comment)
+}
+
+/// Writes the movetext tokens for a line of moves starting at the given ply.
+fn write_moves(moves: &[PgnMove], first_ply: usize, tokens: &mut Vec<String>) {
+    // A White move needs its number when it does not directly follow the Black move
+    let mut number_needed = true;
+    for (offset, mv) in moves.iter().enumerate() {
+        let ply = first_ply + offset;
+        let number = ply / 2 + 1;
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}.", number));
+        } else if number_needed {
+            tokens.push(format!("{}...", number));
+        }
+        tokens.push(mv.record.to_algebraic());
+        number_needed = false;
+
+        if let Some(ref comment) = mv.comment {
+            tokens.push(comment_token(comment));
+            number_needed = true;
+        }
+        for variation in &mv.variations {
+            let mut variation_tokens = Vec::new();
+            write_moves(variation, ply, &mut variation_tokens);
+            if let Some(first) = variation_tokens.first_mut() {
+                first.insert(0, '(');
+            }
+            if let Some(last) = variation_tokens.last_mut() {
+                last.push(')');
+            }
+            tokens.extend(variation_tokens);
+            number_needed = true;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    OpenVariation,
+    CloseVariation,
+    MoveNumber,
+    Move(String),
+    Result(Option<PieceColor>),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Tag(name, _) => format!("tag {}", name),
+            Token::Comment(_) => "comment".to_string(),
+            Token::OpenVariation => "\"(\"".to_string(),
+            Token::CloseVariation => "\")\"".to_string(),
+            Token::MoveNumber => "move number".to_string(),
+            Token::Move(notation) => format!("move {}", notation),
+            Token::Result(result) => format!("result {}", result_code(*result)),
+        }
+    }
+}
+
+/// Splits a record into tokens, each with the line it starts on.
+fn tokenize(content: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = content.chars().peekable();
+    let mut line = 1;
+
+    while let Some(&c) = chars.peek() {
+        let start_line = line;
+        match c {
+            '\n' => {
+                line += 1;
+                chars.next();
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            ';' => {
+                // Comment to the end of the line
+                let text: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                line += 1;
+                tokens.push((start_line, Token::Comment(text[1..].trim().to_string())));
+            }
+            '{' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            text.push(c);
+                        }
+                        None => return Err(format!("Line {}: Unclosed comment", start_line)),
+                    }
+                }
+                tokens.push((start_line, Token::Comment(text.trim().to_string())));
+            }
+            '(' => {
+                chars.next();
+                tokens.push((start_line, Token::OpenVariation));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((start_line, Token::CloseVariation));
+            }
+            '[' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some('"') => {
+                            text.push('"');
+                            // Copy the quoted value, keeping escaped characters escaped
+                            loop {
+                                match chars.next() {
+                                    Some('"') => break,
+                                    Some('\\') => {
+                                        text.push('\\');
+                                        text.extend(chars.next());
+                                    }
+                                    Some('\n') | None => return Err(format!("Line {}: Unterminated tag value", start_line)),
+                                    Some(c) => text.push(c),
+                                }
+                            }
+                            text.push('"');
+                        }
+                        Some('\n') | None => return Err(format!("Line {}: Unclosed tag", start_line)),
+                        Some(c) => text.push(c),
+                    }
+                }
+                tokens.push((start_line, parse_tag(&text, start_line)?));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "{}()[];".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push((start_line, parse_word(&word, start_line)?));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parses the inside of a tag pair, such as `Event "Club night"`.
+fn parse_tag(text: &str, line: usize) -> Result<Token, String> {
+    let text = text.trim();
+    let (name, value) = text
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("Line {}: Invalid tag [{}]", line, text))?;
+    let value = value.trim();
+    let Some(value) = value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) else {
+        return Err(format!("Line {}: Tag {} must have a quoted value", line, name));
+    };
+
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.extend(chars.next());
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Ok(Token::Tag(name.to_string(), unescaped))
+}
+
+fn parse_word(word: &str, line: usize) -> Result<Token, String> {
+    match word {
+        "1-0" => return Ok(Token::Result(Some(PieceColor::Black))),
+        "0-1" => return Ok(Token::Result(Some(PieceColor::White))),
+        "*" => return Ok(Token::Result(None)),
+        _ => {}
+    }
+
+    let digits = word.trim_end_matches('.');
+    if digits.len() < word.len() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(Token::MoveNumber);
+    }
+
+    // A move number may be written without a space before the move, as in "1.d4"
+    let notation = match word.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) if index > 0 && word[index..].starts_with('.') => word[index..].trim_start_matches('.'),
+        _ => word,
+    };
+    if notation.is_empty() || !notation.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(format!("Line {}: Unexpected \"{}\"", line, word));
+    }
+    Ok(Token::Move(notation.to_string()))
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&(usize, Token)> {
+        self.tokens.get(self.next)
+    }
+
+    /// Parses moves starting from a position until the end of the line of play. Returns the moves, any comment
+    /// before the first move, and the final position.
+    fn parse_line(&mut self, start: GameState, depth: usize) -> Result<(Vec<PgnMove>, Option<String>, GameState), String> {
+        let mut moves: Vec<PgnMove> = Vec::new();
+        let mut leading_comment: Option<String> = None;
+        let mut state = start;
+        let mut before_last = state.clone();
+
+        while let Some((line, token)) = self.tokens.get(self.next).cloned() {
+            match token {
+                Token::Tag(name, _) => return Err(format!("Line {}: Tag {} must come before the moves", line, name)),
+                Token::MoveNumber => {}
+                Token::Comment(text) => {
+                    let comment = match moves.last_mut() {
+                        Some(last) => &mut last.comment,
+                        None => &mut leading_comment,
+                    };
+                    match comment {
+                        Some(existing) => {
+                            existing.push(' ');
+                            existing.push_str(&text);
+                        }
+                        None => *comment = Some(text),
+                    }
+                }
+                Token::OpenVariation => {
+                    let Some(last) = moves.last_mut() else {
+                        return Err(format!("Line {}: A variation must follow a move", line));
+                    };
+                    self.next += 1;
+                    let (variation, comment, _) = self.parse_line(before_last.clone(), depth + 1)?;
+                    match self.tokens.get(self.next) {
+                        Some((_, Token::CloseVariation)) => {}
+                        _ => return Err(format!("Line {}: Unclosed variation", line)),
+                    }
+                    if comment.is_some() {
+                        return Err(format!("Line {}: A variation must start with a move", line));
+                    }
+                    last.variations.push(variation);
+                }
+                Token::CloseVariation => {
+                    if depth == 0 {
+                        return Err(format!("Line {}: Unexpected \")\"", line));
+                    }
+                    return Ok((moves, leading_comment, state));
+                }
+                Token::Result(_) => {
+                    if depth > 0 {
+                        return Err(format!("Line {}: Result inside a variation", line));
+                    }
+                    return Ok((moves, leading_comment, state));
+                }
+                Token::Move(notation) => {
+                    before_last = state.clone();
+                    let record = PlayerMove::from_algebraic(&state, &notation)
+                        .and_then(|mv| mv.apply(&mut state).map_err(str::to_string))
+                        .map_err(|err| format!("Line {}: {}: {}", line, notation, err))?;
+                    moves.push(PgnMove::new(record));
+                }
+            }
+            self.next += 1;
+        }
+
+        if depth > 0 {
+            return Err("Unclosed variation at the end of the movetext".to_string());
+        }
+        Ok((moves, leading_comment, state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Position;
+
+    /// Plays the first legal move until the game ends.
+    fn played_history(board_size: usize) -> (MoveHistory, PieceColor) {
+        let center = board_size / 2 - 1;
+        let mut state = GameState::new(board_size, PieceColor::Black);
+        let mut history = vec![
+            Rules::apply_opening_removal(&mut state, Position::new(center, center)).unwrap(),
+            Rules::apply_opening_removal(&mut state, Position::new(center, center + 1)).unwrap(),
+        ];
+        while matches!(state.current_phase(), GamePhase::Play) {
+            let jump = Rules::all_valid_jumps(&state).remove(0);
+            history.push(Rules::apply_jump(&mut state, &jump));
+        }
+        let GamePhase::GameOver { winner } = state.current_phase() else {
+            unreachable!();
+        };
+        (history, winner)
+    }
+
+    mod writer {
+        use super::*;
+
+        #[test]
+        fn writes_tags_then_movetext() {
+            let (history, winner) = played_history(4);
+            let mut game = PgnGame::new(4, &history, Some(winner));
+            game.set_tag("Event", "Test");
+            let pgn = game.to_pgn();
+
+            assert!(pgn.starts_with("[Event \"Test\"]\n"));
+            assert!(pgn.contains("[BoardSize \"4\"]\n"));
+            assert!(pgn.contains("\n\n1. b2 c2 2. "));
+            assert!(pgn.trim_end().ends_with(result_code(Some(winner))));
+        }
+
+        #[test]
+        fn escapes_tag_values() {
+            let mut game = PgnGame::new(4, &Vec::new(), None);
+            game.set_tag("Event", "The \"big\" game");
+            assert!(game.to_pgn().contains(r#"[Event "The \"big\" game"]"#));
+        }
+
+        #[test]
+        fn numbers_white_moves_after_annotations() {
+            let (history, winner) = played_history(4);
+            let mut game = PgnGame::new(4, &history, Some(winner));
+            game.moves[0].comment = Some("Center".to_string());
+            assert!(game.to_pgn().contains("1. b2 {Center} 1... c2 2. "));
+        }
+
+        #[test]
+        fn removes_closing_braces_from_comments() {
+            let (history, winner) = played_history(4);
+            let mut game = PgnGame::new(4, &history, Some(winner));
+            game.comment = Some("Opening {main}".to_string());
+            game.moves[0].comment = Some("Center} 1-0".to_string());
+            game.moves[1].comment = Some("Reply\n[Next \"Game\"]".to_string());
+
+            let pgn = game.to_pgn();
+            let parsed = PgnGame::parse(&pgn).unwrap();
+
+            assert!(pgn.lines().all(|line| !line.starts_with("[Next")), "{}", pgn);
+            assert_eq!(parsed.comment.as_deref(), Some("Opening {main"));
+            assert_eq!(parsed.moves[0].comment.as_deref(), Some("Center 1-0"));
+            assert_eq!(parsed.moves[1].comment.as_deref(), Some("Reply [Next \"Game\"]"));
+            assert_eq!(parsed.move_history(), history);
+            assert_eq!(parsed.result, Some(winner));
+        }
+
+        #[test]
+        fn wraps_long_movetext() {
+            let (history, winner) = played_history(8);
+            let pgn = PgnGame::new(8, &history, Some(winner)).to_pgn();
+            assert!(pgn.lines().all(|line| line.len() <= LINE_WIDTH));
+        }
+    }
+
+    mod clocks {
+        use super::*;
+        use crate::game::TimeControl;
+
+        fn opening() -> MoveHistory {
+            let mut state = GameState::new(4, PieceColor::Black);
+            vec![
+                Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap(),
+                Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap(),
+            ]
+        }
+
+        fn lost_on_time() -> ClockLog {
+            ClockLog {
+                time_control: TimeControl::Increment {
+                    base: Duration::from_secs(300),
+                    increment: Duration::from_secs(3),
+                },
+                times: vec![Duration::from_secs(299), Duration::from_millis(3_601_500)],
+                lost_on_time: Some(PieceColor::Black),
+            }
+        }
+
+        #[test]
+        fn writes_clock_comments_and_tags() {
+            let mut game = PgnGame::new(4, &opening(), Some(PieceColor::White));
+            game.set_clock(&lost_on_time());
+
+            let pgn = game.to_pgn();
+
+            assert!(pgn.contains("[TimeControl \"5+3\"]\n[Termination \"time forfeit\"]\n"), "{}", pgn);
+            assert!(pgn.contains("1. b2 {[%clk 0:04:59]} 1... c2 {[%clk 1:00:01]} 0-1"), "{}", pgn);
+        }
+
+        #[test]
+        fn reads_a_game_lost_on_time() {
+            let mut game = PgnGame::new(4, &opening(), Some(PieceColor::White));
+            game.set_clock(&lost_on_time());
+
+            let parsed = PgnGame::parse(&game.to_pgn()).unwrap();
+
+            assert_eq!(parsed.result, Some(PieceColor::White));
+            assert_eq!(parsed.moves[1].comment.as_deref(), Some("[%clk 1:00:01]"));
+        }
+
+        #[test]
+        fn player_to_move_cannot_win_on_time() {
+            let text = "[BoardSize \"4\"]\n[Termination \"time forfeit\"]\n1. b2 c2 1-0";
+
+            let err = PgnGame::parse(text).unwrap_err();
+
+            assert_eq!(err, "Line 3: Result says Black won on time, but it was Black's turn");
+        }
+    }
+
+    mod reader {
+        use super::*;
+
+        #[test]
+        fn round_trips_move_history() {
+            for size in [4, 6, 8] {
+                let (history, winner) = played_history(size);
+                let game = PgnGame::parse(&PgnGame::new(size, &history, Some(winner)).to_pgn()).unwrap();
+                assert_eq!(game.move_history(), history);
+                assert_eq!(game.result, Some(winner));
+            }
+        }
+
+        #[test]
+        fn round_trips_comments_and_variations() {
+            let text = "[BoardSize \"4\"]\n\n{Opening} 1. b2 {Center} c2 (1... b3) 2. b4-b2 *\n";
+            let game = PgnGame::parse(text).unwrap();
+            assert_eq!(game.comment.as_deref(), Some("Opening"));
+            assert_eq!(game.moves[0].comment.as_deref(), Some("Center"));
+            assert_eq!(game.moves[1].variations.len(), 1);
+            assert_eq!(game.moves[1].variations[0][0].record.to_algebraic(), "b3");
+
+            assert_eq!(PgnGame::parse(&game.to_pgn()).unwrap(), game);
+        }
+
+        #[test]
+        fn reads_nested_variations() {
+            let text = "[BoardSize \"4\"]\n1. b2 c2 2. b4-b2 d3-b3 (2... c4-c2 (2... a2-c2)) *";
+            let game = PgnGame::parse(text).unwrap();
+            let variation = &game.moves[3].variations[0];
+            assert_eq!(variation[0].record.to_algebraic(), "c4-c2");
+            assert_eq!(variation[0].variations[0][0].record.to_algebraic(), "a2-c2");
+
+            assert_eq!(PgnGame::parse(&game.to_pgn()).unwrap(), game);
+        }
+
+        #[test]
+        fn reads_tags_and_compact_move_numbers() {
+            let text = "[Event \"Club night\"]\n[Black \"A \\\"B\\\" C\"]\n[BoardSize \"4\"]\n1.b2 c2 *";
+            let game = PgnGame::parse(text).unwrap();
+            assert_eq!(game.tag("Event"), Some("Club night"));
+            assert_eq!(game.tag("Black"), Some("A \"B\" C"));
+            assert_eq!(game.moves.len(), 2);
+        }
+
+        #[test]
+        fn parses_documented_example() {
+            let text = "[Event \"Club night\"]\n[Result \"*\"]\n[BoardSize \"8\"]\n[Variant \"Standard\"]\n\n\
+                        1. d4 e4 2. b4-d4 {The usual reply} (2. d6-d4) g4-e4 *\n";
+            let game = PgnGame::parse(text).unwrap();
+            assert_eq!(game.moves.len(), 4);
+            assert_eq!(game.moves[2].comment.as_deref(), Some("The usual reply"));
+            assert_eq!(game.moves[2].variations[0][0].record.to_algebraic(), "d6-d4");
+        }
+
+        #[test]
+        fn requires_board_size() {
+            let err = PgnGame::parse("1. b2 *").unwrap_err();
+            assert!(err.contains("BoardSize"));
+        }
+
+        #[test]
+        fn rejects_unknown_variant() {
+            let err = PgnGame::parse("[BoardSize \"4\"]\n[Variant \"Giant\"]\n*").unwrap_err();
+            assert!(err.contains("Unsupported variant"));
+        }
+
+        #[test]
+        fn reports_line_of_illegal_move() {
+            let err = PgnGame::parse("[BoardSize \"4\"]\n\n1. b2 c2\n2. a1-c1 *").unwrap_err();
+            assert_eq!(err, "Line 4: a1-c1: Invalid jump from a1 to c1");
+        }
+
+        #[test]
+        fn checks_variation_moves() {
+            let err = PgnGame::parse("[BoardSize \"4\"]\n1. b2 c2 (1... a1) *").unwrap_err();
+            assert!(err.starts_with("Line 2: a1:"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn rejects_unclosed_variation() {
+            let err = PgnGame::parse("[BoardSize \"4\"]\n1. b2 c2 (1... b3 *").unwrap_err();
+            assert!(err.contains("Result inside a variation"), "Unexpected error: {}", err);
+        }
+
+        #[test]
+        fn requires_result() {
+            let err = PgnGame::parse("[BoardSize \"4\"]\n1. b2 c2").unwrap_err();
+            assert!(err.contains("Missing result"));
+        }
+
+        #[test]
+        fn result_must_match_tag() {
+            let err = PgnGame::parse("[BoardSize \"4\"]\n[Result \"1-0\"]\n1. b2 c2 *").unwrap_err();
+            assert!(err.contains("does not match the Result tag"));
+        }
+
+        #[test]
+        fn result_must_match_game() {
+            let err = PgnGame::parse("[BoardSize \"4\"]\n1. b2 c2 1-0").unwrap_err();
+            assert!(err.contains("not over"));
+        }
+
+        #[test]
+        fn final_state_replays_main_line() {
+            let (history, winner) = played_history(6);
+            let game = PgnGame::new(6, &history, Some(winner));
+            assert_eq!(game.final_state().unwrap().current_phase(), GamePhase::GameOver { winner });
+        }
+    }
+}
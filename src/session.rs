@@ -0,0 +1,403 @@
+//! Saved sessions: an unfinished game together with who is playing it, so it can be resumed later.
+//!
+//! A session file wraps a game in the JSON format. The game holds every move up to the end of the redo stack, and
+//! `current_move` says how many of them have been played, so the moves after it are restored as redo history. A
+//! timed game also stores its time control and the time left on both clocks.
+
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::export;
+use crate::game::clock::TimeControl;
+use crate::game::{GamePhase, GameState, MoveHistory, UndoRedoStack};
+use crate::import;
+
+/// Version of the session format written by this version of Kōnane.
+pub const SESSION_VERSION: u32 = 1;
+
+/// Who controls one side of the board.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PlayerType {
+    #[default]
+    Human,
+    Ai,
+    /// An external program speaking the engine protocol, started with this command
+    External(String),
+}
+
+/// The AI options a session was played with. The opening book is stored by path.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionAiSettings {
+    pub depth: i32,
+    #[serde(default)]
+    pub book: Option<String>,
+    #[serde(default)]
+    pub book_random: bool,
+    #[serde(default = "default_true")]
+    pub move_ordering: bool,
+    #[serde(default)]
+    pub ponder: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The clocks of a timed game when it was saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionClock {
+    pub time_control: TimeControl,
+    pub black: Duration,
+    pub white: Duration,
+    /// Time left on the mover's clock after each move played so far
+    pub times: Vec<Duration>,
+}
+
+/// `SessionClock` as written to the file, with the time control as text and times in milliseconds.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SessionClockFile {
+    time_control: String,
+    black: u64,
+    white: u64,
+    times: Vec<u64>,
+}
+
+impl SessionClockFile {
+    fn new(clock: &SessionClock) -> Self {
+        let millis = |time: Duration| time.as_millis().try_into().unwrap_or(u64::MAX);
+        Self {
+            time_control: clock.time_control.to_string(),
+            black: millis(clock.black),
+            white: millis(clock.white),
+            times: clock.times.iter().map(|&time| millis(time)).collect(),
+        }
+    }
+
+    fn to_clock(&self) -> Result<SessionClock, String> {
+        Ok(SessionClock {
+            time_control: self.time_control.parse()?,
+            black: Duration::from_millis(self.black),
+            white: Duration::from_millis(self.white),
+            times: self.times.iter().map(|&time| Duration::from_millis(time)).collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SessionFile {
+    session_version: u32,
+    black_player: PlayerType,
+    white_player: PlayerType,
+    ai: SessionAiSettings,
+    /// Number of moves of `game` that have been played; the rest can be redone
+    current_move: usize,
+    game: serde_json::Value,
+    /// Absent for untimed games and in sessions written before clocks were saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    clock: Option<SessionClockFile>,
+}
+
+/// A game in progress with its undo and redo history.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub black_player: PlayerType,
+    pub white_player: PlayerType,
+    pub ai: SessionAiSettings,
+    pub state: GameState,
+    pub move_history: MoveHistory,
+    pub undo_stack: UndoRedoStack,
+    pub redo_stack: UndoRedoStack,
+    pub clock: Option<SessionClock>,
+}
+
+impl Session {
+    /// Writes the session as indented JSON.
+    pub fn to_json(&self) -> Result<String, String> {
+        // The bottom of the redo stack is the furthest position reached before undoing.
+        let (final_state, full_history) = self
+            .redo_stack
+            .first()
+            .map_or((&self.state, &self.move_history), |(state, history)| (state, history));
+        let winner = match final_state.current_phase() {
+            GamePhase::GameOver { winner } => Some(winner),
+            _ => None,
+        };
+        let game = export::game_to_json(final_state.board().size(), winner, full_history)?;
+
+        let file = SessionFile {
+            session_version: SESSION_VERSION,
+            black_player: self.black_player.clone(),
+            white_player: self.white_player.clone(),
+            ai: self.ai.clone(),
+            current_move: self.move_history.len(),
+            game: serde_json::from_str(&game).map_err(|err| format!("Failed to serialize game: {}", err))?,
+            clock: self.clock.as_ref().map(SessionClockFile::new),
+        };
+        serde_json::to_string_pretty(&file).map_err(|err| format!("Failed to serialize session: {}", err))
+    }
+
+    /// Reads a session written by `to_json`, replaying and validating its moves.
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let file: SessionFile = serde_json::from_str(content).map_err(|err| format!("Invalid session: {}", err))?;
+        if file.session_version > SESSION_VERSION {
+            return Err(format!(
+                "Unsupported session_version {}: this version of Kōnane reads up to version {}. Upgrade Kōnane to \
+                 resume this game.",
+                file.session_version, SESSION_VERSION
+            ));
+        }
+
+        let (mut state, mut move_history, mut undo_stack) = import::import_game_from_content(&file.game.to_string())?;
+        if file.current_move > move_history.len() {
+            return Err(format!(
+                "current_move is {} but the game has {} moves",
+                file.current_move,
+                move_history.len()
+            ));
+        }
+
+        // Undo back to the current move, as the player did before saving.
+        let mut redo_stack = UndoRedoStack::new();
+        while move_history.len() > file.current_move {
+            let (previous_state, previous_history) = undo_stack.pop().expect("one undo entry per move");
+            redo_stack.push((state, move_history));
+            state = previous_state;
+            move_history = previous_history;
+        }
+
+        let clock = file.clock.as_ref().map(SessionClockFile::to_clock).transpose()?;
+
+        Ok(Self {
+            black_player: file.black_player,
+            white_player: file.white_player,
+            ai: file.ai,
+            state,
+            move_history,
+            undo_stack,
+            redo_stack,
+            clock,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        std::fs::write(path, self.to_json()?).map_err(|err| format!("Failed to write session: {}", err))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| format!("Failed to read file: {}", err))?;
+        Self::from_json(&content)
+    }
+}
+
+/// Returns whether the content looks like a session file rather than a plain game.
+pub fn is_session(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content).is_ok_and(|value| value.get("session_version").is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{PieceColor, Position, Rules};
+
+    fn ai_settings() -> SessionAiSettings {
+        SessionAiSettings {
+            depth: 4,
+            book: Some("book.json".to_string()),
+            book_random: true,
+            move_ordering: false,
+            ponder: true,
+        }
+    }
+
+    /// Plays the 4x4 opening b2, c2, b4-b2 and records undo entries the way the app does.
+    fn played_session() -> Session {
+        let mut state = GameState::new(4, PieceColor::Black);
+        let mut move_history = MoveHistory::new();
+        let mut undo_stack = UndoRedoStack::new();
+
+        for pos in [Position::new(1, 1), Position::new(1, 2)] {
+            undo_stack.push((state.clone(), move_history.clone()));
+            move_history.push(Rules::apply_opening_removal(&mut state, pos).unwrap());
+        }
+        let jump = Rules::all_valid_jumps(&state).remove(0);
+        undo_stack.push((state.clone(), move_history.clone()));
+        move_history.push(Rules::apply_jump(&mut state, &jump));
+
+        Session {
+            black_player: PlayerType::Human,
+            white_player: PlayerType::Ai,
+            ai: ai_settings(),
+            state,
+            move_history,
+            undo_stack,
+            redo_stack: UndoRedoStack::new(),
+            clock: None,
+        }
+    }
+
+    fn undo(session: &mut Session) {
+        let (state, history) = session.undo_stack.pop().unwrap();
+        let current = std::mem::replace(&mut session.state, state);
+        let current_history = std::mem::replace(&mut session.move_history, history);
+        session.redo_stack.push((current, current_history));
+    }
+
+    mod round_trip {
+        use super::*;
+
+        #[test]
+        fn restores_players_and_ai_settings() {
+            let session = played_session();
+
+            let restored = Session::from_json(&session.to_json().unwrap()).unwrap();
+
+            assert_eq!(restored.black_player, PlayerType::Human);
+            assert_eq!(restored.white_player, PlayerType::Ai);
+            assert_eq!(restored.ai, ai_settings());
+        }
+
+        #[test]
+        fn restores_external_engines() {
+            let mut session = played_session();
+            session.white_player = PlayerType::External("konane engine".to_string());
+
+            let restored = Session::from_json(&session.to_json().unwrap()).unwrap();
+
+            assert_eq!(restored.white_player, PlayerType::External("konane engine".to_string()));
+        }
+
+        #[test]
+        fn restores_position_and_undo_stack() {
+            let session = played_session();
+
+            let restored = Session::from_json(&session.to_json().unwrap()).unwrap();
+
+            assert_eq!(restored.move_history, session.move_history);
+            assert_eq!(restored.state.fingerprint(), session.state.fingerprint());
+            assert_eq!(restored.undo_stack.len(), 3);
+            assert!(restored.redo_stack.is_empty());
+        }
+
+        #[test]
+        fn restores_redo_stack() {
+            let mut session = played_session();
+            undo(&mut session);
+            undo(&mut session);
+
+            let restored = Session::from_json(&session.to_json().unwrap()).unwrap();
+
+            assert_eq!(restored.move_history.len(), 1);
+            assert_eq!(restored.undo_stack.len(), 1);
+            assert_eq!(restored.redo_stack.len(), 2);
+            for (restored, original) in restored.redo_stack.iter().zip(&session.redo_stack) {
+                assert_eq!(restored.0.fingerprint(), original.0.fingerprint());
+                assert_eq!(restored.1, original.1);
+            }
+        }
+
+        #[test]
+        fn restores_clocks() {
+            let mut session = played_session();
+            let clock = SessionClock {
+                time_control: "5+3".parse().unwrap(),
+                black: Duration::from_millis(291_500),
+                white: Duration::from_secs(300),
+                times: vec![Duration::from_secs(303), Duration::from_secs(302), Duration::from_millis(291_500)],
+            };
+            session.clock = Some(clock.clone());
+
+            let restored = Session::from_json(&session.to_json().unwrap()).unwrap();
+
+            assert_eq!(restored.clock, Some(clock));
+        }
+
+        #[test]
+        fn untimed_sessions_have_no_clock() {
+            let session = played_session();
+
+            let json = session.to_json().unwrap();
+
+            assert!(!json.contains("\"clock\""));
+            assert_eq!(Session::from_json(&json).unwrap().clock, None);
+        }
+
+        #[test]
+        fn writes_plain_game_json() {
+            let mut session = played_session();
+            undo(&mut session);
+
+            let value: serde_json::Value = serde_json::from_str(&session.to_json().unwrap()).unwrap();
+
+            assert_eq!(value["current_move"], 2);
+            let game = value["game"].to_string();
+            let (_, history, _) = import::import_game_from_content(&game).unwrap();
+            assert_eq!(history.len(), 3);
+        }
+    }
+
+    mod errors {
+        use super::*;
+
+        fn session_value() -> serde_json::Value {
+            serde_json::from_str(&played_session().to_json().unwrap()).unwrap()
+        }
+
+        #[test]
+        fn rejects_newer_version() {
+            let mut value = session_value();
+            value["session_version"] = serde_json::json!(SESSION_VERSION + 1);
+
+            let err = Session::from_json(&value.to_string()).unwrap_err();
+
+            assert!(err.contains("Unsupported session_version"));
+        }
+
+        #[test]
+        fn rejects_current_move_past_end() {
+            let mut value = session_value();
+            value["current_move"] = serde_json::json!(4);
+
+            let err = Session::from_json(&value.to_string()).unwrap_err();
+
+            assert_eq!(err, "current_move is 4 but the game has 3 moves");
+        }
+
+        #[test]
+        fn rejects_invalid_time_control() {
+            let mut value = session_value();
+            value["clock"] = serde_json::json!({ "time_control": "5+", "black": 1000, "white": 1000, "times": [] });
+
+            assert!(Session::from_json(&value.to_string()).is_err());
+        }
+
+        #[test]
+        fn rejects_invalid_game() {
+            let mut value = session_value();
+            value["game"]["board_size"] = serde_json::json!(5);
+
+            assert!(Session::from_json(&value.to_string()).is_err());
+        }
+    }
+
+    mod detection {
+        use super::*;
+
+        #[test]
+        fn recognises_sessions() {
+            assert!(is_session(&played_session().to_json().unwrap()));
+        }
+
+        #[test]
+        fn rejects_plain_games() {
+            let session = played_session();
+            let game = export::game_to_json(4, None, &session.move_history).unwrap();
+
+            assert!(!is_session(&game));
+            assert!(!is_session("1. b2 c2"));
+        }
+    }
+}
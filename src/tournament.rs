@@ -0,0 +1,802 @@
+//! Matches between AI configurations, to tell whether a change to the AI makes it stronger.
+//!
+//! A tournament is a round robin, where every pair of contestants meets, or a gauntlet, where the first contestant
+//! meets each of the others. On each board size, a pair plays every opening (Black's and White's first removals) once
+//! with each contestant as Black, so neither the opening nor the color decides the result. Games run in parallel, and
+//! the results are summarized as win rates and Elo ratings with 95% confidence intervals.
+//!
+//! Contestants are written as comma-separated `key=value` settings, such as `name=deep,depth=6`:
+//!
+//! - `name`: shown in the results (the whole description if omitted).
+//! - `depth`: search depth (the default depth if omitted, or unlimited when `time` is given).
+//! - `time`: milliseconds to search each move; the search stops at the depth or when the time is up.
+//! - `ordering`: `on` or `off`, whether children are searched best first.
+//! - `mobility` and `opponent`: weights of the AI's own jumps and its opponent's jumps in the evaluation.
+//! - `engine`: command starting an external engine, which plays instead of the built-in AI. It must be last, as
+//!   everything after `engine=` is the command.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::engine::UNLIMITED_DEPTH;
+use crate::game::player::{ExternalEnginePlayer, PlayerMove};
+use crate::game::{AiPlayer, EvalWeights, GamePhase, GameState, MoveHistory, PieceColor, Rules};
+
+/// One AI configuration taking part in a tournament.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contestant {
+    pub name: String,
+    pub depth: i32,
+    /// Time to search each move
+    pub move_time: Option<Duration>,
+    pub move_ordering: bool,
+    pub weights: EvalWeights,
+    /// Command starting an external engine, which plays instead of the built-in AI
+    pub engine: Option<String>,
+}
+
+impl Contestant {
+    /// A built-in AI searching to the given depth.
+    pub fn new(name: &str, depth: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            depth,
+            move_time: None,
+            move_ordering: true,
+            weights: EvalWeights::default(),
+            engine: None,
+        }
+    }
+
+    /// Reads a contestant from its settings, described in the module documentation.
+    pub fn parse(description: &str, default_depth: i32) -> Result<Self, String> {
+        let mut contestant = Self::new(description, default_depth);
+        let mut depth = None;
+        let mut rest = description.trim();
+        while !rest.is_empty() {
+            let (setting, remaining) = match rest.split_once(',') {
+                Some(_) if rest.starts_with("engine=") => (rest, ""),
+                Some((setting, remaining)) => (setting, remaining),
+                None => (rest, ""),
+            };
+            rest = remaining.trim_start();
+            let (key, value) = setting
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .ok_or_else(|| format!("Expected key=value in \"{}\", found \"{}\"", description, setting))?;
+            let number = |value: &str| {
+                value
+                    .parse::<f32>()
+                    .map_err(|_| format!("Invalid {} \"{}\" in \"{}\"", key, value, description))
+            };
+            match key {
+                "name" => contestant.name = value.to_string(),
+                "depth" => {
+                    depth = Some(
+                        value
+                            .parse::<i32>()
+                            .ok()
+                            .filter(|depth| (1..=UNLIMITED_DEPTH).contains(depth))
+                            .ok_or_else(|| format!("Invalid depth \"{}\" in \"{}\"", value, description))?,
+                    )
+                }
+                "time" => {
+                    let millis = value
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|&millis| millis > 0)
+                        .ok_or_else(|| format!("Invalid time \"{}\" in \"{}\"", value, description))?;
+                    contestant.move_time = Some(Duration::from_millis(millis));
+                }
+                "ordering" => {
+                    contestant.move_ordering = match value {
+                        "on" => true,
+                        "off" => false,
+                        _ => return Err(format!("Invalid ordering \"{}\" in \"{}\": use on or off", value, description)),
+                    }
+                }
+                "mobility" => contestant.weights.mobility = number(value)?,
+                "opponent" => contestant.weights.opponent_mobility = number(value)?,
+                "engine" if value.is_empty() => return Err(format!("Missing engine command in \"{}\"", description)),
+                "engine" => contestant.engine = Some(value.to_string()),
+                _ => return Err(format!("Unknown setting \"{}\" in \"{}\"", key, description)),
+            }
+        }
+        contestant.depth = match (depth, contestant.move_time) {
+            (Some(depth), _) => depth,
+            (None, Some(_)) => UNLIMITED_DEPTH,
+            (None, None) => default_depth,
+        };
+        Ok(contestant)
+    }
+
+    fn create_player(&self, color: PieceColor, rng: &mut StdRng) -> Result<Competitor, String> {
+        if let Some(ref command) = self.engine {
+            let mut engine = ExternalEnginePlayer::spawn(color, command)?;
+            if let Some(move_time) = self.move_time {
+                engine = engine.with_move_time(move_time);
+            }
+            return Ok(Competitor::Engine(engine));
+        }
+        let ai = AiPlayer::new(color, self.depth)
+            .with_move_ordering(self.move_ordering)
+            .with_weights(self.weights)
+            .with_rng(StdRng::from_rng(rng));
+        Ok(Competitor::Ai {
+            ai: Box::new(ai),
+            depth: self.depth,
+            move_time: self.move_time,
+        })
+    }
+}
+
+/// A contestant playing one game.
+enum Competitor {
+    Ai {
+        ai: Box<AiPlayer>,
+        depth: i32,
+        move_time: Option<Duration>,
+    },
+    Engine(ExternalEnginePlayer),
+}
+
+impl Competitor {
+    fn choose_move(&mut self, state: &GameState) -> Result<PlayerMove, String> {
+        match self {
+            Competitor::Ai { ai, depth, move_time } => {
+                let best_move = match move_time {
+                    Some(move_time) => ai.search_with_time_limit(state, *depth, *move_time).0,
+                    None => ai.compute_move(state),
+                };
+                best_move.ok_or_else(|| format!("{} AI found no move", state.current_player()))
+            }
+            Competitor::Engine(engine) => engine.choose_move(state),
+        }
+    }
+}
+
+/// Which pairs of contestants play each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Every contestant plays every other
+    RoundRobin,
+    /// The first contestant plays each of the others, which do not play each other
+    Gauntlet,
+}
+
+/// One game of a tournament, before it is played.
+#[derive(Debug, Clone)]
+pub struct ScheduledGame {
+    /// Position of the game in the schedule, starting at 1
+    pub number: usize,
+    pub board_size: usize,
+    /// Indices of the contestants playing each color
+    pub black: usize,
+    pub white: usize,
+    /// Black's and White's opening removals
+    pub opening: [PlayerMove; 2],
+}
+
+/// A game that has been played.
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    pub game: ScheduledGame,
+    pub winner: PieceColor,
+    pub history: MoveHistory,
+}
+
+impl GameResult {
+    /// Indices of the winning and losing contestants.
+    pub fn winner_and_loser(&self) -> (usize, usize) {
+        match self.winner {
+            PieceColor::Black => (self.game.black, self.game.white),
+            PieceColor::White => (self.game.white, self.game.black),
+        }
+    }
+}
+
+/// Every opening of a board size: each of Black's removals followed by each of White's replies.
+pub fn openings(board_size: usize) -> Vec<[PlayerMove; 2]> {
+    let start = GameState::new(board_size, PieceColor::Black);
+    let mut openings = Vec::new();
+    for black in Rules::legal_moves(&start) {
+        let mut state = start.clone();
+        if black.apply(&mut state).is_err() {
+            continue;
+        }
+        for white in Rules::legal_moves(&state) {
+            openings.push([black.clone(), white]);
+        }
+    }
+    openings
+}
+
+/// A set of contestants and how they meet.
+pub struct Tournament {
+    contestants: Vec<Contestant>,
+    format: Format,
+    board_sizes: Vec<usize>,
+    rounds: usize,
+    threads: usize,
+    seed: Option<u64>,
+}
+
+impl Tournament {
+    /// A tournament on 8x8 boards, one round, using every processor. Fails with fewer than two contestants or two
+    /// contestants with the same name.
+    pub fn new(contestants: Vec<Contestant>, format: Format) -> Result<Self, String> {
+        if contestants.len() < 2 {
+            return Err("A tournament needs at least two contestants".to_string());
+        }
+        for (index, contestant) in contestants.iter().enumerate() {
+            if contestants[..index].iter().any(|other| other.name == contestant.name) {
+                return Err(format!("Two contestants are named \"{}\"", contestant.name));
+            }
+        }
+        Ok(Self {
+            contestants,
+            format,
+            board_sizes: vec![8],
+            rounds: 1,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            seed: None,
+        })
+    }
+
+    /// Plays on each of the board sizes. Fails if a size is not even or not between 4 and 16.
+    pub fn with_board_sizes(mut self, board_sizes: Vec<usize>) -> Result<Self, String> {
+        if board_sizes.is_empty() {
+            return Err("No board sizes given".to_string());
+        }
+        if let Some(size) = board_sizes.iter().find(|&&size| !(4..=16).contains(&size) || !size.is_multiple_of(2)) {
+            return Err(format!("Invalid board size {}: must be even and between 4 and 16", size));
+        }
+        self.board_sizes = board_sizes;
+        Ok(self)
+    }
+
+    /// Plays the whole schedule this many times.
+    pub fn with_rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds.max(1);
+        self
+    }
+
+    /// Plays this many games at once.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Seeds the AIs' random choices, so that a tournament can be repeated.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn contestants(&self) -> &[Contestant] {
+        &self.contestants
+    }
+
+    fn pairs(&self) -> Vec<(usize, usize)> {
+        let count = self.contestants.len();
+        match self.format {
+            Format::RoundRobin => (0..count).flat_map(|a| (a + 1..count).map(move |b| (a, b))).collect(),
+            Format::Gauntlet => (1..count).map(|b| (0, b)).collect(),
+        }
+    }
+
+    /// Lists the games to play: for each round, board size and pair, every opening with each contestant as Black.
+    pub fn schedule(&self) -> Vec<ScheduledGame> {
+        let mut games = Vec::new();
+        for _ in 0..self.rounds {
+            for &board_size in &self.board_sizes {
+                let openings = openings(board_size);
+                for (a, b) in self.pairs() {
+                    for opening in &openings {
+                        for (black, white) in [(a, b), (b, a)] {
+                            games.push(ScheduledGame {
+                                number: games.len() + 1,
+                                board_size,
+                                black,
+                                white,
+                                opening: opening.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        games
+    }
+
+    /// Plays the schedule in parallel, calling `on_result` as each game finishes. Stops at the first game that fails,
+    /// such as when an engine crashes, or when `on_result` fails.
+    pub fn run<F>(&self, mut on_result: F) -> Result<Standings, String>
+    where
+        F: FnMut(&GameResult) -> Result<(), String>,
+    {
+        let games = self.schedule();
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let next = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let mut standings = Standings::new(self.contestants.iter().map(|contestant| contestant.name.clone()).collect());
+        let mut error = None;
+
+        thread::scope(|scope| {
+            let (sender, results) = mpsc::channel();
+            for _ in 0..self.threads.min(games.len()) {
+                let sender = sender.clone();
+                let (games, next, failed) = (&games, &next, &failed);
+                scope.spawn(move || {
+                    while !failed.load(Ordering::SeqCst) {
+                        let Some(game) = games.get(next.fetch_add(1, Ordering::SeqCst)) else {
+                            break;
+                        };
+                        let result = self.play(game, seed.wrapping_add(game.number as u64));
+                        if sender.send(result).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            for result in results {
+                if error.is_some() {
+                    continue;
+                }
+                match result.and_then(|result| on_result(&result).map(|()| result)) {
+                    Ok(result) => standings.record(&result),
+                    Err(err) => {
+                        failed.store(true, Ordering::SeqCst);
+                        error = Some(err);
+                    }
+                }
+            }
+        });
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(standings),
+        }
+    }
+
+    /// Plays one game from its opening to the end.
+    pub fn play(&self, game: &ScheduledGame, seed: u64) -> Result<GameResult, String> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let black = &self.contestants[game.black];
+        let white = &self.contestants[game.white];
+        let mut players = [
+            black.create_player(PieceColor::Black, &mut rng)?,
+            white.create_player(PieceColor::White, &mut rng)?,
+        ];
+
+        let mut state = GameState::new(game.board_size, PieceColor::Black);
+        let mut history = MoveHistory::new();
+        for player_move in &game.opening {
+            history.push(player_move.apply(&mut state).map_err(str::to_string)?);
+        }
+        let winner = loop {
+            if let GamePhase::GameOver { winner } = state.current_phase() {
+                break winner;
+            }
+            let (player, contestant) = match state.current_player() {
+                PieceColor::Black => (&mut players[0], black),
+                PieceColor::White => (&mut players[1], white),
+            };
+            let player_move = player
+                .choose_move(&state)
+                .map_err(|err| format!("Game {}, {} at move {}: {}", game.number, contestant.name, history.len() + 1, err))?;
+            let record = player_move.apply(&mut state).map_err(|err| {
+                format!("Game {}, {} at move {}: {}", game.number, contestant.name, history.len() + 1, err)
+            })?;
+            history.push(record);
+        };
+
+        Ok(GameResult {
+            game: game.clone(),
+            winner,
+            history,
+        })
+    }
+}
+
+/// Wins and losses between contestants, for summarizing a tournament.
+#[derive(Debug, Clone)]
+pub struct Standings {
+    names: Vec<String>,
+    /// `wins[a][b]` is the number of games `a` won against `b`
+    wins: Vec<Vec<u32>>,
+    /// Wins and games of each contestant on each board size
+    by_size: BTreeMap<usize, Vec<(u32, u32)>>,
+}
+
+impl Standings {
+    pub fn new(names: Vec<String>) -> Self {
+        let count = names.len();
+        Self {
+            names,
+            wins: vec![vec![0; count]; count],
+            by_size: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, result: &GameResult) {
+        let (winner, loser) = result.winner_and_loser();
+        self.wins[winner][loser] += 1;
+        let count = self.names.len();
+        let sizes = self.by_size.entry(result.game.board_size).or_insert_with(|| vec![(0, 0); count]);
+        sizes[winner].0 += 1;
+        sizes[winner].1 += 1;
+        sizes[loser].1 += 1;
+    }
+
+    /// Wins and games of a contestant against everyone.
+    pub fn score(&self, contestant: usize) -> (u32, u32) {
+        let wins: u32 = self.wins[contestant].iter().sum();
+        let losses: u32 = self.wins.iter().map(|row| row[contestant]).sum();
+        (wins, wins + losses)
+    }
+
+    /// Elo ratings fitted to all the results at once, averaging 0. Each pair that met is credited with an extra half
+    /// win each, so that a contestant that won or lost every game still gets a finite rating.
+    pub fn ratings(&self) -> Vec<f64> {
+        let count = self.names.len();
+        let smoothed = |a: usize, b: usize| {
+            let games = self.wins[a][b] + self.wins[b][a];
+            if games > 0 { f64::from(self.wins[a][b]) + 0.5 } else { 0.0 }
+        };
+        let mut strengths = vec![1.0; count];
+        for _ in 0..1000 {
+            for a in 0..count {
+                let wins: f64 = (0..count).map(|b| smoothed(a, b)).sum();
+                let expected: f64 = (0..count)
+                    .filter(|&b| b != a)
+                    .map(|b| (smoothed(a, b) + smoothed(b, a)) / (strengths[a] + strengths[b]))
+                    .sum();
+                if expected > 0.0 {
+                    strengths[a] = wins / expected;
+                }
+            }
+            let mean_log = strengths.iter().map(|strength: &f64| strength.ln()).sum::<f64>() / count as f64;
+            strengths.iter_mut().for_each(|strength| *strength /= mean_log.exp());
+        }
+        strengths.iter().map(|strength| 400.0 * strength.log10()).collect()
+    }
+
+    /// Describes the results: each contestant's win rate and rating, every pairing, and the win rates on each board
+    /// size.
+    pub fn report(&self) -> String {
+        let ratings = self.ratings();
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|&a, &b| ratings[b].total_cmp(&ratings[a]));
+        let width = self.names.iter().map(|name| name.chars().count()).max().unwrap_or(0).max(6);
+
+        let mut text = format!(
+            "{:<width$}  {:>5}  {:>5}  {:>6}  {:>5}  {:>6}\n",
+            "Player", "Games", "Wins", "Win %", "Elo", "95% CI"
+        );
+        for &index in &order {
+            let (wins, games) = self.score(index);
+            let (low, high) = elo_interval(wins, games);
+            let margin = format!("±{:.0}", (high - low) / 2.0);
+            text.push_str(&format!(
+                "{:<width$}  {:>5}  {:>5}  {:>6}  {:>+5.0}  {:>6}\n",
+                self.names[index],
+                games,
+                wins,
+                percentage(wins, games),
+                ratings[index],
+                margin,
+            ));
+        }
+
+        text.push('\n');
+        for (position, &a) in order.iter().enumerate() {
+            for &b in &order[position + 1..] {
+                let (wins, losses) = (self.wins[a][b], self.wins[b][a]);
+                if wins + losses == 0 {
+                    continue;
+                }
+                let (low, high) = elo_interval(wins, wins + losses);
+                text.push_str(&format!(
+                    "{} vs {}: {}-{} ({}), Elo {:+.0} [{:+.0}, {:+.0}]\n",
+                    self.names[a],
+                    self.names[b],
+                    wins,
+                    losses,
+                    percentage(wins, wins + losses).trim(),
+                    elo_difference(smoothed_score(wins, wins + losses)),
+                    low,
+                    high
+                ));
+            }
+        }
+
+        if self.by_size.len() > 1 {
+            let label = "Win % by size";
+            let width = width.max(label.len());
+            text.push_str(&format!("\n{:<width$}", label));
+            for size in self.by_size.keys() {
+                let size = format!("{}x{}", size, size);
+                text.push_str(&format!("  {:>6}", size));
+            }
+            text.push('\n');
+            for &index in &order {
+                text.push_str(&format!("{:<width$}", self.names[index]));
+                for sizes in self.by_size.values() {
+                    let (wins, games) = sizes[index];
+                    text.push_str(&format!("  {:>6}", percentage(wins, games)));
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+fn percentage(wins: u32, games: u32) -> String {
+    if games == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}%", 100.0 * f64::from(wins) / f64::from(games))
+}
+
+/// The score with half a win and half a loss added, so that it is never 0 or 1.
+fn smoothed_score(wins: u32, games: u32) -> f64 {
+    (f64::from(wins) + 0.5) / (f64::from(games) + 1.0)
+}
+
+/// The Elo difference at which the stronger player is expected to score `score`, a fraction of the points between 0
+/// and 1.
+pub fn elo_difference(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// The 95% confidence interval of the Elo difference implied by winning `wins` of `games`.
+pub fn elo_interval(wins: u32, games: u32) -> (f64, f64) {
+    let score = smoothed_score(wins, games);
+    let margin = 1.96 * (score * (1.0 - score) / f64::from(games.max(1))).sqrt();
+    let bound = |score: f64| elo_difference(score.clamp(0.001, 0.999));
+    (bound(score - margin), bound(score + margin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod contestants {
+        use super::*;
+
+        #[test]
+        fn parses_settings() {
+            let contestant = Contestant::parse("name=wide,depth=3,ordering=off,mobility=1,opponent=2.5", 8).unwrap();
+
+            assert_eq!(contestant.name, "wide");
+            assert_eq!(contestant.depth, 3);
+            assert!(!contestant.move_ordering);
+            assert_eq!(contestant.weights.opponent_mobility, 2.5);
+            assert_eq!(contestant.engine, None);
+        }
+
+        #[test]
+        fn defaults_to_the_description_and_depth() {
+            let contestant = Contestant::parse("ordering=on", 5).unwrap();
+
+            assert_eq!(contestant, Contestant::new("ordering=on", 5));
+        }
+
+        #[test]
+        fn time_without_depth_searches_until_the_time_is_up() {
+            let contestant = Contestant::parse("time=250", 5).unwrap();
+
+            assert_eq!(contestant.move_time, Some(Duration::from_millis(250)));
+            assert_eq!(contestant.depth, UNLIMITED_DEPTH);
+        }
+
+        #[test]
+        fn engine_command_keeps_its_commas_and_spaces() {
+            let contestant = Contestant::parse("name=ext, engine=my-engine --weights 1,2", 5).unwrap();
+
+            assert_eq!(contestant.engine.as_deref(), Some("my-engine --weights 1,2"));
+        }
+
+        #[test]
+        fn rejects_unknown_and_invalid_settings() {
+            assert!(Contestant::parse("speed=3", 5).unwrap_err().contains("Unknown setting"));
+            assert!(Contestant::parse("depth=0", 5).is_err());
+            assert!(Contestant::parse("ordering=maybe", 5).is_err());
+            assert!(Contestant::parse("depth", 5).is_err());
+        }
+    }
+
+    mod schedule {
+        use super::*;
+
+        fn contestants(count: usize) -> Vec<Contestant> {
+            (1..=count).map(|depth| Contestant::new(&format!("depth{}", depth), depth as i32)).collect()
+        }
+
+        #[test]
+        fn covers_every_opening() {
+            // Black removes a corner or a center stone, and White removes a stone next to it
+            assert_eq!(openings(4).len(), 12);
+            assert_eq!(openings(8).len(), 12);
+        }
+
+        #[test]
+        fn plays_each_opening_with_both_colors() {
+            let tournament = Tournament::new(contestants(2), Format::RoundRobin).unwrap();
+
+            let games = tournament.schedule();
+
+            assert_eq!(games.len(), 24);
+            assert_eq!(games.iter().filter(|game| game.black == 0).count(), 12);
+            assert_eq!(games[0].opening[0].to_algebraic(), games[1].opening[0].to_algebraic());
+            assert_eq!((games[0].black, games[1].black), (0, 1));
+        }
+
+        #[test]
+        fn a_gauntlet_only_plays_the_first_contestant() {
+            let tournament = Tournament::new(contestants(3), Format::Gauntlet)
+                .unwrap()
+                .with_board_sizes(vec![4, 6])
+                .unwrap();
+
+            let games = tournament.schedule();
+
+            assert_eq!(games.len(), 2 * 2 * openings(4).len() + 2 * 2 * openings(6).len());
+            assert!(games.iter().all(|game| game.black == 0 || game.white == 0));
+        }
+
+        #[test]
+        fn a_round_robin_plays_every_pair() {
+            let tournament = Tournament::new(contestants(3), Format::RoundRobin).unwrap().with_rounds(2);
+
+            assert_eq!(tournament.schedule().len(), 2 * 3 * 24);
+        }
+
+        #[test]
+        fn rejects_bad_tournaments() {
+            assert!(Tournament::new(contestants(1), Format::RoundRobin).is_err());
+            let twins = vec![Contestant::new("twin", 1), Contestant::new("twin", 2)];
+            assert!(Tournament::new(twins, Format::RoundRobin).is_err());
+            let tournament = Tournament::new(contestants(2), Format::RoundRobin).unwrap();
+            assert!(tournament.with_board_sizes(vec![5]).is_err());
+        }
+    }
+
+    mod running {
+        use super::*;
+
+        #[test]
+        fn plays_every_game_in_parallel() {
+            let contestants = vec![Contestant::new("shallow", 1), Contestant::new("deeper", 2)];
+            let tournament = Tournament::new(contestants, Format::RoundRobin)
+                .unwrap()
+                .with_board_sizes(vec![4])
+                .unwrap()
+                .with_threads(3)
+                .with_seed(Some(1));
+            let mut numbers = Vec::new();
+
+            let standings = tournament
+                .run(|result| {
+                    numbers.push(result.game.number);
+                    Ok(())
+                })
+                .unwrap();
+
+            numbers.sort();
+            assert_eq!(numbers, (1..=24).collect::<Vec<_>>());
+            assert_eq!(standings.score(0).1, 24);
+            assert_eq!(standings.score(0).0 + standings.score(1).0, 24);
+        }
+
+        #[test]
+        fn stops_when_a_result_cannot_be_recorded() {
+            let contestants = vec![Contestant::new("a", 1), Contestant::new("b", 1)];
+            let tournament = Tournament::new(contestants, Format::RoundRobin)
+                .unwrap()
+                .with_board_sizes(vec![4])
+                .unwrap()
+                .with_threads(2);
+
+            let result = tournament.run(|_| Err("disk full".to_string()));
+
+            assert_eq!(result.unwrap_err(), "disk full");
+        }
+
+        #[test]
+        fn a_timed_search_still_finds_a_move() {
+            let contestant = Contestant::parse("time=20", 1).unwrap();
+            let mut player = contestant.create_player(PieceColor::Black, &mut StdRng::seed_from_u64(0)).unwrap();
+
+            assert!(player.choose_move(&GameState::new(8, PieceColor::Black)).is_ok());
+        }
+    }
+
+    mod ratings {
+        use super::*;
+
+        fn result(black: usize, white: usize, winner: PieceColor) -> GameResult {
+            GameResult {
+                game: ScheduledGame {
+                    number: 1,
+                    board_size: 4,
+                    black,
+                    white,
+                    opening: openings(4)[0].clone(),
+                },
+                winner,
+                history: MoveHistory::new(),
+            }
+        }
+
+        #[test]
+        fn converts_scores_to_elo() {
+            assert!(elo_difference(0.5).abs() < 1e-9);
+            assert!((elo_difference(0.75) - 190.85).abs() < 0.01);
+            assert!((elo_difference(0.25) + 190.85).abs() < 0.01);
+        }
+
+        #[test]
+        fn intervals_narrow_with_more_games() {
+            let (low, high) = elo_interval(6, 10);
+            let (more_low, more_high) = elo_interval(60, 100);
+
+            assert!(low < 0.0 && high > 0.0);
+            assert!(more_high - more_low < high - low);
+        }
+
+        #[test]
+        fn fits_ratings_to_the_results() {
+            let mut standings = Standings::new(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+            for _ in 0..3 {
+                standings.record(&result(0, 1, PieceColor::Black));
+                standings.record(&result(1, 2, PieceColor::Black));
+            }
+            standings.record(&result(0, 2, PieceColor::White));
+
+            let ratings = standings.ratings();
+
+            assert!(ratings[0] > ratings[1] && ratings[1] > ratings[2]);
+            assert!(ratings.iter().sum::<f64>().abs() < 1e-6);
+        }
+
+        #[test]
+        fn even_results_rate_equally() {
+            let mut standings = Standings::new(vec!["a".to_string(), "b".to_string()]);
+            standings.record(&result(0, 1, PieceColor::Black));
+            standings.record(&result(1, 0, PieceColor::Black));
+
+            let ratings = standings.ratings();
+
+            assert!(ratings[0].abs() < 1e-6 && ratings[1].abs() < 1e-6);
+        }
+
+        #[test]
+        fn reports_players_pairings_and_sizes() {
+            let mut standings = Standings::new(vec!["strong".to_string(), "weak".to_string()]);
+            standings.record(&result(0, 1, PieceColor::Black));
+            standings.record(&result(1, 0, PieceColor::White));
+            let mut larger = result(0, 1, PieceColor::White);
+            larger.game.board_size = 6;
+            standings.record(&larger);
+
+            let report = standings.report();
+            let lines: Vec<&str> = report.lines().collect();
+
+            assert!(lines[0].starts_with("Player"));
+            assert!(lines[1].starts_with("strong      3      2   66.7%"), "{}", report);
+            assert!(report.contains("strong vs weak: 2-1 (66.7%)"), "{}", report);
+            assert!(report.contains("Win % by size     4x4     6x6"), "{}", report);
+        }
+    }
+}
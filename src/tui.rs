@@ -0,0 +1,367 @@
+//! Playing in a terminal, for machines without a display such as over SSH.
+//!
+//! `TerminalGame` holds the game and turns each line the user types into a move or a command, using the same rules
+//! and AI as the desktop app. The board is drawn as text with file letters and rank numbers; reading input and
+//! printing is left to the caller.
+
+use crate::game::player::{Player, PlayerMove};
+use crate::game::{AiPlayer, Board, Cell, GamePhase, GameState, MoveHistory, PieceColor, Position, Rules, UndoRedoStack};
+
+pub const HELP: &str = "\
+Enter a move in algebraic notation: a square such as e4 to remove a stone in the opening, or a jump such as f4-d4.
+Commands:
+  moves   list the legal moves
+  undo    take back the last move (and the AI's reply)
+  redo    replay a move that was taken back
+  board   show the board again
+  help    show this help
+  quit    leave the game";
+
+/// Draws a board as text with Black's stones as `B`, White's as `W` and empty squares as `.`, rank 1 at the bottom and
+/// the coordinates around the edge.
+pub fn render_board(board: &Board) -> String {
+    let size = board.size();
+    let files: String = (0..size).map(|col| format!(" {}", (b'a' + col as u8) as char)).collect();
+
+    let mut text = format!("  {}\n", files);
+    for row in (0..size).rev() {
+        text.push_str(&format!("{:>2}", row + 1));
+        for col in 0..size {
+            let symbol = match board.get(Position::new(row, col)) {
+                Some(Cell::Occupied(PieceColor::Black)) => 'B',
+                Some(Cell::Occupied(PieceColor::White)) => 'W',
+                _ => '.',
+            };
+            text.push(' ');
+            text.push(symbol);
+        }
+        text.push_str(&format!(" {}\n", row + 1));
+    }
+    text.push_str(&format!("  {}\n", files));
+    text
+}
+
+/// A game played by typing moves. Sides without an AI are played by the user.
+pub struct TerminalGame {
+    state: GameState,
+    move_history: MoveHistory,
+    undo_stack: UndoRedoStack,
+    redo_stack: UndoRedoStack,
+    black_ai: Option<AiPlayer>,
+    white_ai: Option<AiPlayer>,
+}
+
+impl TerminalGame {
+    pub fn new(board_size: usize) -> Self {
+        Self::from_position(GameState::new(board_size, PieceColor::Black), MoveHistory::new(), UndoRedoStack::new())
+    }
+
+    /// Continues a game, such as one that was imported, from its current position.
+    pub fn from_position(state: GameState, move_history: MoveHistory, undo_stack: UndoRedoStack) -> Self {
+        Self {
+            state,
+            move_history,
+            undo_stack,
+            redo_stack: UndoRedoStack::new(),
+            black_ai: None,
+            white_ai: None,
+        }
+    }
+
+    /// Lets the AI play the side of its color.
+    pub fn with_ai(mut self, ai: AiPlayer) -> Self {
+        match ai.color() {
+            PieceColor::Black => self.black_ai = Some(ai),
+            PieceColor::White => self.white_ai = Some(ai),
+        }
+        self
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn move_history(&self) -> &MoveHistory {
+        &self.move_history
+    }
+
+    pub fn is_over(&self) -> bool {
+        matches!(self.state.current_phase(), GamePhase::GameOver { .. })
+    }
+
+    fn ai_for(&self, color: PieceColor) -> Option<&AiPlayer> {
+        match color {
+            PieceColor::Black => self.black_ai.as_ref(),
+            PieceColor::White => self.white_ai.as_ref(),
+        }
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        !self.is_over() && self.ai_for(self.state.current_player()).is_some()
+    }
+
+    /// Draws the board followed by the status line.
+    pub fn render(&self) -> String {
+        format!("{}{}", render_board(self.state.board()), self.status())
+    }
+
+    /// Describes whose turn it is, or the result.
+    pub fn status(&self) -> String {
+        let player = self.state.current_player();
+        match self.state.current_phase() {
+            GamePhase::GameOver { winner } => format!("Game over: {} wins after {} moves", winner, self.move_history.len()),
+            GamePhase::OpeningBlackRemoval | GamePhase::OpeningWhiteRemoval => {
+                format!("{} to remove a stone", player)
+            }
+            _ => format!("{} to move", player),
+        }
+    }
+
+    /// Lists the legal moves in algebraic notation.
+    pub fn legal_moves(&self) -> Vec<String> {
+        Rules::legal_moves(&self.state).iter().map(PlayerMove::to_algebraic).collect()
+    }
+
+    /// Carries out one line of input: a move or a command. Returns what to show the user.
+    pub fn execute(&mut self, input: &str) -> Result<String, String> {
+        match input.trim().to_lowercase().as_str() {
+            "" => Ok(String::new()),
+            "help" | "?" => Ok(HELP.to_string()),
+            "board" => Ok(self.render()),
+            "moves" => match self.legal_moves() {
+                moves if moves.is_empty() => Ok("No legal moves".to_string()),
+                moves => Ok(moves.join(" ")),
+            },
+            "undo" => self.undo().map(|()| self.render()),
+            "redo" => self.redo().map(|()| self.render()),
+            notation => {
+                if self.is_over() {
+                    return Err("The game is over; undo or quit".to_string());
+                }
+                if self.is_ai_turn() {
+                    return Err(format!("It is the AI's turn as {}", self.state.current_player()));
+                }
+                let player_move = PlayerMove::from_algebraic(&self.state, notation)?;
+                self.play(&player_move)?;
+                Ok(self.render())
+            }
+        }
+    }
+
+    /// Plays AI moves until it is the user's turn or the game ends, returning a line for each move.
+    pub fn play_ai_moves(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        while self.is_ai_turn() {
+            let color = self.state.current_player();
+            let Some(player_move) = self.ai_for(color).and_then(|ai| ai.compute_move(&self.state)) else {
+                break;
+            };
+            match self.play(&player_move) {
+                Ok(()) => lines.push(format!("{} (AI) plays {}", color, player_move.to_algebraic())),
+                Err(err) => {
+                    lines.push(format!("{} (AI) chose an illegal move: {}", color, err));
+                    break;
+                }
+            }
+        }
+        lines
+    }
+
+    fn play(&mut self, player_move: &PlayerMove) -> Result<(), String> {
+        let before = (self.state.clone(), self.move_history.clone());
+        let record = player_move.apply(&mut self.state).map_err(str::to_string)?;
+        self.undo_stack.push(before);
+        self.redo_stack.clear();
+        self.move_history.push(record);
+        Ok(())
+    }
+
+    /// Takes back moves until the user is to move again, so that an AI's reply is undone with the move before it.
+    fn undo(&mut self) -> Result<(), String> {
+        if self.undo_stack.is_empty() {
+            return Err("Nothing to undo".to_string());
+        }
+        while let Some((state, history)) = self.undo_stack.pop() {
+            let current = std::mem::replace(&mut self.state, state);
+            self.redo_stack.push((current, std::mem::replace(&mut self.move_history, history)));
+            if !self.is_ai_turn() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replays moves that were taken back until the user is to move again.
+    fn redo(&mut self) -> Result<(), String> {
+        if self.redo_stack.is_empty() {
+            return Err("Nothing to redo".to_string());
+        }
+        while let Some((state, history)) = self.redo_stack.pop() {
+            let current = std::mem::replace(&mut self.state, state);
+            self.undo_stack.push((current, std::mem::replace(&mut self.move_history, history)));
+            if !self.is_ai_turn() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut TerminalGame, moves: &[&str]) {
+        for notation in moves {
+            game.execute(notation).unwrap();
+        }
+    }
+
+    mod rendering {
+        use super::*;
+
+        #[test]
+        fn draws_the_board_with_coordinates() {
+            let game = TerminalGame::new(4);
+
+            let text = game.render();
+            let lines: Vec<&str> = text.lines().collect();
+
+            assert_eq!(lines[0], "   a b c d");
+            assert_eq!(lines[1], " 4 W B W B 4");
+            assert_eq!(lines[4], " 1 B W B W 1");
+            assert_eq!(lines[5], "   a b c d");
+            assert_eq!(lines[6], "Black to remove a stone");
+        }
+
+        #[test]
+        fn shows_empty_squares() {
+            let mut game = TerminalGame::new(4);
+
+            play(&mut game, &["b2"]);
+
+            assert!(game.render().contains(" 2 W . W B 2"));
+        }
+    }
+
+    mod input {
+        use super::*;
+
+        #[test]
+        fn plays_removals_and_jumps() {
+            let mut game = TerminalGame::new(4);
+
+            play(&mut game, &["b2", "c2", "b4-b2"]);
+
+            assert_eq!(game.move_history().len(), 3);
+            assert_eq!(game.status(), "White to move");
+        }
+
+        #[test]
+        fn lists_legal_moves() {
+            let mut game = TerminalGame::new(4);
+            play(&mut game, &["b2", "c2", "b4-b2"]);
+
+            let moves = game.execute("moves").unwrap();
+
+            assert_eq!(moves, "b1-b3 a2-c2 d3-b3 c4-c2");
+        }
+
+        #[test]
+        fn rejects_illegal_moves() {
+            let mut game = TerminalGame::new(4);
+
+            assert!(game.execute("b1").is_err());
+            assert!(game.execute("b4-b2").is_err());
+            assert!(game.execute("z9").is_err());
+            assert!(game.move_history().is_empty());
+        }
+
+        #[test]
+        fn accepts_commands_in_any_case() {
+            let mut game = TerminalGame::new(4);
+
+            assert!(game.execute("HELP").unwrap().contains("Commands"));
+            assert!(game.execute(" Board ").unwrap().contains("a b c d"));
+        }
+    }
+
+    mod undo_redo {
+        use super::*;
+
+        #[test]
+        fn undoes_and_redoes_moves() {
+            let mut game = TerminalGame::new(4);
+            play(&mut game, &["b2", "c2"]);
+
+            game.execute("undo").unwrap();
+            assert_eq!(game.move_history().len(), 1);
+            game.execute("redo").unwrap();
+            assert_eq!(game.move_history().len(), 2);
+        }
+
+        #[test]
+        fn a_new_move_clears_redo() {
+            let mut game = TerminalGame::new(4);
+            play(&mut game, &["b2", "c2", "undo", "b1"]);
+
+            assert_eq!(game.execute("redo").unwrap_err(), "Nothing to redo");
+        }
+
+        #[test]
+        fn fails_with_nothing_to_undo() {
+            let mut game = TerminalGame::new(4);
+
+            assert_eq!(game.execute("undo").unwrap_err(), "Nothing to undo");
+        }
+    }
+
+    mod ai {
+        use super::*;
+
+        #[test]
+        fn replies_to_the_user() {
+            let mut game = TerminalGame::new(4).with_ai(AiPlayer::new(PieceColor::White, 2));
+            play(&mut game, &["b2"]);
+
+            let lines = game.play_ai_moves();
+
+            assert_eq!(lines.len(), 1);
+            assert!(lines[0].starts_with("White (AI) plays "));
+            assert_eq!(game.state().current_player(), PieceColor::Black);
+        }
+
+        #[test]
+        fn does_not_accept_moves_for_the_ai() {
+            let mut game = TerminalGame::new(4).with_ai(AiPlayer::new(PieceColor::White, 2));
+            play(&mut game, &["b2"]);
+
+            assert!(game.execute("c2").unwrap_err().contains("AI's turn"));
+        }
+
+        #[test]
+        fn undo_takes_back_the_reply_too() {
+            let mut game = TerminalGame::new(4).with_ai(AiPlayer::new(PieceColor::White, 2));
+            play(&mut game, &["b2"]);
+            game.play_ai_moves();
+
+            game.execute("undo").unwrap();
+
+            assert!(game.move_history().is_empty());
+            game.execute("redo").unwrap();
+            assert_eq!(game.move_history().len(), 2);
+        }
+
+        #[test]
+        fn plays_both_sides_to_the_end() {
+            let mut game = TerminalGame::new(4)
+                .with_ai(AiPlayer::new(PieceColor::Black, 1))
+                .with_ai(AiPlayer::new(PieceColor::White, 1));
+
+            game.play_ai_moves();
+
+            assert!(game.is_over());
+            assert!(game.status().starts_with("Game over"));
+        }
+    }
+}
@@ -1,15 +1,29 @@
-use std::time::Duration;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use iced::widget::{Space, button, column, container, row, scrollable, stack, text};
-use iced::{Alignment, Element, Length, Subscription, Task};
-use konane::import;
+use iced::widget::{Space, button, column, container, row, scrollable, stack, text, text_input};
+use iced::{Alignment, Element, Length, Subscription, Task, window};
+use konane::correspondence::{self, CorrespondenceGame};
+use konane::export::ClockLog;
+use konane::import::{self, LenientImport};
+use konane::network::{NetworkEvent, NetworkPlayer, Spectator};
+use konane::session::{self, Session, SessionAiSettings, SessionClock};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
-use crate::game::player::{Player, PlayerMove};
+use crate::game::clock::format_clock;
+use crate::game::player::{ExternalEnginePlayer, Player, PlayerMove};
 use crate::game::rules::Jump;
-use crate::game::{AiPlayer, GamePhase, GameState, MoveHistory, PieceColor, Position, Rules, UndoRedoStack};
+use crate::game::{
+    AiPlayer, GameClock, GamePhase, GameState, MoveHistory, OpeningBook, PieceColor, Ponderer, Position, Rules,
+    SearchReport, TimeControl, UndoRedoStack, Z,
+};
 use crate::ui::board_view::{BoardMessage, BoardView};
 use crate::ui::game_over_view::{ExportFormat, GameOverMessage, GameOverView};
-use crate::ui::setup_view::{PlayerType, SetupMessage, SetupView};
+use crate::ui::setup_view::{PlayerType, SetupMessage, SetupView, backdrop_style, popup_style};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -17,7 +31,121 @@ pub enum Message {
     Board(BoardMessage),
     GameOver(GameOverMessage),
     Tick,
-    AiMoveComputed(Option<PlayerMove>),
+    AiMoveComputed(Option<PlayerMove>, Box<SearchReport>),
+    EngineMoveComputed(Result<PlayerMove, String>),
+    EnginesStarted(Result<StartedEngines, String>),
+    NetworkTick,
+    ClockTick,
+    ToggleDebugPanel,
+    ShowSessionModal(SessionAction),
+    SessionPathChanged(String),
+    CancelSession,
+    ConfirmSession,
+    CloseRequested,
+}
+
+/// The external engines started for a new game, along with the game's time control.
+#[derive(Clone)]
+pub struct StartedEngines {
+    black: Option<Arc<Mutex<ExternalEnginePlayer>>>,
+    white: Option<Arc<Mutex<ExternalEnginePlayer>>>,
+    time_control: Option<TimeControl>,
+}
+
+impl std::fmt::Debug for StartedEngines {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StartedEngines")
+            .field("black", &self.black.is_some())
+            .field("white", &self.white.is_some())
+            .field("time_control", &self.time_control)
+            .finish()
+    }
+}
+
+/// What the save/load dialog in the playing view does with its path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionAction {
+    Save,
+    Load,
+}
+
+/// A correspondence game loaded from a file. The player to move makes one move, which is written back to the file.
+struct Correspondence {
+    path: String,
+    game: CorrespondenceGame,
+    /// Number of moves in the file when it was loaded; the board is locked once a move has been added
+    moves_at_load: usize,
+}
+
+struct SessionModal {
+    action: SessionAction,
+    path: String,
+    error: Option<String>,
+}
+
+/// AI options chosen on the command line.
+#[derive(Clone)]
+pub struct AiSettings {
+    pub depth: i32,
+    pub book: Option<Arc<OpeningBook>>,
+    /// File the book was loaded from, recorded in saved sessions
+    pub book_path: Option<String>,
+    pub book_random: bool,
+    /// File that each search report is appended to as a JSON line
+    pub search_log: Option<String>,
+    pub move_ordering: bool,
+    /// Let the AI think while the human is deciding in Human vs AI games
+    pub ponder: bool,
+}
+
+impl AiSettings {
+    fn create_ai(&self, color: PieceColor, rng: StdRng) -> AiPlayer {
+        let mut ai = AiPlayer::new(color, self.depth).with_move_ordering(self.move_ordering).with_rng(rng);
+        if let Some(ref book) = self.book {
+            ai = ai.with_book(book.clone(), self.book_random);
+        }
+        ai
+    }
+
+    fn to_session(&self) -> SessionAiSettings {
+        SessionAiSettings {
+            depth: self.depth,
+            book: self.book_path.clone(),
+            book_random: self.book_random,
+            move_ordering: self.move_ordering,
+            ponder: self.ponder,
+        }
+    }
+
+    /// Applies the settings of a saved session, loading its opening book if it differs from the current one.
+    fn apply_session(&mut self, saved: &SessionAiSettings) -> Result<(), String> {
+        if saved.book != self.book_path {
+            self.book = match saved.book {
+                Some(ref path) => Some(Arc::new(OpeningBook::load(path)?)),
+                None => None,
+            };
+            self.book_path = saved.book.clone();
+        }
+        self.depth = saved.depth;
+        self.book_random = saved.book_random;
+        self.move_ordering = saved.move_ordering;
+        self.ponder = saved.ponder;
+        Ok(())
+    }
+}
+
+impl Default for AiSettings {
+    fn default() -> Self {
+        Self {
+            depth: 8,
+            book: None,
+            book_path: None,
+            book_random: false,
+            search_log: None,
+            move_ordering: true,
+            ponder: false,
+        }
+    }
 }
 
 pub enum AppView {
@@ -39,7 +167,44 @@ pub struct KonaneApp {
     black_player_type: PlayerType,
     white_player_type: PlayerType,
     ai_computing: bool,
-    ai_depth: i32,
+    ai_settings: AiSettings,
+    /// External engines playing either side, started with the game
+    black_engine: Option<Arc<Mutex<ExternalEnginePlayer>>>,
+    white_engine: Option<Arc<Mutex<ExternalEnginePlayer>>>,
+    /// The opponent in a network game, which plays the other color from the one played here
+    network: Option<NetworkPlayer>,
+    /// The connection to a network game being watched rather than played
+    spectator: Option<Spectator>,
+    correspondence: Option<Correspondence>,
+    /// When the app last tried to reconnect to the game server
+    last_reconnect: Option<Instant>,
+    /// The clocks of a game started with a time control
+    clock: Option<GameClock>,
+    /// Time left on the mover's clock after each move, recorded in exported games
+    clock_times: Vec<Duration>,
+    /// The player whose clock ran out, ending the game
+    lost_on_time: Option<PieceColor>,
+    last_search_report: Option<SearchReport>,
+    /// The search log, opened when the first report is written and kept open
+    search_log: Option<File>,
+    /// Set when the search log could not be written, so that the failure is reported once and not retried
+    search_log_failed: bool,
+    show_debug_panel: bool,
+    /// Background AI used in Human vs AI games when pondering is enabled
+    ponderer: Option<Ponderer>,
+    /// Fingerprint of the position being pondered
+    pondering: Option<Z>,
+    /// Source of all randomness in the app. Each AI gets a generator seeded from it, so a seeded app plays
+    /// reproducible games.
+    rng: StdRng,
+    session_modal: Option<SessionModal>,
+    /// The valid start of a game that failed to import, offered from the import modal
+    partial_import: Option<LenientImport>,
+    /// File the game in progress is saved to after every move and when the window closes
+    autosave_path: Option<PathBuf>,
+    /// Move count, redo count, fingerprint and whether the game was lost on time when last autosaved, to skip
+    /// unchanged saves
+    autosaved: Option<(usize, usize, Z, bool)>,
 }
 
 impl Default for KonaneApp {
@@ -57,17 +222,40 @@ impl Default for KonaneApp {
             black_player_type: PlayerType::Human,
             white_player_type: PlayerType::Human,
             ai_computing: false,
-            ai_depth: 8,
+            ai_settings: AiSettings::default(),
+            black_engine: None,
+            white_engine: None,
+            network: None,
+            spectator: None,
+            correspondence: None,
+            last_reconnect: None,
+            clock: None,
+            clock_times: Vec::new(),
+            lost_on_time: None,
+            last_search_report: None,
+            search_log: None,
+            search_log_failed: false,
+            show_debug_panel: false,
+            ponderer: None,
+            pondering: None,
+            rng: StdRng::from_os_rng(),
+            session_modal: None,
+            partial_import: None,
+            autosave_path: None,
+            autosaved: None,
         }
     }
 }
 
 impl KonaneApp {
-    pub fn new(ai_depth: i32) -> (Self, iced::Task<Message>) {
-        let app = Self {
-            ai_depth,
+    pub fn new(ai_settings: AiSettings, seed: Option<u64>, autosave_path: Option<PathBuf>) -> (Self, iced::Task<Message>) {
+        let mut app = Self {
+            ai_settings,
+            rng: seed.map_or_else(StdRng::from_os_rng, StdRng::seed_from_u64),
+            autosave_path,
             ..Default::default()
         };
+        app.setup.autosave_available = app.autosave_exists();
         (app, iced::Task::none())
     }
 
@@ -76,7 +264,9 @@ impl KonaneApp {
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
-        match message {
+        // A move made after the flag fell is too late, so the clock is checked before the message is handled
+        self.check_flag_fall();
+        let task = match message {
             Message::Setup(msg) => self.handle_setup(msg),
             Message::Board(msg) => self.handle_board(msg),
             Message::GameOver(msg) => self.handle_game_over(msg),
@@ -84,17 +274,79 @@ impl KonaneApp {
                 self.board_view.update_animations();
                 Task::none()
             }
-            Message::AiMoveComputed(maybe_move) => self.handle_ai_move(maybe_move),
-        }
+            Message::AiMoveComputed(maybe_move, report) => {
+                let task = self.handle_ai_move(maybe_move);
+                // After the move, so that a failure to log the report is not replaced by the status of the move
+                self.record_search_report(*report);
+                task
+            }
+            Message::EngineMoveComputed(Ok(player_move)) => self.handle_ai_move(Some(player_move)),
+            Message::EnginesStarted(result) => self.handle_engines_started(result),
+            Message::EngineMoveComputed(Err(error)) => {
+                self.ai_computing = false;
+                self.status_message = error;
+                Task::none()
+            }
+            Message::NetworkTick => self.handle_network_tick(),
+            // The flag has been checked above; the tick also redraws the clocks
+            Message::ClockTick => Task::none(),
+            Message::ToggleDebugPanel => {
+                self.show_debug_panel = !self.show_debug_panel;
+                Task::none()
+            }
+            Message::ShowSessionModal(action) => {
+                let path = match action {
+                    SessionAction::Save => "konane_session.json".to_string(),
+                    SessionAction::Load => String::new(),
+                };
+                self.session_modal = Some(SessionModal {
+                    action,
+                    path,
+                    error: None,
+                });
+                Task::none()
+            }
+            Message::SessionPathChanged(path) => {
+                if let Some(ref mut modal) = self.session_modal {
+                    modal.path = path;
+                    modal.error = None;
+                }
+                Task::none()
+            }
+            Message::CancelSession => {
+                self.session_modal = None;
+                Task::none()
+            }
+            Message::ConfirmSession => self.handle_confirm_session(),
+            Message::CloseRequested => {
+                self.autosave();
+                return iced::exit();
+            }
+        };
+        self.autosave();
+        task
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
         // Only subscribe to ticks when there are animations running
-        if self.board_view.has_animations() {
+        let ticks = if self.board_view.has_animations() {
             iced::time::every(Duration::from_millis(16)).map(|_| Message::Tick)
         } else {
             Subscription::none()
-        }
+        };
+        // Poll the game server while playing or watching a network game
+        let network = if self.network.is_some() || self.spectator.is_some() {
+            iced::time::every(Duration::from_millis(100)).map(|_| Message::NetworkTick)
+        } else {
+            Subscription::none()
+        };
+        // Count down the running clock and notice when its flag falls
+        let clock = if self.clock.as_ref().is_some_and(|clock| clock.running().is_some()) {
+            iced::time::every(Duration::from_millis(100)).map(|_| Message::ClockTick)
+        } else {
+            Subscription::none()
+        };
+        Subscription::batch([ticks, network, clock, window::close_requests().map(|_| Message::CloseRequested)])
     }
 
     fn handle_setup(&mut self, msg: SetupMessage) -> Task<Message> {
@@ -105,58 +357,120 @@ impl KonaneApp {
             SetupMessage::ColorOptionSelected(option) => {
                 self.setup.color_option = option;
             }
-            SetupMessage::BlackPlayerTypeSelected(player_type) => {
-                self.setup.black_player_type = player_type;
+            SetupMessage::BlackPlayerTypeSelected(choice) => {
+                self.setup.black_player_type = choice;
+                self.setup.start_error = None;
+            }
+            SetupMessage::WhitePlayerTypeSelected(choice) => {
+                self.setup.white_player_type = choice;
+                self.setup.start_error = None;
             }
-            SetupMessage::WhitePlayerTypeSelected(player_type) => {
-                self.setup.white_player_type = player_type;
+            SetupMessage::BlackEngineChanged(command) => {
+                self.setup.black_engine = command;
+                self.setup.start_error = None;
+            }
+            SetupMessage::WhiteEngineChanged(command) => {
+                self.setup.white_engine = command;
+                self.setup.start_error = None;
+            }
+            SetupMessage::TimeControlChanged(time_control) => {
+                self.setup.time_control = time_control;
+                self.setup.start_error = None;
             }
             SetupMessage::StartGame => {
-                let first_player = self.setup.color_option.to_piece_color();
-                self.game_state = Some(GameState::new(self.setup.board_size, first_player));
-                self.board_view = BoardView::default();
-                self.move_history.clear();
-                self.undo_stack.clear();
-                self.redo_stack.clear();
-                self.black_player_type = self.setup.black_player_type;
-                self.white_player_type = self.setup.white_player_type;
-                self.ai_computing = false;
-                self.view = AppView::Playing;
-                self.update_status();
-                return self.maybe_trigger_ai_move();
+                if self.setup.starting_engines {
+                    return Task::none();
+                }
+                let time_control = match self.setup.time_control() {
+                    Ok(time_control) => time_control,
+                    Err(error) => {
+                        self.setup.start_error = Some(error);
+                        return Task::none();
+                    }
+                };
+                let players = self
+                    .setup
+                    .player_type(PieceColor::Black)
+                    .and_then(|black| Ok((black, self.setup.player_type(PieceColor::White)?)));
+                let (black, white) = match players {
+                    Ok(players) => players,
+                    Err(error) => {
+                        self.setup.start_error = Some(error);
+                        return Task::none();
+                    }
+                };
+                self.network = None;
+                self.spectator = None;
+                self.correspondence = None;
+                self.black_player_type = black;
+                self.white_player_type = white;
+                self.setup.start_error = None;
+                return self.start_engines(time_control);
             }
             SetupMessage::ShowImportModal => {
                 self.setup.show_import_modal = true;
                 self.setup.import_path.clear();
-                self.setup.import_error = None;
+                self.clear_import_error();
             }
             SetupMessage::ImportPathChanged(path) => {
                 self.setup.import_path = path;
-                self.setup.import_error = None;
+                self.clear_import_error();
             }
             SetupMessage::CancelImport => {
                 self.setup.show_import_modal = false;
                 self.setup.import_path.clear();
-                self.setup.import_error = None;
+                self.clear_import_error();
             }
             SetupMessage::ImportGame => {
                 let path = self.setup.import_path.clone();
-                match import::import_game_from_path(&path) {
-                    Ok((state, move_history, undo_stack)) => {
-                        self.game_state = Some(state);
-                        self.board_view = BoardView::default();
-                        self.move_history = move_history;
-                        self.undo_stack = undo_stack;
-                        self.redo_stack.clear();
-                        self.view = AppView::Playing;
-                        self.update_status();
+                match self.load_game(&path) {
+                    Ok(task) => {
                         self.setup.show_import_modal = false;
                         self.setup.import_path.clear();
-                        self.setup.import_error = None;
+                        self.clear_import_error();
+                        return task;
+                    }
+                    Err(error) => {
+                        self.setup.show_import_modal = true;
+                        self.offer_partial_import(&path, error);
                     }
+                }
+            }
+            SetupMessage::ImportPartial => {
+                if let Some(partial) = self.partial_import.take() {
+                    self.setup.show_import_modal = false;
+                    self.setup.import_path.clear();
+                    self.clear_import_error();
+                    return self.start_loaded_game(
+                        partial.state,
+                        partial.move_history,
+                        partial.undo_stack,
+                        Vec::new(),
+                        None,
+                    );
+                }
+            }
+            SetupMessage::NetworkAddressChanged(address) => {
+                self.setup.network_address = address;
+                self.setup.start_error = None;
+            }
+            SetupMessage::JoinNetworkGame => match NetworkPlayer::connect(self.setup.network_address.trim(), None) {
+                Ok(network) => return self.start_network_game(network),
+                Err(error) => self.setup.start_error = Some(error),
+            },
+            SetupMessage::WatchNetworkGame => match Spectator::connect(self.setup.network_address.trim()) {
+                Ok(spectator) => return self.start_watching(spectator),
+                Err(error) => self.setup.start_error = Some(error),
+            },
+            SetupMessage::ResumeAutosave => {
+                let Some(path) = self.autosave_path.clone() else {
+                    return Task::none();
+                };
+                match self.load_game(&path.to_string_lossy()) {
+                    Ok(task) => return task,
                     Err(error) => {
                         self.setup.show_import_modal = true;
-                        self.setup.import_error = Some(error);
+                        self.setup.import_error = Some(format!("Could not resume the autosaved game: {}", error));
                     }
                 }
             }
@@ -165,7 +479,8 @@ impl KonaneApp {
     }
 
     fn handle_board(&mut self, msg: BoardMessage) -> Task<Message> {
-        if self.game_state.is_none() {
+        // A spectator only watches, and nobody moves once a flag has fallen
+        if self.game_state.is_none() || self.spectator.is_some() || self.lost_on_time.is_some() {
             return Task::none();
         }
 
@@ -177,6 +492,23 @@ impl KonaneApp {
             }
         }
 
+        // In a network game the server keeps the moves, so they cannot be taken back, and the opponent's moves
+        // come from the server
+        if self.network.is_some() && (matches!(msg, BoardMessage::Undo | BoardMessage::Redo) || self.is_remote_turn()) {
+            return Task::none();
+        }
+        // Taking back a move would not give back the time spent on it
+        if self.clock.is_some() && matches!(msg, BoardMessage::Undo | BoardMessage::Redo) {
+            return Task::none();
+        }
+        // In a correspondence game the player to move makes one move, then the file goes to the other player
+        if let Some(ref correspondence) = self.correspondence
+            && (self.move_history.len() > correspondence.moves_at_load || matches!(msg, BoardMessage::Undo | BoardMessage::Redo))
+        {
+            return Task::none();
+        }
+        let moves_before = self.move_history.len();
+
         match msg {
             BoardMessage::CellClicked(pos) => {
                 self.handle_cell_click(pos);
@@ -192,12 +524,20 @@ impl KonaneApp {
             }
         }
 
-        // Check for game over
-        if let Some(ref state) = self.game_state
-            && let GamePhase::GameOver { winner } = state.current_phase()
+        if self.move_history.len() > moves_before
+            && let (Some(network), Some(record)) = (&mut self.network, self.move_history.last())
+            && let Err(error) = network.send_move(record)
         {
-            self.game_over_view = Some(GameOverView::new(winner, self.move_history.clone(), state.board().size()));
-            self.view = AppView::GameOver;
+            self.status_message = error;
+        }
+        if self.move_history.len() > moves_before && self.correspondence.is_some() {
+            self.send_correspondence_move();
+        }
+        if self.move_history.len() > moves_before {
+            self.press_clock();
+        }
+
+        if self.show_game_over_if_finished() {
             return Task::none();
         }
 
@@ -214,6 +554,7 @@ impl KonaneApp {
             GamePhase::OpeningBlackRemoval => {
                 let valid = Rules::valid_black_opening_removals(state);
                 if valid.contains(&pos) {
+                    self.stop_pondering();
                     self.save_state_for_undo();
                     let state = self.game_state.as_mut().unwrap();
                     let color = state.board().get_piece_color(pos).unwrap_or(PieceColor::Black);
@@ -228,6 +569,7 @@ impl KonaneApp {
             GamePhase::OpeningWhiteRemoval => {
                 let valid = Rules::valid_white_opening_removals(state);
                 if valid.contains(&pos) {
+                    self.stop_pondering();
                     self.save_state_for_undo();
                     let state = self.game_state.as_mut().unwrap();
                     let color = state.board().get_piece_color(pos).unwrap_or(PieceColor::White);
@@ -265,6 +607,7 @@ impl KonaneApp {
             .filter_map(|&pos| state.board().get_piece_color(pos).map(|color| (pos, color)))
             .collect();
 
+        self.stop_pondering();
         self.save_state_for_undo();
         let state = self.game_state.as_mut().unwrap();
 
@@ -284,6 +627,16 @@ impl KonaneApp {
     fn handle_game_over(&mut self, msg: GameOverMessage) -> Task<Message> {
         match msg {
             GameOverMessage::Dismiss => {
+                self.ponderer = None;
+                self.network = None;
+                self.spectator = None;
+                self.correspondence = None;
+                self.black_engine = None;
+                self.white_engine = None;
+                self.pondering = None;
+                self.clock = None;
+                self.lost_on_time = None;
+                self.setup.autosave_available = self.autosave_exists();
                 self.view = AppView::Setup;
                 self.game_state = None;
                 self.game_over_view = None;
@@ -314,6 +667,7 @@ impl KonaneApp {
                     let content = match view.export_format {
                         Some(ExportFormat::Text) => view.generate_text_log(),
                         Some(ExportFormat::Json) => view.generate_json_log(),
+                        Some(ExportFormat::Pgn) => view.generate_pgn_log(),
                         None => return Task::none(),
                     };
                     let _ = std::fs::write(&path, content);
@@ -326,6 +680,229 @@ impl KonaneApp {
         Task::none()
     }
 
+    /// Switches to the game over view if the game has ended. Returns whether it has.
+    fn show_game_over_if_finished(&mut self) -> bool {
+        let Some(ref state) = self.game_state else {
+            return false;
+        };
+        let GamePhase::GameOver { winner } = state.current_phase() else {
+            return false;
+        };
+        self.show_game_over(winner);
+        true
+    }
+
+    fn show_game_over(&mut self, winner: PieceColor) {
+        let Some(ref state) = self.game_state else {
+            return;
+        };
+        let mut game_over = GameOverView::new(winner, self.move_history.clone(), state.board().size())
+            .with_players(self.player_name(PieceColor::Black), self.player_name(PieceColor::White));
+        if let Some(ref clock) = self.clock {
+            game_over = game_over.with_clock(ClockLog {
+                time_control: clock.control(),
+                times: self.clock_times.clone(),
+                lost_on_time: self.lost_on_time,
+            });
+        }
+        self.game_over_view = Some(game_over);
+        self.view = AppView::GameOver;
+    }
+
+    /// Stops the clock of the player who just moved, recording their time, and starts the opponent's.
+    fn press_clock(&mut self) {
+        let (Some(clock), Some(state)) = (&mut self.clock, &self.game_state) else {
+            return;
+        };
+        let now = Instant::now();
+        self.clock_times.extend(clock.stop(now));
+        if !matches!(state.current_phase(), GamePhase::GameOver { .. }) {
+            clock.start(state.current_player(), now);
+        }
+    }
+
+    /// Ends the game if the clock of the player to move has run out.
+    fn check_flag_fall(&mut self) {
+        let now = Instant::now();
+        let Some(loser) = self.clock.as_ref().and_then(|clock| clock.flag_fall(now)) else {
+            return;
+        };
+        if let Some(ref mut clock) = self.clock {
+            clock.stop(now);
+        }
+        self.lost_on_time = Some(loser);
+        self.stop_pondering();
+        self.board_view.clear_selection();
+        self.update_status();
+        self.show_game_over(loser.opposite());
+    }
+
+    fn handle_confirm_session(&mut self) -> Task<Message> {
+        let Some(ref modal) = self.session_modal else {
+            return Task::none();
+        };
+        let path = modal.path.clone();
+        let result = match modal.action {
+            SessionAction::Save => match self.current_session() {
+                Some(session) => session.save(&path).map(|_| Task::none()),
+                None => Ok(Task::none()),
+            },
+            SessionAction::Load => self.load_game(&path),
+        };
+        match result {
+            Ok(task) => {
+                self.session_modal = None;
+                task
+            }
+            Err(error) => {
+                if let Some(ref mut modal) = self.session_modal {
+                    modal.error = Some(error);
+                }
+                Task::none()
+            }
+        }
+    }
+
+    /// Captures the game in progress, its history and the players for saving.
+    fn current_session(&self) -> Option<Session> {
+        Some(Session {
+            black_player: self.black_player_type.clone(),
+            white_player: self.white_player_type.clone(),
+            ai: self.ai_settings.to_session(),
+            state: self.game_state.clone()?,
+            move_history: self.move_history.clone(),
+            undo_stack: self.undo_stack.clone(),
+            redo_stack: self.redo_stack.clone(),
+            clock: self.clock.as_ref().map(|clock| {
+                let now = Instant::now();
+                SessionClock {
+                    time_control: clock.control(),
+                    black: clock.remaining(PieceColor::Black, now),
+                    white: clock.remaining(PieceColor::White, now),
+                    times: self.clock_times.clone(),
+                }
+            }),
+        })
+    }
+
+    /// Loads a saved session, or a game in any import format, and continues playing it.
+    fn load_game(&mut self, path: &str) -> Result<Task<Message>, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| format!("Failed to read file: {}", err))?;
+        if correspondence::is_correspondence(&content) {
+            return self.start_correspondence(path, CorrespondenceGame::from_json(&content)?);
+        }
+        let (state, move_history, undo_stack, redo_stack, clock) = if session::is_session(&content) {
+            let session = Session::from_json(&content)?;
+            self.ai_settings.apply_session(&session.ai)?;
+            // A session file could name any program, so external engines are only started from the setup view
+            self.black_player_type = without_engine(session.black_player);
+            self.white_player_type = without_engine(session.white_player);
+            self.black_engine = None;
+            self.white_engine = None;
+            self.network = None;
+            self.spectator = None;
+            self.correspondence = None;
+            (session.state, session.move_history, session.undo_stack, session.redo_stack, session.clock)
+        } else {
+            let (state, move_history, undo_stack) = import::import_game_from_path(path)?;
+            self.network = None;
+            self.spectator = None;
+            self.correspondence = None;
+            (state, move_history, undo_stack, UndoRedoStack::new(), None)
+        };
+        Ok(self.start_loaded_game(state, move_history, undo_stack, redo_stack, clock))
+    }
+
+    /// Shows why a game failed to import and, if part of it can be played, offers to load that part.
+    fn offer_partial_import(&mut self, path: &str, error: String) {
+        self.partial_import = None;
+        self.setup.partial_import = None;
+        self.setup.import_error = Some(error);
+
+        let Ok(partial) = import::import_game_lenient_from_path(path) else {
+            return;
+        };
+        if partial.is_complete() {
+            return;
+        }
+        let mut details: Vec<String> = partial.error.iter().map(ToString::to_string).collect();
+        details.extend(partial.warnings.iter().cloned());
+        self.setup.import_error = Some(details.join("\n"));
+        self.setup.partial_import = Some((partial.move_history.len(), partial.total_moves));
+        self.partial_import = Some(partial);
+    }
+
+    fn clear_import_error(&mut self) {
+        self.setup.import_error = None;
+        self.setup.partial_import = None;
+        self.partial_import = None;
+    }
+
+    /// Continues playing a loaded game from its current position. A session saved from a timed game restores its
+    /// clocks, and the clock of the player to move starts again.
+    fn start_loaded_game(
+        &mut self,
+        state: GameState,
+        move_history: MoveHistory,
+        undo_stack: UndoRedoStack,
+        redo_stack: UndoRedoStack,
+        clock: Option<SessionClock>,
+    ) -> Task<Message> {
+        self.game_state = Some(state);
+        self.board_view = BoardView::default();
+        self.game_over_view = None;
+        self.move_history = move_history;
+        self.undo_stack = undo_stack;
+        self.redo_stack = redo_stack;
+        self.ai_computing = false;
+        self.last_search_report = None;
+        self.ponderer = self.create_ponderer();
+        self.pondering = None;
+        self.clock_times.clear();
+        self.clock = clock.map(|saved| {
+            self.clock_times = saved.times;
+            GameClock::new(saved.time_control).with_remaining(saved.black, saved.white)
+        });
+        self.lost_on_time = None;
+        if let (Some(clock), Some(state)) = (&mut self.clock, &self.game_state)
+            && !matches!(state.current_phase(), GamePhase::GameOver { .. })
+        {
+            clock.start(state.current_player(), Instant::now());
+        }
+        self.view = AppView::Playing;
+        self.update_status();
+        if self.show_game_over_if_finished() {
+            return Task::none();
+        }
+        self.maybe_trigger_ai_move()
+    }
+
+    /// Saves the game in progress to the autosave file if it has changed. A finished game removes the file, since
+    /// there is nothing left to resume. Network and correspondence games are not autosaved: resuming one as a local
+    /// game would lose the connection or the file it is played through.
+    fn autosave(&mut self) {
+        if self.is_network_game() || self.correspondence.is_some() {
+            return;
+        }
+        let (Some(path), Some(state)) = (&self.autosave_path, &self.game_state) else {
+            return;
+        };
+        let key = (self.move_history.len(), self.redo_stack.len(), state.fingerprint(), self.lost_on_time.is_some());
+        if self.autosaved == Some(key) {
+            return;
+        }
+        if matches!(state.current_phase(), GamePhase::GameOver { .. }) || self.lost_on_time.is_some() {
+            let _ = std::fs::remove_file(path);
+        } else if let Some(session) = self.current_session() {
+            let _ = session.save(path);
+        }
+        self.autosaved = Some(key);
+    }
+
+    fn autosave_exists(&self) -> bool {
+        self.autosave_path.as_ref().is_some_and(|path| path.exists())
+    }
+
     fn save_state_for_undo(&mut self) {
         if let Some(ref state) = self.game_state {
             self.undo_stack.push((state.clone(), self.move_history.clone()));
@@ -360,20 +937,36 @@ impl KonaneApp {
     }
 
     fn can_undo(&self) -> bool {
-        !self.undo_stack.is_empty()
+        !self.is_network_game() && self.correspondence.is_none() && self.clock.is_none() && !self.undo_stack.is_empty()
     }
 
     fn can_redo(&self) -> bool {
-        !self.redo_stack.is_empty()
+        !self.is_network_game() && self.correspondence.is_none() && self.clock.is_none() && !self.redo_stack.is_empty()
+    }
+
+    /// True if the game is played or watched through a game server, which keeps the moves.
+    fn is_network_game(&self) -> bool {
+        self.network.is_some() || self.spectator.is_some()
     }
 
     fn update_status(&mut self) {
         let Some(ref state) = self.game_state else {
             return;
         };
+        if let Some(loser) = self.lost_on_time {
+            self.status_message = format!("{} ran out of time - {} wins!", loser, loser.opposite());
+            return;
+        }
 
-        let is_ai = self.is_current_player_ai();
-        let ai_suffix = if is_ai { " (AI)" } else { "" };
+        let ai_suffix = if self.is_remote_turn() {
+            " (opponent)".to_string()
+        } else if self.is_current_player_ai() {
+            " (AI)".to_string()
+        } else if let Some(ref correspondence) = self.correspondence {
+            format!(" ({})", correspondence.game.name(state.current_player()))
+        } else {
+            String::new()
+        };
 
         self.status_message = match state.current_phase() {
             GamePhase::OpeningBlackRemoval => {
@@ -394,16 +987,324 @@ impl KonaneApp {
             }
             _ => String::new(),
         };
+        if self.spectator.is_some() {
+            self.status_message = format!("Watching - {}", self.status_message);
+        }
     }
 
+    /// Describes a player for game records.
+    fn player_name(&self, color: PieceColor) -> String {
+        if let Some(ref correspondence) = self.correspondence {
+            return correspondence.game.name(color).to_string();
+        }
+        match self.player_type(color) {
+            PlayerType::Human if self.network.as_ref().is_some_and(|network| network.color() == color) => {
+                "Network opponent".to_string()
+            }
+            PlayerType::Human => "Human".to_string(),
+            PlayerType::Ai => format!("AI (depth {})", self.ai_settings.depth),
+            PlayerType::External(command) => match self.engine(color).and_then(|engine| engine.lock().ok()) {
+                Some(engine) => engine.name().to_string(),
+                None => command.clone(),
+            },
+        }
+    }
+
+    fn player_type(&self, color: PieceColor) -> &PlayerType {
+        match color {
+            PieceColor::Black => &self.black_player_type,
+            PieceColor::White => &self.white_player_type,
+        }
+    }
+
+    fn engine(&self, color: PieceColor) -> Option<&Arc<Mutex<ExternalEnginePlayer>>> {
+        match color {
+            PieceColor::Black => self.black_engine.as_ref(),
+            PieceColor::White => self.white_engine.as_ref(),
+        }
+    }
+
+    /// Sets up a new game from the setup view once its players are ready.
+    fn begin_game(&mut self, time_control: Option<TimeControl>) -> Task<Message> {
+        let first_player = self.setup.color_option.to_piece_color(&mut self.rng);
+        self.game_state = Some(GameState::new(self.setup.board_size, first_player));
+        self.board_view = BoardView::default();
+        self.move_history.clear();
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.ai_computing = false;
+        self.last_search_report = None;
+        self.ponderer = self.create_ponderer();
+        self.pondering = None;
+        self.clock = time_control.map(GameClock::new);
+        self.clock_times.clear();
+        self.lost_on_time = None;
+        // The game decides who moves first, whichever color was picked
+        if let (Some(clock), Some(state)) = (&mut self.clock, &self.game_state) {
+            clock.start(state.current_player(), Instant::now());
+        }
+        self.view = AppView::Playing;
+        self.update_status();
+        self.maybe_trigger_ai_move()
+    }
+
+    /// Starts the external engines chosen for the new game, replacing those of the last game, and then the game.
+    /// An engine can take a while to answer, so they are started in the background and the game begins when
+    /// `Message::EnginesStarted` arrives.
+    fn start_engines(&mut self, time_control: Option<TimeControl>) -> Task<Message> {
+        self.black_engine = None;
+        self.white_engine = None;
+        let command = |player_type: &PlayerType| match player_type {
+            PlayerType::External(command) => Some(command.clone()),
+            _ => None,
+        };
+        let (black, white) = (command(&self.black_player_type), command(&self.white_player_type));
+        if black.is_none() && white.is_none() {
+            return self.begin_game(time_control);
+        }
+
+        self.setup.starting_engines = true;
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || -> Result<StartedEngines, String> {
+                    let spawn = |color, command: Option<String>| {
+                        command
+                            .map(|command| ExternalEnginePlayer::spawn(color, &command))
+                            .transpose()
+                            .map(|engine| engine.map(|engine| Arc::new(Mutex::new(engine))))
+                    };
+                    Ok(StartedEngines {
+                        black: spawn(PieceColor::Black, black)?,
+                        white: spawn(PieceColor::White, white)?,
+                        time_control,
+                    })
+                })
+                .await
+                .unwrap_or_else(|err| Err(err.to_string()))
+            },
+            Message::EnginesStarted,
+        )
+    }
+
+    /// Begins the game the engines were started for, unless another game was started or loaded in the meantime.
+    fn handle_engines_started(&mut self, result: Result<StartedEngines, String>) -> Task<Message> {
+        if !std::mem::take(&mut self.setup.starting_engines) || !matches!(self.view, AppView::Setup) {
+            return Task::none();
+        }
+        match result {
+            Ok(engines) => {
+                self.black_engine = engines.black;
+                self.white_engine = engines.white;
+                self.begin_game(engines.time_control)
+            }
+            Err(error) => {
+                self.setup.start_error = Some(error);
+                Task::none()
+            }
+        }
+    }
+
+    /// True if the player to move is the AI or an external engine.
     fn is_current_player_ai(&self) -> bool {
         let Some(ref state) = self.game_state else {
             return false;
         };
-        match state.current_player() {
-            PieceColor::Black => self.black_player_type == PlayerType::Ai,
-            PieceColor::White => self.white_player_type == PlayerType::Ai,
+        !matches!(self.player_type(state.current_player()), PlayerType::Human)
+    }
+
+    /// True if the player to move is the opponent in a network game.
+    fn is_remote_turn(&self) -> bool {
+        match (&self.network, &self.game_state) {
+            (Some(network), Some(state)) => network.color() == state.current_player(),
+            _ => false,
+        }
+    }
+
+    /// Plays a game joined on a server, with a human on each side: the one here and the one across the network.
+    fn start_network_game(&mut self, network: NetworkPlayer) -> Task<Message> {
+        let (state, move_history, undo_stack) = match network.game() {
+            Ok(game) => game,
+            Err(error) => {
+                self.setup.start_error = Some(error);
+                return Task::none();
+            }
+        };
+        self.black_player_type = PlayerType::Human;
+        self.white_player_type = PlayerType::Human;
+        self.black_engine = None;
+        self.white_engine = None;
+        self.network = Some(network);
+        self.spectator = None;
+        self.correspondence = None;
+        self.last_reconnect = None;
+        self.setup.start_error = None;
+        self.start_loaded_game(state, move_history, undo_stack, UndoRedoStack::new(), None)
+    }
+
+    /// Continues a correspondence game, with the player to move at the board.
+    fn start_correspondence(&mut self, path: &str, game: CorrespondenceGame) -> Result<Task<Message>, String> {
+        let (state, move_history) = (game.state().clone(), game.move_history().clone());
+        self.black_player_type = PlayerType::Human;
+        self.white_player_type = PlayerType::Human;
+        self.black_engine = None;
+        self.white_engine = None;
+        self.network = None;
+        self.spectator = None;
+        self.correspondence = Some(Correspondence {
+            path: path.to_string(),
+            moves_at_load: move_history.len(),
+            game,
+        });
+        Ok(self.start_loaded_game(state, move_history, UndoRedoStack::new(), UndoRedoStack::new(), None))
+    }
+
+    /// Signs the move just made in a correspondence game and writes the file back. The move is signed on a copy of
+    /// the game, which replaces it only once the file is written; if writing fails, the move is taken back on the
+    /// board so it can be made again.
+    fn send_correspondence_move(&mut self) {
+        let (Some(correspondence), Some(record)) = (&mut self.correspondence, self.move_history.last()) else {
+            return;
+        };
+        let mut game = correspondence.game.clone();
+        let player = game.player_to_move().unwrap_or_default().to_string();
+        let result = PlayerMove::from_record(game.state(), record)
+            .and_then(|player_move| game.play(&player, &player_move))
+            .and_then(|_| game.save(&correspondence.path));
+        if let Err(error) = result {
+            self.handle_undo();
+            self.redo_stack.clear();
+            self.status_message = format!("Could not save the move: {}. Make the move again to retry.", error);
+            return;
+        }
+        self.status_message = match game.player_to_move() {
+            Some(next) => format!("Saved your move to {}. Send it to {}.", correspondence.path, next),
+            None => format!("Saved the final move to {}.", correspondence.path),
+        };
+        correspondence.game = game;
+    }
+
+    /// Shows a game being played on a server, following it as moves are made.
+    fn start_watching(&mut self, spectator: Spectator) -> Task<Message> {
+        let (state, move_history, undo_stack) = match spectator.game() {
+            Ok(game) => game,
+            Err(error) => {
+                self.setup.start_error = Some(error);
+                return Task::none();
+            }
+        };
+        self.black_player_type = PlayerType::Human;
+        self.white_player_type = PlayerType::Human;
+        self.black_engine = None;
+        self.white_engine = None;
+        self.network = None;
+        self.spectator = Some(spectator);
+        self.correspondence = None;
+        self.last_reconnect = None;
+        self.setup.start_error = None;
+        self.start_loaded_game(state, move_history, undo_stack, UndoRedoStack::new(), None)
+    }
+
+    /// Plays the opponent's moves from the server, follows the server's game when it differs from this one, and
+    /// reconnects if the connection was lost.
+    fn handle_network_tick(&mut self) -> Task<Message> {
+        if self.spectator.is_some() {
+            return self.handle_spectator_tick();
+        }
+        let Some(ref mut network) = self.network else {
+            return Task::none();
+        };
+        if !network.is_connected() && self.last_reconnect.is_none_or(|last| last.elapsed() >= Duration::from_secs(2)) {
+            self.last_reconnect = Some(Instant::now());
+            if network.reconnect().is_err() {
+                self.status_message = "Connection to the server lost, reconnecting...".to_string();
+                return Task::none();
+            }
+        }
+
+        let mut synced = None;
+        for event in network.take_events() {
+            match event {
+                NetworkEvent::Synced(moves) => synced = Some(moves),
+                NetworkEvent::Rejected(reason) => self.status_message = reason,
+                NetworkEvent::OpponentJoined => self.status_message = "Your opponent joined".to_string(),
+                NetworkEvent::OpponentLeft => self.status_message = "Your opponent left".to_string(),
+                NetworkEvent::Disconnected => {
+                    self.status_message = "Connection to the server lost, reconnecting...".to_string();
+                }
+            }
+        }
+        if synced.is_some_and(|moves| moves != self.move_history) {
+            let Ok((state, move_history, undo_stack)) = network.game() else {
+                return Task::none();
+            };
+            self.game_state = Some(state);
+            self.move_history = move_history;
+            self.undo_stack = undo_stack;
+            self.redo_stack.clear();
+            self.board_view = BoardView::default();
+            self.update_status();
+            if self.show_game_over_if_finished() {
+                return Task::none();
+            }
+        }
+
+        let Some(ref state) = self.game_state else {
+            return Task::none();
+        };
+        if matches!(self.view, AppView::Playing)
+            && let Some(network) = self.network.as_mut()
+            && let Some(player_move) = network.request_move(state)
+        {
+            return self.handle_ai_move(Some(player_move));
+        }
+        Task::none()
+    }
+
+    /// Shows the moves played since the last tick, or the whole game again if this one no longer matches it.
+    fn handle_spectator_tick(&mut self) -> Task<Message> {
+        let Some(ref mut spectator) = self.spectator else {
+            return Task::none();
+        };
+        if !spectator.is_connected() && self.last_reconnect.is_none_or(|last| last.elapsed() >= Duration::from_secs(2)) {
+            self.last_reconnect = Some(Instant::now());
+            if spectator.reconnect().is_err() {
+                self.status_message = "Connection to the server lost, reconnecting...".to_string();
+                return Task::none();
+            }
+        }
+        if spectator.take_events().contains(&NetworkEvent::Disconnected) {
+            self.status_message = "Connection to the server lost, reconnecting...".to_string();
+        }
+
+        let moves = spectator.moves();
+        if moves.len() > self.move_history.len() && moves.starts_with(&self.move_history) {
+            // Play the new moves one at a time, so that each is animated
+            let new_moves = moves[self.move_history.len()..].to_vec();
+            let mut task = Task::none();
+            for record in new_moves {
+                let Some(ref state) = self.game_state else {
+                    break;
+                };
+                let Ok(player_move) = PlayerMove::from_record(state, &record) else {
+                    break;
+                };
+                task = self.handle_ai_move(Some(player_move));
+            }
+            return task;
         }
+        if *moves != self.move_history
+            && let Ok((state, move_history, undo_stack)) = spectator.game()
+        {
+            self.game_state = Some(state);
+            self.move_history = move_history;
+            self.undo_stack = undo_stack;
+            self.board_view = BoardView::default();
+            self.view = AppView::Playing;
+            self.game_over_view = None;
+            self.update_status();
+            self.show_game_over_if_finished();
+        }
+        Task::none()
     }
 
     fn maybe_trigger_ai_move(&mut self) -> Task<Message> {
@@ -415,36 +1316,148 @@ impl KonaneApp {
             return Task::none();
         };
 
-        if matches!(state.current_phase(), GamePhase::GameOver { .. }) {
+        if matches!(state.current_phase(), GamePhase::GameOver { .. }) || self.lost_on_time.is_some() {
             return Task::none();
         }
 
         if !self.is_current_player_ai() {
+            self.maybe_start_pondering();
             return Task::none();
         }
 
-        let state_clone = state.clone();
-        let depth = self.ai_depth;
-
         self.ai_computing = true;
+        self.pondering = None;
         self.update_status();
 
+        let state_clone = self.game_state.clone().unwrap();
+        // In a timed game the AI's budget for the move comes from what is left on its clock
+        let move_time = self.clock.as_ref().map(|clock| clock.think_time(state_clone.current_player(), Instant::now()));
+        if let PlayerType::External(command) = self.player_type(state_clone.current_player()).clone() {
+            let Some(engine) = self.engine(state_clone.current_player()).cloned() else {
+                self.ai_computing = false;
+                self.status_message = format!("Engine {} is not running", command);
+                return Task::none();
+            };
+            return Task::perform(
+                async move {
+                    tokio::task::spawn_blocking(move || match engine.lock() {
+                        Ok(mut engine) => {
+                            engine.set_move_time(move_time);
+                            engine.choose_move(&state_clone)
+                        }
+                        Err(_) => Err("The engine failed".to_string()),
+                    })
+                    .await
+                    .unwrap_or_else(|err| Err(err.to_string()))
+                },
+                Message::EngineMoveComputed,
+            );
+        }
+
+        let on_result = |(maybe_move, report)| Message::AiMoveComputed(maybe_move, Box::new(report));
+
+        if let Some(ref ponderer) = self.ponderer {
+            let result = match move_time {
+                Some(move_time) => ponderer.search_with_time_limit(&state_clone, move_time),
+                None => ponderer.search(&state_clone),
+            };
+            return Task::perform(
+                async move {
+                    tokio::task::spawn_blocking(move || result.recv().unwrap_or_default())
+                        .await
+                        .unwrap_or_default()
+                },
+                on_result,
+            );
+        }
+
+        let settings = self.ai_settings.clone();
+        let rng = StdRng::from_rng(&mut self.rng);
         Task::perform(
             async move {
                 tokio::task::spawn_blocking(move || {
-                    let mut ai = AiPlayer::new(state_clone.current_player(), depth);
-                    ai.request_move(&state_clone)
+                    let ai = settings.create_ai(state_clone.current_player(), rng);
+                    match move_time {
+                        Some(move_time) => ai.search_with_time_limit(&state_clone, settings.depth, move_time),
+                        None => ai.search(&state_clone),
+                    }
                 })
                 .await
-                .ok()
-                .flatten()
+                .unwrap_or_default()
             },
-            Message::AiMoveComputed,
+            on_result,
         )
     }
 
+    /// Creates the background AI for a new game if pondering is enabled and exactly one player is the AI.
+    fn create_ponderer(&mut self) -> Option<Ponderer> {
+        if !self.ai_settings.ponder {
+            return None;
+        }
+        let ai_color = match (&self.black_player_type, &self.white_player_type) {
+            (PlayerType::Ai, PlayerType::Human) => PieceColor::Black,
+            (PlayerType::Human, PlayerType::Ai) => PieceColor::White,
+            _ => return None,
+        };
+        let settings = self.ai_settings.clone();
+        let rng = StdRng::from_rng(&mut self.rng);
+        Some(Ponderer::spawn(move || settings.create_ai(ai_color, rng)))
+    }
+
+    /// Lets the AI ponder the position while the human decides, unless it is already pondering it.
+    fn maybe_start_pondering(&mut self) {
+        let (Some(ponderer), Some(state)) = (&self.ponderer, &self.game_state) else {
+            return;
+        };
+        if matches!(state.current_phase(), GamePhase::GameOver { .. }) || self.pondering == Some(state.fingerprint()) {
+            return;
+        }
+        ponderer.ponder(state);
+        self.pondering = Some(state.fingerprint());
+    }
+
+    fn stop_pondering(&mut self) {
+        if let Some(ref ponderer) = self.ponderer {
+            ponderer.stop();
+        }
+        self.pondering = None;
+    }
+
+    /// Keeps the report for the debug panel and appends it to the search log, if there is one. If the log can't be
+    /// written, the status bar says so and no more reports are logged.
+    fn record_search_report(&mut self, report: SearchReport) {
+        if !self.search_log_failed
+            && let Err(error) = self.write_search_log(&report)
+        {
+            self.search_log_failed = true;
+            self.status_message = format!("{} - Search reports are no longer logged: {}", self.status_message, error);
+        }
+        self.last_search_report = Some(report);
+    }
+
+    fn write_search_log(&mut self, report: &SearchReport) -> Result<(), String> {
+        let Some(ref path) = self.ai_settings.search_log else {
+            return Ok(());
+        };
+        let line = serde_json::to_string(report).map_err(|err| format!("Failed to serialize search report: {}", err))?;
+        if self.search_log.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|err| format!("Failed to open {}: {}", path, err))?;
+            self.search_log = Some(file);
+        }
+        let file = self.search_log.as_mut().expect("opened above");
+        writeln!(file, "{}", line).map_err(|err| format!("Failed to write {}: {}", path, err))
+    }
+
     fn handle_ai_move(&mut self, maybe_move: Option<PlayerMove>) -> Task<Message> {
         self.ai_computing = false;
+        // The AI ran out of time before it could move
+        if self.lost_on_time.is_some() {
+            return Task::none();
+        }
 
         let Some(player_move) = maybe_move else {
             self.update_status();
@@ -488,14 +1501,10 @@ impl KonaneApp {
             }
         }
 
+        self.press_clock();
         self.update_status();
 
-        // Check for game over
-        if let Some(ref state) = self.game_state
-            && let GamePhase::GameOver { winner } = state.current_phase()
-        {
-            self.game_over_view = Some(GameOverView::new(winner, self.move_history.clone(), state.board().size()));
-            self.view = AppView::GameOver;
+        if self.show_game_over_if_finished() {
             return Task::none();
         }
 
@@ -530,8 +1539,11 @@ impl KonaneApp {
             return text("No game in progress").into();
         };
 
-        // Status bar
-        let status = text(&self.status_message).size(20);
+        // Status bar, with the clocks beside it in a timed game
+        let status = row![text(&self.status_message).size(20)]
+            .push(self.clock.as_ref().map(|clock| self.clock_view(clock)))
+            .spacing(30)
+            .align_y(Alignment::Center);
 
         // Undo/Redo buttons
         let undo_btn = button(text("Undo").size(14));
@@ -551,7 +1563,20 @@ impl KonaneApp {
         // Current player indicator
         let player_indicator = row![text("Current: ").size(16), text(state.current_player().to_string()).size(16),].spacing(5);
 
-        let info_bar = row![undo_btn, redo_btn, player_indicator]
+        let debug_btn = button(text(if self.show_debug_panel { "Hide Debug" } else { "Debug" }).size(14))
+            .on_press(Message::ToggleDebugPanel);
+
+        let save_btn = button(text("Save").size(14)).on_press(Message::ShowSessionModal(SessionAction::Save));
+
+        // Loading while the AI is thinking would apply its move to the loaded game
+        let load_btn = button(text("Load").size(14));
+        let load_btn = if self.ai_computing {
+            load_btn
+        } else {
+            load_btn.on_press(Message::ShowSessionModal(SessionAction::Load))
+        };
+
+        let info_bar = row![undo_btn, redo_btn, player_indicator, save_btn, load_btn, debug_btn]
             .spacing(15)
             .align_y(Alignment::Center);
 
@@ -569,13 +1594,229 @@ impl KonaneApp {
             .height(Length::Fill)
             .padding(10);
 
-        let board_row = row![board, move_panel].spacing(0);
+        let mut board_row = row![board, move_panel].spacing(0);
+        if self.show_debug_panel {
+            board_row = board_row.push(self.debug_panel());
+        }
 
         let content = column![status, info_bar, board_row]
             .spacing(10)
             .padding(20)
             .align_x(Alignment::Center);
 
-        container(content).width(Length::Fill).height(Length::Fill).into()
+        let main_view = container(content).width(Length::Fill).height(Length::Fill);
+
+        if let Some(ref modal) = self.session_modal {
+            stack![main_view, self.session_modal_view(modal)].into()
+        } else {
+            main_view.into()
+        }
+    }
+
+    /// Both players' clocks. The running clock is highlighted, and shown in red when under ten seconds.
+    fn clock_view(&self, clock: &GameClock) -> Element<'_, Message> {
+        let now = Instant::now();
+        let clock_text = |color: PieceColor| {
+            let remaining = clock.remaining(color, now);
+            let style = if clock.running() != Some(color) {
+                text::default
+            } else if remaining < Duration::from_secs(10) {
+                text::danger
+            } else {
+                text::primary
+            };
+            text(format!("{} {}", color, format_clock(remaining))).size(20).style(style)
+        };
+        row![clock_text(PieceColor::Black), clock_text(PieceColor::White)].spacing(20).into()
+    }
+
+    fn session_modal_view<'a>(&self, modal: &'a SessionModal) -> Element<'a, Message> {
+        let (title, confirm) = match modal.action {
+            SessionAction::Save => ("Save Game", "Save"),
+            SessionAction::Load => ("Load Game", "Load"),
+        };
+
+        let path_input = text_input("Enter file path...", &modal.path)
+            .on_input(Message::SessionPathChanged)
+            .on_submit(Message::ConfirmSession)
+            .padding(10)
+            .width(Length::Fixed(300.0));
+
+        let confirm_btn = button(text(confirm).size(16)).padding(10).on_press(Message::ConfirmSession);
+        let cancel_btn = button(text("Cancel").size(16)).padding(10).on_press(Message::CancelSession);
+
+        let mut modal_content = column![text(title).size(24), path_input];
+        if let Some(ref error) = modal.error {
+            modal_content = modal_content.push(text(format!("Error: {}", error)));
+        }
+        let modal_content = modal_content
+            .push(row![cancel_btn, confirm_btn].spacing(10))
+            .spacing(15)
+            .align_x(Alignment::Center);
+
+        let popup = container(modal_content)
+            .width(Length::Fixed(400.0))
+            .padding(30)
+            .style(popup_style);
+
+        container(popup)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .style(backdrop_style)
+            .into()
+    }
+
+    fn debug_panel(&self) -> Element<'_, Message> {
+        let mut panel = column![text("Search").size(16)].spacing(4);
+
+        match self.last_search_report {
+            Some(ref report) if report.from_book => {
+                let best_move = report.best_move.as_deref().unwrap_or("-");
+                panel = panel.push(text(format!("Book move: {}", best_move)).size(14));
+            }
+            Some(ref report) => {
+                panel = panel
+                    .push(text(format!("Depth: {}", report.depth_reached)).size(14))
+                    .push(text(format!("Nodes: {}", report.nodes)).size(14))
+                    .push(text(format!("TT hits: {}", report.tt_hits)).size(14))
+                    .push(text(format!("Cutoffs: {}", report.cutoffs)).size(14))
+                    .push(text(format!("EBF: {:.2}", report.effective_branching_factor)).size(14))
+                    .push(text(format!("Time: {} ms", report.elapsed_ms)).size(14))
+                    .push(text("Iterations:").size(14));
+                for iteration in &report.iterations {
+                    let best_move = iteration.best_move.as_deref().unwrap_or("-");
+                    panel = panel.push(
+                        text(format!("{}: {} ({} nodes)", iteration.depth, best_move, iteration.nodes)).size(12),
+                    );
+                }
+            }
+            None => {
+                panel = panel.push(text("No search yet").size(14));
+            }
+        }
+
+        container(scrollable(panel).height(Length::Fill).width(Length::Fill))
+            .width(Length::Fixed(200.0))
+            .height(Length::Fill)
+            .padding(10)
+            .into()
+    }
+}
+
+/// Replaces an external engine with the built-in AI.
+fn without_engine(player_type: PlayerType) -> PlayerType {
+    match player_type {
+        PlayerType::External(_) => PlayerType::Ai,
+        player_type => player_type,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::setup_view::{ColorOption, PlayerChoice};
+
+    #[test]
+    fn clock_starts_for_the_player_to_move_when_white_is_picked() {
+        let (mut app, _) = KonaneApp::new(AiSettings::default(), Some(1), None);
+        app.setup.color_option = ColorOption::White;
+        app.setup.time_control = "5+3".to_string();
+
+        let _ = app.update(Message::Setup(SetupMessage::StartGame));
+
+        let first_player = app.game_state.as_ref().unwrap().current_player();
+        assert_eq!(app.clock.as_ref().unwrap().running(), Some(first_player));
+    }
+
+    #[test]
+    fn timed_sessions_resume_with_their_clocks() {
+        let (mut app, _) = KonaneApp::new(AiSettings::default(), Some(1), None);
+        app.setup.time_control = "5+3".to_string();
+        let _ = app.update(Message::Setup(SetupMessage::StartGame));
+        let session = Session::from_json(&app.current_session().unwrap().to_json().unwrap()).unwrap();
+
+        let (mut resumed, _) = KonaneApp::new(AiSettings::default(), Some(1), None);
+        let _ = resumed.start_loaded_game(
+            session.state,
+            session.move_history,
+            session.undo_stack,
+            session.redo_stack,
+            session.clock,
+        );
+
+        let clock = resumed.clock.as_ref().unwrap();
+        assert_eq!(clock.control(), "5+3".parse().unwrap());
+        assert_eq!(clock.running(), Some(resumed.game_state.as_ref().unwrap().current_player()));
+        assert!(clock.remaining(PieceColor::Black, Instant::now()) <= Duration::from_secs(300));
+    }
+
+    #[test]
+    fn correspondence_games_are_not_autosaved() {
+        let dir = std::env::temp_dir().join(format!("konane-autosave-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let autosave_path = dir.join("autosave.json");
+        let (mut app, _) = KonaneApp::new(AiSettings::default(), Some(1), Some(autosave_path.clone()));
+        let game = CorrespondenceGame::new("Alice", "Bob", 4).unwrap();
+        let _ = app.start_correspondence(dir.join("game.json").to_str().unwrap(), game).unwrap();
+
+        app.autosave();
+
+        assert!(app.game_state.is_some());
+        assert!(!autosave_path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn correspondence_move_is_taken_back_when_the_file_cannot_be_written() {
+        let (mut app, _) = KonaneApp::new(AiSettings::default(), Some(1), None);
+        let game = CorrespondenceGame::new("Alice", "Bob", 4).unwrap();
+        let missing = std::env::temp_dir().join("konane-missing-dir").join("game.json");
+        let _ = app.start_correspondence(missing.to_str().unwrap(), game).unwrap();
+        let removal = Rules::valid_black_opening_removals(app.game_state.as_ref().unwrap())[0];
+
+        let _ = app.update(Message::Board(BoardMessage::CellClicked(removal)));
+
+        assert!(app.move_history.is_empty());
+        assert!(app.correspondence.as_ref().unwrap().game.move_history().is_empty());
+        assert!(app.status_message.starts_with("Could not save the move"));
+    }
+
+    #[test]
+    fn search_log_failure_is_reported_once() {
+        let missing = std::env::temp_dir().join("konane-missing-dir").join("search.jsonl");
+        let ai_settings = AiSettings {
+            search_log: Some(missing.to_string_lossy().into_owned()),
+            ..AiSettings::default()
+        };
+        let (mut app, _) = KonaneApp::new(ai_settings, Some(1), None);
+
+        app.record_search_report(SearchReport::default());
+        assert!(app.status_message.contains("Search reports are no longer logged"), "{}", app.status_message);
+        app.status_message.clear();
+        app.record_search_report(SearchReport::default());
+
+        assert!(app.search_log_failed);
+        assert!(app.status_message.is_empty());
+        assert!(app.last_search_report.is_some());
+    }
+
+    #[test]
+    fn engines_start_in_the_background() {
+        let (mut app, _) = KonaneApp::new(AiSettings::default(), Some(1), None);
+        app.setup.white_player_type = PlayerChoice::External;
+        app.setup.white_engine = "konane engine".to_string();
+
+        let _ = app.update(Message::Setup(SetupMessage::StartGame));
+
+        assert!(app.setup.starting_engines);
+        assert!(app.game_state.is_none());
+
+        let _ = app.update(Message::EnginesStarted(Err("Failed to start engine konane".to_string())));
+
+        assert!(!app.setup.starting_engines);
+        assert!(app.game_state.is_none());
+        assert_eq!(app.setup.start_error.as_deref(), Some("Failed to start engine konane"));
     }
 }
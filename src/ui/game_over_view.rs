@@ -2,11 +2,14 @@ use iced::widget::{button, column, container, row, text, text_input};
 use iced::{Alignment, Background, Border, Color, Element, Length, Shadow, Theme};
 
 use crate::game::{MoveHistory, PieceColor};
+use konane::export::{self, ClockLog};
+use konane::pgn::PgnGame;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExportFormat {
     Text,
     Json,
+    Pgn,
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +25,10 @@ pub struct GameOverView {
     pub winner: PieceColor,
     pub move_history: MoveHistory,
     pub board_size: usize,
+    pub black_player: String,
+    pub white_player: String,
+    /// The clocks, for a game played with a time control
+    pub clock: Option<ClockLog>,
     pub show_export_modal: bool,
     pub export_path: String,
     pub export_format: Option<ExportFormat>,
@@ -33,51 +40,65 @@ impl GameOverView {
             winner,
             move_history,
             board_size,
+            black_player: "?".to_string(),
+            white_player: "?".to_string(),
+            clock: None,
             show_export_modal: false,
             export_path: String::new(),
             export_format: None,
         }
     }
 
-    pub fn generate_text_log(&self) -> String {
-        let mut log = String::new();
-
-        for (i, move_record) in self.move_history.iter().enumerate() {
-            log.push_str(&format!("{}. {}\n", i + 1, move_record.to_algebraic()));
-        }
+    /// Names the players in game records.
+    pub fn with_players(mut self, black: String, white: String) -> Self {
+        self.black_player = black;
+        self.white_player = white;
+        self
+    }
 
-        let result_code = match self.winner {
-            PieceColor::Black => "1-0",
-            PieceColor::White => "0-1",
-        };
-        log.push_str(result_code);
-        log.push('\n');
+    /// Records the clocks of a game played with a time control.
+    pub fn with_clock(mut self, clock: ClockLog) -> Self {
+        self.clock = Some(clock);
+        self
+    }
 
-        log
+    fn lost_on_time(&self) -> bool {
+        self.clock.as_ref().is_some_and(|clock| clock.lost_on_time.is_some())
     }
 
-    pub fn generate_json_log(&self) -> String {
-        #[derive(serde::Serialize)]
-        struct GameLog<'a> {
-            board_size: usize,
-            winner: String,
-            total_moves: usize,
-            moves: &'a MoveHistory,
+    pub fn generate_pgn_log(&self) -> String {
+        let mut game = PgnGame::new(self.board_size, &self.move_history, Some(self.winner));
+        game.set_tag("Event", "Kōnane game");
+        game.set_tag("Date", &chrono::Local::now().format("%Y.%m.%d").to_string());
+        game.set_tag("Black", &self.black_player);
+        game.set_tag("White", &self.white_player);
+        if let Some(ref clock) = self.clock {
+            game.set_clock(clock);
         }
+        game.to_pgn()
+    }
 
-        let log = GameLog {
-            board_size: self.board_size,
-            winner: self.winner.to_string(),
-            total_moves: self.move_history.len(),
-            moves: &self.move_history,
-        };
+    /// The text format cannot say that a game was lost on time, so such a game is written as unfinished.
+    pub fn generate_text_log(&self) -> String {
+        let winner = (!self.lost_on_time()).then_some(self.winner);
+        export::game_to_text(self.board_size, winner, &self.move_history)
+    }
 
-        serde_json::to_string_pretty(&log).unwrap_or_else(|_| "Error generating JSON".to_string())
+    pub fn generate_json_log(&self) -> String {
+        let json = match self.clock {
+            Some(ref clock) => export::timed_game_to_json(self.board_size, Some(self.winner), &self.move_history, clock),
+            None => export::game_to_json(self.board_size, Some(self.winner), &self.move_history),
+        };
+        json.unwrap_or_else(|_| "Error generating JSON".to_string())
     }
 
     pub fn view(&self) -> Element<'_, GameOverMessage> {
         let title = text("Game Over!").size(36);
-        let winner_text = text(format!("{} wins!", self.winner)).size(28);
+        let winner_text = if self.lost_on_time() {
+            text(format!("{} wins on time!", self.winner)).size(28)
+        } else {
+            text(format!("{} wins!", self.winner)).size(28)
+        };
         let moves_text = text(format!("Total moves: {}", self.move_history.len())).size(18);
         let download_label = text("Download game log:").size(16);
         let text_button = button(text("Text").size(16))
@@ -86,7 +107,10 @@ impl GameOverView {
         let json_button = button(text("JSON").size(16))
             .padding(10)
             .on_press(GameOverMessage::ShowExportModal(ExportFormat::Json));
-        let download_row = row![download_label, text_button, json_button]
+        let pgn_button = button(text("PGN").size(16))
+            .padding(10)
+            .on_press(GameOverMessage::ShowExportModal(ExportFormat::Pgn));
+        let download_row = row![download_label, text_button, json_button, pgn_button]
             .spacing(10)
             .align_y(Alignment::Center);
         let dismiss_button = button(text("New Game").size(18))
@@ -128,6 +152,7 @@ impl GameOverView {
         let format_name = match self.export_format {
             Some(ExportFormat::Text) => "Text",
             Some(ExportFormat::Json) => "JSON",
+            Some(ExportFormat::Pgn) => "PGN",
             None => "File",
         };
         let title = text(format!("Export {}", format_name)).size(24);
@@ -135,6 +160,7 @@ impl GameOverView {
         let default_name = match self.export_format {
             Some(ExportFormat::Text) => "konane_game.txt",
             Some(ExportFormat::Json) => "konane_game.json",
+            Some(ExportFormat::Pgn) => "konane_game.pgn",
             None => "konane_game.txt",
         };
         let placeholder = format!("Enter file path (e.g., {})", default_name);
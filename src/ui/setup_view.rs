@@ -2,27 +2,38 @@ use iced::widget::{button, column, container, pick_list, radio, row, text, text_
 use iced::{Alignment, Background, Border, Color, Element, Length, Shadow, Theme};
 use rand::Rng;
 
-use crate::game::PieceColor;
+use crate::game::{PieceColor, TimeControl};
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum SetupMessage {
     BoardSizeSelected(usize),
     ColorOptionSelected(ColorOption),
-    BlackPlayerTypeSelected(PlayerType),
-    WhitePlayerTypeSelected(PlayerType),
+    BlackPlayerTypeSelected(PlayerChoice),
+    WhitePlayerTypeSelected(PlayerChoice),
+    BlackEngineChanged(String),
+    WhiteEngineChanged(String),
+    TimeControlChanged(String),
     StartGame,
     ImportGame,
     ShowImportModal,
     ImportPathChanged(String),
     CancelImport,
+    ImportPartial,
+    ResumeAutosave,
+    NetworkAddressChanged(String),
+    JoinNetworkGame,
+    WatchNetworkGame,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-pub enum PlayerType {
-    #[default]
+pub use konane::session::PlayerType;
+
+/// The kind of player picked for a side. An external engine's command is entered separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerChoice {
     Human,
     Ai,
+    External,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -44,12 +55,12 @@ impl std::fmt::Display for ColorOption {
 }
 
 impl ColorOption {
-    pub fn to_piece_color(self) -> PieceColor {
+    pub fn to_piece_color<R: Rng + ?Sized>(self, rng: &mut R) -> PieceColor {
         match self {
             ColorOption::Black => PieceColor::Black,
             ColorOption::White => PieceColor::White,
             ColorOption::Random => {
-                if rand::rng().random_bool(0.5) {
+                if rng.random_bool(0.5) {
                     PieceColor::Black
                 } else {
                     PieceColor::White
@@ -62,11 +73,26 @@ impl ColorOption {
 pub struct SetupView {
     pub board_size: usize,
     pub color_option: ColorOption,
-    pub black_player_type: PlayerType,
-    pub white_player_type: PlayerType,
+    pub black_player_type: PlayerChoice,
+    pub white_player_type: PlayerChoice,
+    /// Commands that start the external engines
+    pub black_engine: String,
+    pub white_engine: String,
+    /// Time control for the game, such as 5+3 or 30s, or empty for an untimed game
+    pub time_control: String,
+    /// Why the last game could not be started, such as an engine that failed to launch
+    pub start_error: Option<String>,
+    /// Whether the external engines for a new game are being started
+    pub starting_engines: bool,
     pub show_import_modal: bool,
     pub import_path: String,
     pub import_error: Option<String>,
+    /// Moves that can be loaded from a game that failed to import, and the number of moves in it
+    pub partial_import: Option<(usize, usize)>,
+    /// Whether an autosaved game is waiting to be resumed
+    pub autosave_available: bool,
+    /// Address of the game server to join, such as 192.168.1.5:7777
+    pub network_address: String,
 }
 
 impl Default for SetupView {
@@ -74,11 +100,19 @@ impl Default for SetupView {
         Self {
             board_size: 8,
             color_option: ColorOption::Black,
-            black_player_type: PlayerType::Human,
-            white_player_type: PlayerType::Ai,
+            black_player_type: PlayerChoice::Human,
+            white_player_type: PlayerChoice::Ai,
+            black_engine: String::new(),
+            white_engine: String::new(),
+            time_control: String::new(),
+            start_error: None,
+            starting_engines: false,
             show_import_modal: false,
             import_path: String::new(),
             import_error: None,
+            partial_import: None,
+            autosave_available: false,
+            network_address: "127.0.0.1:7777".to_string(),
         }
     }
 }
@@ -96,50 +130,66 @@ impl SetupView {
 
         let size_row = row![size_label, size_picker].spacing(10).align_y(Alignment::Center);
 
-        // Black player type selector
-        let black_player_label = text("Black Player:").size(18);
-        let black_human_radio = radio(
-            "Human",
-            PlayerType::Human,
-            Some(self.black_player_type),
-            SetupMessage::BlackPlayerTypeSelected,
-        );
-        let black_ai_radio = radio(
-            "AI",
-            PlayerType::Ai,
-            Some(self.black_player_type),
+        let black_player_column = Self::player_selector(
+            "Black Player:",
+            self.black_player_type,
+            &self.black_engine,
             SetupMessage::BlackPlayerTypeSelected,
+            SetupMessage::BlackEngineChanged,
         );
-        let black_player_row = row![black_human_radio, black_ai_radio].spacing(20);
-        let black_player_column = column![black_player_label, black_player_row].spacing(8);
-
-        // White player type selector
-        let white_player_label = text("White Player:").size(18);
-        let white_human_radio = radio(
-            "Human",
-            PlayerType::Human,
-            Some(self.white_player_type),
+        let white_player_column = Self::player_selector(
+            "White Player:",
+            self.white_player_type,
+            &self.white_engine,
             SetupMessage::WhitePlayerTypeSelected,
+            SetupMessage::WhiteEngineChanged,
         );
-        let white_ai_radio = radio(
-            "AI",
-            PlayerType::Ai,
-            Some(self.white_player_type),
-            SetupMessage::WhitePlayerTypeSelected,
-        );
-        let white_player_row = row![white_human_radio, white_ai_radio].spacing(20);
-        let white_player_column = column![white_player_label, white_player_row].spacing(8);
 
-        // Start button
-        let start_button = button(text("Start Game").size(20))
+        // Clocks: minutes plus seconds a move, or seconds a move
+        let time_control_row = row![
+            text("Time Control:").size(18),
+            text_input("Untimed, 5+3 or 30s", &self.time_control)
+                .on_input(SetupMessage::TimeControlChanged)
+                .on_submit(SetupMessage::StartGame)
+                .padding(8)
+                .width(Length::Fixed(180.0)),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
+        // Start button, disabled while the engines for the game start
+        let start_label = if self.starting_engines { "Starting Engines..." } else { "Start Game" };
+        let start_button = button(text(start_label).size(20))
             .padding(15)
-            .on_press(SetupMessage::StartGame);
+            .on_press_maybe((!self.starting_engines).then_some(SetupMessage::StartGame));
 
         // Import button
         let import_button = button(text("Import Game").size(16))
             .padding(10)
             .on_press(SetupMessage::ShowImportModal);
 
+        // Resume button, shown when an unfinished game was autosaved
+        let resume_button = self.autosave_available.then(|| {
+            button(text("Resume Last Game").size(16))
+                .padding(10)
+                .on_press(SetupMessage::ResumeAutosave)
+        });
+
+        // Network game, joined or watched at a server started with `konane serve`
+        let network_row = row![
+            text_input("Server address", &self.network_address)
+                .on_input(SetupMessage::NetworkAddressChanged)
+                .on_submit(SetupMessage::JoinNetworkGame)
+                .padding(10)
+                .width(Length::Fixed(200.0)),
+            button(text("Join Network Game").size(16))
+                .padding(10)
+                .on_press(SetupMessage::JoinNetworkGame),
+            button(text("Watch").size(16)).padding(10).on_press(SetupMessage::WatchNetworkGame),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center);
+
         // Layout
         let content = column![
             title,
@@ -150,11 +200,16 @@ impl SetupView {
             black_player_column,
             text("").height(Length::Fixed(10.0)),
             white_player_column,
-            text("").height(Length::Fixed(30.0)),
-            start_button,
             text("").height(Length::Fixed(10.0)),
-            import_button,
+            time_control_row,
+            text("").height(Length::Fixed(20.0)),
+            start_button,
         ]
+        .push(self.start_error.as_ref().map(|error| text(format!("Error: {}", error))))
+        .push(text("").height(Length::Fixed(10.0)))
+        .push(import_button)
+        .push(resume_button)
+        .push(network_row)
         .spacing(10)
         .align_x(Alignment::Center);
 
@@ -172,6 +227,52 @@ impl SetupView {
         }
     }
 
+    /// Radio buttons for one side, with the engine command when an external engine is chosen.
+    fn player_selector<'a>(
+        label: &'a str,
+        choice: PlayerChoice,
+        engine: &'a str,
+        on_select: fn(PlayerChoice) -> SetupMessage,
+        on_engine: fn(String) -> SetupMessage,
+    ) -> Element<'a, SetupMessage> {
+        let radios = row![
+            radio("Human", PlayerChoice::Human, Some(choice), on_select),
+            radio("AI", PlayerChoice::Ai, Some(choice), on_select),
+            radio("External engine", PlayerChoice::External, Some(choice), on_select),
+        ]
+        .spacing(20);
+        let engine_input = (choice == PlayerChoice::External).then(|| {
+            text_input("Engine command, such as konane engine", engine)
+                .on_input(on_engine)
+                .padding(8)
+                .width(Length::Fixed(300.0))
+        });
+        column![text(label).size(18), radios].push(engine_input).spacing(8).into()
+    }
+
+    /// The player chosen for a side. Fails if an external engine is chosen without a command.
+    pub fn player_type(&self, color: PieceColor) -> Result<PlayerType, String> {
+        let (choice, engine) = match color {
+            PieceColor::Black => (self.black_player_type, &self.black_engine),
+            PieceColor::White => (self.white_player_type, &self.white_engine),
+        };
+        match choice {
+            PlayerChoice::Human => Ok(PlayerType::Human),
+            PlayerChoice::Ai => Ok(PlayerType::Ai),
+            PlayerChoice::External if engine.trim().is_empty() => Err(format!("Enter the command for {}'s engine", color)),
+            PlayerChoice::External => Ok(PlayerType::External(engine.trim().to_string())),
+        }
+    }
+
+    /// The time control entered, or `None` for an untimed game. Fails if it cannot be read.
+    pub fn time_control(&self) -> Result<Option<TimeControl>, String> {
+        let text = self.time_control.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        text.parse().map(Some)
+    }
+
     fn import_modal_view(&self) -> Element<'_, SetupMessage> {
         let title = text("Import Game").size(24);
 
@@ -187,7 +288,15 @@ impl SetupView {
             .padding(10)
             .on_press(SetupMessage::CancelImport);
 
-        let buttons = row![cancel_btn, import_btn].spacing(10);
+        let mut buttons = row![cancel_btn, import_btn].spacing(10);
+        if let Some((loadable, total)) = self.partial_import {
+            let label = if loadable < total {
+                format!("Load First {} of {} Moves", loadable, total)
+            } else {
+                "Load Anyway".to_string()
+            };
+            buttons = buttons.push(button(text(label).size(16)).padding(10).on_press(SetupMessage::ImportPartial));
+        }
         let mut modal_content = column![title, path_input];
 
         if let Some(error) = &self.import_error {
@@ -211,14 +320,14 @@ impl SetupView {
     }
 }
 
-fn backdrop_style(_theme: &Theme) -> container::Style {
+pub fn backdrop_style(_theme: &Theme) -> container::Style {
     container::Style {
         background: Some(Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.6))),
         ..Default::default()
     }
 }
 
-fn popup_style(theme: &Theme) -> container::Style {
+pub fn popup_style(theme: &Theme) -> container::Style {
     let palette = theme.extended_palette();
     container::Style {
         background: Some(Background::Color(palette.background.base.color)),
@@ -0,0 +1,137 @@
+use std::time::{Duration, Instant};
+
+use konane::game::player::{ExternalEnginePlayer, Player, PlayerMove};
+use konane::game::{GamePhase, GameState, PieceColor, Position, Rules};
+
+const MOCK_ENGINE: &str = env!("CARGO_BIN_EXE_mock_engine");
+
+fn mock_engine(color: PieceColor, options: &str) -> ExternalEnginePlayer {
+    let command = format!("{} {}", MOCK_ENGINE, options);
+    ExternalEnginePlayer::spawn_with_timeout(color, &command, Duration::from_secs(2)).unwrap()
+}
+
+/// The 4x4 game after b2 and c2, with Black to jump.
+fn opened_game() -> GameState {
+    let mut state = GameState::new(4, PieceColor::Black);
+    Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap();
+    Rules::apply_opening_removal(&mut state, Position::new(1, 2)).unwrap();
+    state
+}
+
+#[test]
+fn external_engine_reads_its_name_from_the_handshake() {
+    let engine = mock_engine(PieceColor::Black, "");
+
+    assert_eq!(engine.name(), "mock engine");
+    assert!(engine.is_ready());
+}
+
+#[test]
+fn external_engine_plays_removals_and_jumps() {
+    let mut black = mock_engine(PieceColor::Black, "");
+    let mut state = GameState::new(4, PieceColor::Black);
+
+    let removal = black.choose_move(&state).unwrap();
+    assert!(matches!(removal, PlayerMove::OpeningRemoval(position) if position == Position::new(1, 1)));
+
+    state = opened_game();
+    let jump = black.request_move(&state).unwrap();
+    assert!(matches!(jump, PlayerMove::Jump(_)));
+}
+
+#[test]
+fn external_engines_play_a_whole_game() {
+    let mut black = mock_engine(PieceColor::Black, "");
+    let mut white = mock_engine(PieceColor::White, "");
+    let mut state = GameState::new(6, PieceColor::Black);
+
+    while !matches!(state.current_phase(), GamePhase::GameOver { .. }) {
+        let player: &mut ExternalEnginePlayer = match state.current_player() {
+            PieceColor::Black => &mut black,
+            PieceColor::White => &mut white,
+        };
+        player.choose_move(&state).unwrap().apply(&mut state).unwrap();
+    }
+}
+
+#[test]
+fn external_engine_that_crashes_is_reported() {
+    let mut engine = mock_engine(PieceColor::Black, "--crash");
+
+    let err = engine.choose_move(&opened_game()).unwrap_err();
+
+    assert!(err.contains("exited"), "{}", err);
+    assert!(!engine.is_ready());
+    assert!(engine.choose_move(&opened_game()).unwrap_err().contains("not running"));
+}
+
+#[test]
+fn external_engine_that_hangs_times_out_and_is_killed() {
+    let mut engine = mock_engine(PieceColor::Black, "--hang");
+    let start = Instant::now();
+
+    let err = engine.choose_move(&opened_game()).unwrap_err();
+
+    assert!(err.contains("did not reply"), "{}", err);
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert!(!engine.is_ready());
+}
+
+#[test]
+fn external_engine_illegal_moves_are_rejected() {
+    let mut engine = mock_engine(PieceColor::Black, "--illegal");
+
+    let err = engine.choose_move(&opened_game()).unwrap_err();
+
+    assert!(err.contains("illegal move a1-a1"), "{}", err);
+}
+
+#[test]
+fn external_engine_that_never_answers_the_handshake_fails_to_start() {
+    let command = format!("{} --silent", MOCK_ENGINE);
+
+    let result = ExternalEnginePlayer::spawn_with_timeout(PieceColor::Black, &command, Duration::from_millis(200));
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn external_engine_command_can_quote_words() {
+    let command = format!("'{}' \"--illegal\"", MOCK_ENGINE);
+    let mut engine =
+        ExternalEnginePlayer::spawn_with_timeout(PieceColor::Black, &command, Duration::from_secs(2)).unwrap();
+
+    let err = engine.choose_move(&opened_game()).unwrap_err();
+
+    assert!(err.contains("illegal move a1-a1"), "{}", err);
+}
+
+#[test]
+fn dropping_an_external_engine_does_not_wait_for_it_to_exit() {
+    let engine = mock_engine(PieceColor::Black, "--ignore-quit");
+    let start = Instant::now();
+
+    drop(engine);
+
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
+
+#[test]
+fn external_engine_that_does_not_exist_fails_to_start() {
+    let result = ExternalEnginePlayer::spawn(PieceColor::Black, "/nonexistent/engine");
+
+    assert!(matches!(result, Err(err) if err.starts_with("Failed to start engine")));
+}
+
+#[test]
+fn external_engine_can_be_the_konane_engine() {
+    let command = format!("{} --ai-depth 1 engine", env!("CARGO_BIN_EXE_konane"));
+    let mut engine =
+        ExternalEnginePlayer::spawn_with_timeout(PieceColor::White, &command, Duration::from_secs(10)).unwrap();
+    let mut state = GameState::new(4, PieceColor::Black);
+    Rules::apply_opening_removal(&mut state, Position::new(1, 1)).unwrap();
+
+    let player_move = engine.choose_move(&state).unwrap();
+
+    assert!(player_move.apply(&mut state).is_ok());
+}
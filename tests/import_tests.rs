@@ -1,4 +1,8 @@
-use konane::import::import_game_from_content;
+use konane::archive::{ArchiveFormat, ArchiveReader, ArchiveWriter};
+use konane::import::{
+    import_game_from_content, import_game_from_path, import_pgn_game_from_content, import_text_game_from_content,
+};
+use konane::pgn::PgnGame;
 
 #[test]
 fn import_accepts_valid_opening_sequence() {
@@ -43,3 +47,71 @@ fn import_rejects_winner_without_game_over() {
     let result = import_game_from_content(json);
     assert!(result.is_err());
 }
+
+#[test]
+fn text_import_matches_json_import() {
+    let json = r#"{
+        "board_size": 4,
+        "moves": [
+            {"OpeningRemoval": {"color": "Black", "position": {"row": 1, "col": 1}}},
+            {"OpeningRemoval": {"color": "White", "position": {"row": 1, "col": 2}}},
+            {"Jump": {"color": "Black", "from": {"row": 3, "col": 1}, "to": {"row": 1, "col": 1}, "captured": [{"row": 2, "col": 1}]}}
+        ]
+    }"#;
+    let text = "Board size: 4\n1. b2\n2. c2\n3. b4-b2\n";
+
+    let (json_state, json_history, _) = import_game_from_content(json).unwrap();
+    let (text_state, text_history, _) = import_text_game_from_content(text).unwrap();
+    assert_eq!(json_state.fingerprint(), text_state.fingerprint());
+    assert_eq!(json_history.len(), text_history.len());
+}
+
+#[test]
+fn import_from_path_detects_text_format() {
+    let path = std::env::temp_dir().join(format!("konane-import-{}.txt", std::process::id()));
+    std::fs::write(&path, "Board size: 4\n1. b2\n2. c2\n").unwrap();
+
+    let result = import_game_from_path(path.to_str().unwrap());
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(result.map(|(_, history, _)| history.len()), Ok(2));
+}
+
+#[test]
+fn pgn_import_round_trips_text_import() {
+    let text = "Board size: 4\n1. b2\n2. c2\n3. b4-b2\n";
+    let (_, history, _) = import_text_game_from_content(text).unwrap();
+
+    let pgn = PgnGame::new(4, &history, None).to_pgn();
+    let (_, pgn_history, undo_stack) = import_pgn_game_from_content(&pgn).unwrap();
+    assert_eq!(pgn_history, history);
+    assert_eq!(undo_stack.len(), 3);
+}
+
+#[test]
+fn archive_file_detects_format_from_content() {
+    let (_, history, _) = import_text_game_from_content("Board size: 4\n1. b2\n2. c2\n").unwrap();
+    let path = std::env::temp_dir().join(format!("konane-archive-{}.games", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let mut writer = ArchiveWriter::create(path, ArchiveFormat::Pgn).unwrap();
+    writer.write_game(4, &history, None).unwrap();
+    writer.write_game(4, &history, None).unwrap();
+    writer.finish().unwrap();
+
+    let games: Vec<_> = ArchiveReader::open(path).unwrap().collect();
+    let _ = std::fs::remove_file(path);
+    assert_eq!(games.len(), 2);
+    assert!(games.iter().all(|game| game.as_ref().is_ok_and(|(_, moves)| *moves == history)));
+}
+
+/// The published schema must match the Rust types. Run with `UPDATE_SCHEMA=1` to regenerate it after changing them.
+#[test]
+fn published_schema_is_up_to_date() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/schema/game.schema.json");
+    let schema = format!("{}\n", konane::import::game_schema());
+    if std::env::var_os("UPDATE_SCHEMA").is_some() {
+        std::fs::write(path, &schema).unwrap();
+    }
+    let published = std::fs::read_to_string(path).unwrap_or_default();
+    assert!(published == schema, "{} is out of date; run the tests with UPDATE_SCHEMA=1", path);
+}